@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// One JSON object per line, so the log can be appended to without reading it and grepped easily
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub context: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub user: String,
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+pub async fn append(
+    path: &str,
+    action: &str,
+    context: &str,
+    detail: Option<String>,
+) -> Result<(), BoxError> {
+    let entry = AuditEntry {
+        timestamp: Utc::now(),
+        action: action.to_string(),
+        context: context.to_string(),
+        detail,
+        user: current_user(),
+    };
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+// Lines that can't be parsed (e.g. a torn write) are skipped rather than hiding the whole log
+pub async fn load(path: &str) -> Result<Vec<AuditEntry>, BoxError> {
+    if !Path::new(path).exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(path).await?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
@@ -0,0 +1,639 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use futures::StreamExt;
+use kube::config::Kubeconfig;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::audit;
+use crate::cloud;
+use crate::cluster::{self, FailureKind, LatencyResult, LatencySort};
+use crate::config::KtxConfig;
+use crate::history;
+use crate::kubeconfig::{self, ConflictResolution, EntryKind, Sources};
+use crate::metadata::{self, KtxMetadata, Manifest, Provenance};
+use crate::project;
+use crate::runner::SystemCommandRunner;
+
+type EmptyResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const HEALTH_CHECK_CONCURRENCY: usize = 10;
+const MANIFEST_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const LIST_COLUMNS: [&str; 5] = ["name", "server", "provider", "health", "last-used"];
+
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Csv,
+    Markdown,
+}
+
+async fn context_health(kubeconfig: &Kubeconfig, name: &str, config: &KtxConfig) -> String {
+    if config.offline {
+        return "unknown".to_string();
+    }
+    if config.is_excluded_from_sweeps(name) {
+        return "skipped".to_string();
+    }
+    match tokio::time::timeout(
+        HEALTH_CHECK_TIMEOUT,
+        cluster::health_check(
+            kubeconfig,
+            name,
+            config.ip_family_for(name),
+            &config.health_probe_for(name),
+        ),
+    )
+    .await
+    {
+        Ok(Ok(version)) => format!("healthy ({})", version),
+        Ok(Err(e)) => match FailureKind::classify(&e.to_string()) {
+            // stdout is the table, the fix goes to stderr
+            FailureKind::MissingPlugin => {
+                eprintln!("{}: {}", name, e);
+                "unhealthy (plugin missing)".to_string()
+            }
+            FailureKind::Other => "unhealthy".to_string(),
+            kind => format!("unhealthy ({})", kind.label().to_lowercase()),
+        },
+        Err(_) => "unhealthy (unreachable)".to_string(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn markdown_field(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+fn render_rows(rows: &[Vec<String>], format: OutputFormat) -> String {
+    let mut output = String::new();
+    match format {
+        OutputFormat::Csv => {
+            output.push_str(&LIST_COLUMNS.join(","));
+            output.push('\n');
+            for row in rows {
+                let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+                output.push_str(&fields.join(","));
+                output.push('\n');
+            }
+        }
+        OutputFormat::Markdown => {
+            output.push_str(&format!("| {} |\n", LIST_COLUMNS.join(" | ")));
+            output.push_str(&format!("|{}\n", "---|".repeat(LIST_COLUMNS.len())));
+            for row in rows {
+                let fields: Vec<String> = row.iter().map(|f| markdown_field(f)).collect();
+                output.push_str(&format!("| {} |\n", fields.join(" | ")));
+            }
+        }
+    }
+    output
+}
+
+// Prints the context inventory without starting the UI
+pub async fn list(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    format: OutputFormat,
+) -> EmptyResult {
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let metadata = KtxMetadata::load(metadata_path)?;
+    let health: Vec<String> = futures::stream::iter(kubeconfig.contexts.iter())
+        .map(|context| context_health(&kubeconfig, &context.name, config))
+        .buffered(HEALTH_CHECK_CONCURRENCY)
+        .collect()
+        .await;
+    let rows: Vec<Vec<String>> = kubeconfig
+        .contexts
+        .iter()
+        .zip(health)
+        .map(|(context, health)| {
+            let name = context.name.as_str();
+            let server = kubeconfig::context_cluster(&kubeconfig, name)
+                .and_then(|c| c.cluster.as_ref())
+                .and_then(|c| c.server.clone())
+                .unwrap_or_default();
+            vec![
+                name.to_string(),
+                server,
+                metadata::context_provider(&metadata, &kubeconfig, name).unwrap_or_default(),
+                health,
+                metadata
+                    .last_used(name)
+                    .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print!("{}", render_rows(&rows, format));
+    Ok(())
+}
+
+pub async fn rename(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    old_name: &str,
+    new_name: &str,
+) -> EmptyResult {
+    let (mut kubeconfig, mut sources) =
+        kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?;
+    sources.check_writable(old_name)?;
+    kubeconfig::rename_context(&mut kubeconfig, old_name, new_name)?;
+    sources.rename_context(old_name, new_name);
+    kubeconfig::save_sources(&kubeconfig, &sources, &history::dir(metadata_path)).await?;
+    if let Some(path) = &config.audit_log {
+        audit::append(path, "rename", new_name, Some(format!("from {}", old_name))).await?;
+    }
+    let mut metadata = KtxMetadata::load(metadata_path)?;
+    if metadata.contexts.contains_key(old_name) {
+        metadata.rename(old_name, new_name);
+        metadata.save(metadata_path).await?;
+    }
+    Ok(())
+}
+
+// Writes a standalone kubeconfig for one context, or prints it when no file is given
+pub async fn export(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    name: &str,
+    output: Option<&str>,
+) -> EmptyResult {
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let exported = kubeconfig::extract_context(&kubeconfig, name)
+        .ok_or(format!("Context {} not found", name))?;
+    match output {
+        Some(path) => kubeconfig::save_private(&exported, Path::new(path)).await,
+        None => {
+            print!("{}", serde_yaml::to_string(&exported)?);
+            Ok(())
+        }
+    }
+}
+
+pub async fn export_provenance(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    output: Option<&str>,
+) -> EmptyResult {
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let metadata = KtxMetadata::load(metadata_path)?;
+    let manifest = metadata.manifest(kubeconfig.contexts.iter().map(|c| c.name.as_str()));
+    if manifest.clusters.is_empty() {
+        eprintln!("No imported contexts to export");
+    }
+    let serialized = serde_yaml::to_string(&manifest)?;
+    match output {
+        Some(path) => Ok(tokio::fs::write(path, serialized).await?),
+        None => {
+            print!("{}", serialized);
+            Ok(())
+        }
+    }
+}
+
+pub async fn use_context(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    name: &str,
+    namespace: Option<&str>,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let (mut kubeconfig, sources) =
+        kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?;
+    // Pinning a namespace edits the context itself
+    if namespace.is_some() {
+        sources.check_writable(name)?;
+    }
+    if !kubeconfig::switch_context(&mut kubeconfig, name, namespace)? {
+        return Ok(false);
+    }
+    kubeconfig::save_sources(&kubeconfig, &sources, &history::dir(metadata_path)).await?;
+    if let Some(path) = &config.audit_log {
+        audit::append(
+            path,
+            "switch",
+            name,
+            namespace.map(|ns| format!("namespace {}", ns)),
+        )
+        .await?;
+    }
+    let mut metadata = KtxMetadata::load(metadata_path)?;
+    metadata.context_mut(name).last_used = Some(chrono::Utc::now());
+    metadata.save(metadata_path).await?;
+    Ok(true)
+}
+
+// Switches to the context pinned by the closest .ktx file. Quiet mode is meant for shell hooks,
+// which run in every directory and shouldn't complain outside of projects.
+pub async fn use_project(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    quiet: bool,
+) -> EmptyResult {
+    let cwd = std::env::current_dir()?;
+    let Some(path) = project::find(&cwd) else {
+        if quiet {
+            return Ok(());
+        }
+        return Err(format!(
+            "No {} file in {} or its parents",
+            project::PROJECT_FILE,
+            cwd.display()
+        )
+        .into());
+    };
+    let pin = project::load(&path)?;
+    let switched = use_context(
+        kubeconfig_path,
+        config,
+        metadata_path,
+        &pin.context,
+        pin.namespace.as_deref(),
+    )
+    .await?;
+    if switched && !quiet {
+        println!("Switched to {} from {}", pin.context, path.display());
+    }
+    Ok(())
+}
+
+// Inlines certificate and key files, in place unless an output file is given
+pub async fn flatten(
+    kubeconfig_path: &str,
+    metadata_path: &str,
+    output: Option<&str>,
+) -> EmptyResult {
+    // Only personal files are rewritten, so the shared kubeconfig is left out
+    let (kubeconfig, sources) = kubeconfig::load(kubeconfig_path, None)?;
+    let (flattened, inlined) = kubeconfig::flatten(&kubeconfig)?;
+    match output {
+        Some(path) => kubeconfig::save_private(&flattened, Path::new(path)).await?,
+        None if inlined > 0 => {
+            kubeconfig::save_sources(&flattened, &sources, &history::dir(metadata_path)).await?
+        }
+        None => {}
+    }
+    eprintln!("Inlined {} file(s)", inlined);
+    Ok(())
+}
+
+// Upgrades deprecated auth-providers in the personal kubeconfig after showing what would change
+pub async fn normalize(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+) -> EmptyResult {
+    let (mut kubeconfig, sources) = kubeconfig::load(kubeconfig_path, None)?;
+    let preview = kubeconfig::normalize(&mut kubeconfig, |_| true);
+    if preview.is_empty() {
+        eprintln!("Nothing to normalize");
+        return Ok(());
+    }
+    println!("{}", preview.join("\n"));
+    if config.preview_merges
+        && !confirm(&format!("Rewrite these users in {}?", kubeconfig_path)).await?
+    {
+        return Err("Normalize cancelled".into());
+    }
+    kubeconfig::save_sources(&kubeconfig, &sources, &history::dir(metadata_path)).await?;
+    eprintln!("Upgraded {} user(s)", preview.len());
+    Ok(())
+}
+
+// Merges kubeconfig files into the loaded one, replacing entries with the same name, after showing
+// what would change. The current context is left alone.
+pub async fn merge(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    files: &[&str],
+) -> EmptyResult {
+    let (mut kubeconfig, sources) =
+        kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?;
+    let mut incoming = Kubeconfig::default();
+    for file in files {
+        kubeconfig::merge(
+            &mut incoming,
+            Kubeconfig::read_from(file)?,
+            ConflictResolution::Overwrite,
+        );
+    }
+    incoming.current_context = None;
+    check_incoming_writable(&kubeconfig, &sources, &incoming)?;
+    let preview = kubeconfig::merge_preview(&kubeconfig, &incoming);
+    if preview.is_empty() {
+        eprintln!("Nothing to merge");
+        return Ok(());
+    }
+    println!("{}", preview.join("\n"));
+    if config.preview_merges
+        && !confirm(&format!("Merge these changes into {}?", kubeconfig_path)).await?
+    {
+        return Err("Merge cancelled".into());
+    }
+    let merged = kubeconfig::merge(&mut kubeconfig, incoming, ConflictResolution::Overwrite);
+    kubeconfig::save_sources(&kubeconfig, &sources, &history::dir(metadata_path)).await?;
+    if let Some(path) = &config.audit_log {
+        for name in &merged {
+            audit::append(
+                path,
+                "merge",
+                name,
+                Some(format!("from {}", files.join(", "))),
+            )
+            .await?;
+        }
+    }
+    eprintln!("Merged {} context(s)", merged.len());
+    Ok(())
+}
+
+fn check_incoming_writable(
+    kubeconfig: &Kubeconfig,
+    sources: &Sources,
+    incoming: &Kubeconfig,
+) -> EmptyResult {
+    for context in &incoming.contexts {
+        sources.check_writable(&context.name)?;
+    }
+    for cluster in &incoming.clusters {
+        sources.check_entry_writable(kubeconfig, EntryKind::Cluster, &cluster.name)?;
+    }
+    for user in &incoming.auth_infos {
+        sources.check_entry_writable(kubeconfig, EntryKind::User, &user.name)?;
+    }
+    Ok(())
+}
+
+async fn fetch_manifest(source: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    if !source.starts_with("https://") && !source.starts_with("http://") {
+        return Ok(tokio::fs::read_to_string(source).await?);
+    }
+    let connector = hyper_openssl::HttpsConnector::new()?;
+    let client: hyper::Client<_, hyper::Body> = hyper::Client::builder().build(connector);
+    let response = tokio::time::timeout(MANIFEST_FETCH_TIMEOUT, client.get(source.parse()?))
+        .await
+        .map_err(|_| format!("Fetching {} timed out", source))??;
+    if !response.status().is_success() {
+        return Err(format!("{} responded with {}", source, response.status()).into());
+    }
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    Ok(String::from_utf8(body.to_vec())?)
+}
+
+// Imports every cluster of a team manifest that the user's own credentials can reach
+pub async fn bootstrap(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    source: &str,
+) -> EmptyResult {
+    let manifest: Manifest = serde_yaml::from_str(&fetch_manifest(source).await?)?;
+    if manifest.clusters.is_empty() {
+        return Err(format!("{} lists no clusters", source).into());
+    }
+    eprintln!(
+        "Importing {} cluster(s) from {}",
+        manifest.clusters.len(),
+        source
+    );
+    let results = cloud::import_manifest(&SystemCommandRunner, &manifest, config).await;
+    let (mut kubeconfig, sources) =
+        kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?;
+    let mut incoming = Kubeconfig::default();
+    let mut imported = vec![];
+    let mut inaccessible = vec![];
+    for (entry, result) in results {
+        match result {
+            Ok(cluster) => {
+                kubeconfig::merge(&mut incoming, cluster, ConflictResolution::Overwrite);
+                imported.push(entry);
+            }
+            Err(e) => inaccessible.push((entry, e)),
+        }
+    }
+    if !imported.is_empty() {
+        check_incoming_writable(&kubeconfig, &sources, &incoming)?;
+        let preview = kubeconfig::merge_preview(&kubeconfig, &incoming);
+        println!("{}", preview.join("\n"));
+        if config.preview_merges
+            && !preview.is_empty()
+            && !confirm(&format!("Merge these changes into {}?", kubeconfig_path)).await?
+        {
+            return Err("Bootstrap cancelled".into());
+        }
+        let merged = kubeconfig::merge(&mut kubeconfig, incoming, ConflictResolution::Overwrite);
+        kubeconfig::save_sources(&kubeconfig, &sources, &history::dir(metadata_path)).await?;
+        if let Some(path) = &config.audit_log {
+            for name in &merged {
+                audit::append(path, "bootstrap", name, Some(format!("from {}", source))).await?;
+            }
+        }
+        let mut metadata = KtxMetadata::load(metadata_path)?;
+        for entry in imported.iter().cloned() {
+            let provenance = Provenance {
+                imported_at: Some(chrono::Utc::now()),
+                ..entry.provenance
+            };
+            metadata.record_import(&entry.context, provenance, &config.copy_import_tags);
+        }
+        metadata.save(metadata_path).await?;
+    }
+    eprintln!(
+        "Imported {} of {} cluster(s)",
+        imported.len(),
+        manifest.clusters.len()
+    );
+    if !inaccessible.is_empty() {
+        eprintln!("Not accessible with your credentials:");
+        for (entry, error) in &inaccessible {
+            eprintln!(
+                "  {} ({} {} {}): {}",
+                entry.context,
+                entry.provenance.provider,
+                entry.provenance.account,
+                entry.provenance.cluster,
+                error
+            );
+        }
+    }
+    if imported.is_empty() {
+        return Err(format!("None of the clusters in {} could be imported", source).into());
+    }
+    Ok(())
+}
+
+async fn confirm(question: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let answer = BufReader::new(tokio::io::stdin())
+        .lines()
+        .next_line()
+        .await?
+        .unwrap_or_default();
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Minifies to the given contexts, or the current one when none are given
+pub async fn minify(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    names: &[&str],
+    output: Option<&str>,
+) -> EmptyResult {
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let current_context = kubeconfig.current_context.clone().unwrap_or_default();
+    let names = if names.is_empty() {
+        if current_context.is_empty() {
+            return Err("No current context set, name the contexts to keep".into());
+        }
+        vec![current_context.as_str()]
+    } else {
+        names.to_vec()
+    };
+    let minified = kubeconfig::minify(&kubeconfig, &names)?;
+    match output {
+        Some(path) => kubeconfig::save_private(&minified, Path::new(path)).await,
+        None => {
+            print!("{}", serde_yaml::to_string(&minified)?);
+            Ok(())
+        }
+    }
+}
+
+fn print_numbered(contexts: &[(usize, String)], current: Option<&str>) {
+    for (number, name) in contexts {
+        if Some(name.as_str()) == current {
+            println!("{}. {} (current)", number, name);
+        } else {
+            println!("{}. {}", number, name);
+        }
+    }
+}
+
+// A line based prompt instead of the full screen UI, which screen readers can follow
+pub async fn plain(kubeconfig_path: &str, config: &KtxConfig, metadata_path: &str) -> EmptyResult {
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let contexts: Vec<(usize, String)> = kubeconfig
+        .contexts
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i + 1, c.name.clone()))
+        .collect();
+    if contexts.is_empty() {
+        println!("No contexts in {}", kubeconfig_path);
+        return Ok(());
+    }
+    let current = kubeconfig.current_context.as_deref();
+    println!(
+        "{} contexts, current is {}",
+        contexts.len(),
+        current.unwrap_or("not set")
+    );
+    print_numbered(&contexts, current);
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("Number to switch, text to filter, q to quit: ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next_line().await? else {
+            return Ok(());
+        };
+        let input = line.trim();
+        if input == "q" {
+            return Ok(());
+        }
+        if input.is_empty() {
+            print_numbered(&contexts, current);
+            continue;
+        }
+        if let Ok(number) = input.parse::<usize>() {
+            match contexts.iter().find(|(n, _)| *n == number) {
+                Some((_, name)) => {
+                    use_context(kubeconfig_path, config, metadata_path, name, None).await?;
+                    println!("Switched to {}", name);
+                    return Ok(());
+                }
+                None => println!("There is no context number {}", number),
+            }
+            continue;
+        }
+        let matching: Vec<(usize, String)> = contexts
+            .iter()
+            .filter(|(_, name)| name.to_lowercase().contains(&input.to_lowercase()))
+            .cloned()
+            .collect();
+        if matching.is_empty() {
+            println!("No contexts match {}", input);
+        } else {
+            println!("{} matching contexts", matching.len());
+            print_numbered(&matching, current);
+        }
+    }
+}
+
+pub async fn bench(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    names: &[&str],
+    rounds: u32,
+    sort: LatencySort,
+) -> EmptyResult {
+    if config.offline {
+        return Err("Benchmarks are disabled in offline mode".into());
+    }
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let names: Vec<String> = if names.is_empty() {
+        kubeconfig.contexts.iter().map(|c| c.name.clone()).collect()
+    } else {
+        names.iter().map(|n| n.to_string()).collect()
+    };
+    let kubeconfig = &kubeconfig;
+    let mut results: Vec<(String, Option<LatencyResult>)> = futures::stream::iter(names)
+        .map(|name| async move {
+            let family = config.ip_family_for(&name);
+            let result = cluster::benchmark(kubeconfig, &name, family, rounds).await;
+            (name, Some(result))
+        })
+        .buffer_unordered(cluster::BENCHMARK_CONCURRENCY)
+        .collect()
+        .await;
+    sort.sort(&mut results);
+    let width = results
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "{:<width$}  {:>9}  {:>9}  {:>9}",
+        "context", "min", "avg", "max"
+    );
+    for (name, result) in results {
+        match result {
+            Some(Ok(stats)) => println!(
+                "{:<width$}  {:>9}  {:>9}  {:>9}",
+                name,
+                cluster::format_millis(stats.min),
+                cluster::format_millis(stats.avg),
+                cluster::format_millis(stats.max)
+            ),
+            Some(Err(e)) => println!("{:<width$}  error: {}", name, e),
+            None => {}
+        }
+    }
+    Ok(())
+}
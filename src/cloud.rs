@@ -0,0 +1,784 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use futures::StreamExt;
+use kube::config::Kubeconfig;
+
+use crate::config::{AwsConfig, AwsCredentialsWrapper, KtxConfig};
+use crate::kubeconfig;
+use crate::metadata::{Manifest, ManifestEntry};
+use crate::runner::CommandRunner;
+use crate::ui::CloudImportPath;
+
+#[cfg(test)]
+mod tests;
+
+// primary id, display name, optional secondary id
+pub type ImportOption = (String, String, Option<String>);
+
+const DESCRIBE_CONCURRENCY: usize = 8;
+const MANIFEST_IMPORT_CONCURRENCY: usize = 4;
+
+// What `kubelogin convert-kubeconfig -l` accepts
+pub const KUBELOGIN_MODES: [&str; 8] = [
+    "azurecli",
+    "devicecode",
+    "interactive",
+    "spn",
+    "ropc",
+    "msi",
+    "workloadidentity",
+    "azd",
+];
+
+type OptionsResult = Result<Vec<ImportOption>, Box<dyn Error + Send + Sync>>;
+
+// What the cloud CLIs' cluster listings say about each cluster, for sorting the import list.
+// Fields the listing doesn't include stay None.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClusterDetails {
+    pub region: Option<String>,
+    pub status: Option<String>,
+    pub version: Option<String>,
+    // Labels on GKE, tags on EKS and AKS
+    pub tags: BTreeMap<String, String>,
+}
+
+impl ClusterDetails {
+    // Importing a cluster that is being deleted or failed to provision only yields a broken context.
+    // GKE reports deletions as STOPPING.
+    pub fn is_unusable(&self) -> bool {
+        matches!(
+            self.status.as_deref().map(|s| s.to_lowercase()).as_deref(),
+            Some("deleting" | "failed" | "error" | "stopping")
+        )
+    }
+}
+
+type DetailedOptionsResult =
+    Result<Vec<(ImportOption, ClusterDetails)>, Box<dyn Error + Send + Sync>>;
+
+fn field(value: &serde_json::Value) -> Option<String> {
+    value
+        .as_str()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+fn tags(value: &serde_json::Value) -> BTreeMap<String, String> {
+    value
+        .as_object()
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn without_details(result: DetailedOptionsResult) -> OptionsResult {
+    Ok(result?.into_iter().map(|(option, _)| option).collect())
+}
+
+type KubeconfigResult = Result<Kubeconfig, Box<dyn Error + Send + Sync>>;
+
+async fn is_gcp_configured(runner: &dyn CommandRunner) -> bool {
+    match runner
+        .exec_to_json("gcloud", &["--format", "json", "info"])
+        .await
+    {
+        Err(_) => false,
+        Ok(info) => {
+            let account = info["config"]["account"].as_str().unwrap_or("");
+            !account.is_empty()
+        }
+    }
+}
+
+async fn is_aws_configured(runner: &dyn CommandRunner) -> bool {
+    match runner
+        .exec_to_str("aws", &["configure", "list-profiles"])
+        .await
+    {
+        Err(_) => false,
+        Ok(output) => {
+            let profiles = output.split('\n').collect::<Vec<&str>>();
+            !profiles.is_empty()
+        }
+    }
+}
+
+async fn is_azure_configured(runner: &dyn CommandRunner) -> bool {
+    match runner
+        .exec_to_json("az", &["account", "show", "--output", "json"])
+        .await
+    {
+        Err(_) => false,
+        Ok(account) => {
+            let user = account["user"]["name"].as_str().unwrap_or("");
+            !user.is_empty()
+        }
+    }
+}
+
+// The providers whose CLI is installed and logged in, checked side by side
+pub async fn list_clouds(runner: &dyn CommandRunner) -> OptionsResult {
+    let (gcp_configured, aws_configured, azure_configured) = tokio::join!(
+        is_gcp_configured(runner),
+        is_aws_configured(runner),
+        is_azure_configured(runner)
+    );
+    let mut options = vec![];
+    if aws_configured {
+        options.push(("aws".to_string(), "AWS".to_string(), None));
+    }
+    if gcp_configured {
+        options.push(("gcp".to_string(), "GCP".to_string(), None));
+    }
+    if azure_configured {
+        options.push(("azure".to_string(), "Azure".to_string(), None));
+    };
+    Ok(options)
+}
+
+async fn list_gcp_projects(runner: &dyn CommandRunner) -> OptionsResult {
+    let projects = runner
+        .exec_to_json("gcloud", &["--format", "json", "projects", "list"])
+        .await?;
+    let mut options = vec![];
+    for project in projects.as_array().ok_or("Unexpected gcloud output")? {
+        let project_id = project["projectId"].as_str().unwrap_or("");
+        let project_name = project["name"].as_str().unwrap_or("");
+        let lifecycle_state = project["lifecycleState"].as_str().unwrap_or("");
+        if !project_id.is_empty()
+            && !project_id.starts_with("sys-")
+            && !project_name.is_empty()
+            && lifecycle_state == "ACTIVE"
+        {
+            options.push((
+                project_id.to_string(),
+                format!("{} ({})", project_name, project_id),
+                None,
+            ));
+        }
+    }
+    Ok(options)
+}
+
+async fn list_gke_clusters(runner: &dyn CommandRunner, project: &str) -> DetailedOptionsResult {
+    let clusters = runner
+        .exec_to_json(
+            "gcloud",
+            &[
+                "--format",
+                "json",
+                "container",
+                "clusters",
+                "list",
+                "--project",
+                project,
+            ],
+        )
+        .await?;
+    let mut options = vec![];
+    for cluster in clusters.as_array().ok_or("Unexpected gcloud output")? {
+        let cluster_name = cluster["name"].as_str().unwrap_or("");
+        let zone = cluster["zone"].as_str().unwrap_or("");
+        options.push((
+            (
+                cluster_name.to_string(),
+                cluster_name.to_string(),
+                Some(zone.to_string()),
+            ),
+            ClusterDetails {
+                region: field(&cluster["location"]).or_else(|| field(&cluster["zone"])),
+                status: field(&cluster["status"]),
+                version: field(&cluster["currentMasterVersion"]),
+                tags: tags(&cluster["resourceLabels"]),
+            },
+        ));
+    }
+    Ok(options)
+}
+
+async fn list_aws_profiles(runner: &dyn CommandRunner) -> OptionsResult {
+    let output = runner
+        .exec_to_str("aws", &["configure", "list-profiles"])
+        .await?;
+    Ok(output
+        .split('\n')
+        .filter(|profile| !profile.is_empty())
+        .map(|profile| (profile.to_string(), profile.to_string(), None))
+        .collect())
+}
+
+// The aws call for `args`, which name the profile with --profile. Wrappers provide the profile's
+// credentials themselves, so the flag is dropped for them.
+fn aws_command(aws: &AwsConfig, profile: &str, args: &[&str]) -> (&'static str, Vec<String>) {
+    let mut unprofiled = vec![];
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if *arg == "--profile" {
+            rest.next();
+        } else {
+            unprofiled.push(arg.to_string());
+        }
+    }
+    match aws.credentials_wrapper {
+        None => ("aws", args.iter().map(|a| a.to_string()).collect()),
+        Some(AwsCredentialsWrapper::AwsVault) => (
+            "aws-vault",
+            ["exec", profile, "--", "aws"]
+                .into_iter()
+                .map(String::from)
+                .chain(unprofiled)
+                .collect(),
+        ),
+        Some(AwsCredentialsWrapper::Granted) => (
+            "assume",
+            vec![
+                profile.to_string(),
+                "--exec".to_string(),
+                // Run through a shell by granted, so every argument is quoted
+                shell_words::join(std::iter::once("aws".to_string()).chain(unprofiled)),
+            ],
+        ),
+    }
+}
+
+async fn run_aws(
+    runner: &dyn CommandRunner,
+    aws: &AwsConfig,
+    profile: &str,
+    args: &[&str],
+) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
+    let (command, args) = aws_command(aws, profile, args);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    runner.exec_to_json(command, &args).await
+}
+
+async fn list_aws_regions(
+    runner: &dyn CommandRunner,
+    aws: &AwsConfig,
+    profile: &str,
+) -> OptionsResult {
+    let regions = run_aws(
+        runner,
+        aws,
+        profile,
+        &[
+            "--profile",
+            profile,
+            "--output",
+            "json",
+            "ec2",
+            "describe-regions",
+        ],
+    )
+    .await?;
+    let mut options = vec![];
+    for region in regions["Regions"]
+        .as_array()
+        .ok_or("Unexpected aws output")?
+    {
+        let region_name = region["RegionName"].as_str().unwrap_or("");
+        options.push((region_name.to_string(), region_name.to_string(), None));
+    }
+    Ok(options)
+}
+
+async fn list_eks_clusters(
+    runner: &dyn CommandRunner,
+    aws: &AwsConfig,
+    profile: &str,
+    region: &str,
+) -> DetailedOptionsResult {
+    let clusters = run_aws(
+        runner,
+        aws,
+        profile,
+        &[
+            "--profile",
+            profile,
+            "--output",
+            "json",
+            "eks",
+            "list-clusters",
+            "--region",
+            region,
+        ],
+    )
+    .await?;
+    let mut options = vec![];
+    for cluster in clusters["clusters"]
+        .as_array()
+        .ok_or("Unexpected aws output")?
+    {
+        let cluster_name = cluster.as_str().unwrap_or("");
+        options.push((
+            (cluster_name.to_string(), cluster_name.to_string(), None),
+            ClusterDetails {
+                region: Some(region.to_string()),
+                ..Default::default()
+            },
+        ));
+    }
+    Ok(options)
+}
+
+async fn list_azure_subscriptions(runner: &dyn CommandRunner) -> OptionsResult {
+    let subscriptions = runner
+        .exec_to_json("az", &["account", "list", "--output", "json"])
+        .await?;
+    let mut options = vec![];
+    for subscription in subscriptions.as_array().ok_or("Unexpected az output")? {
+        let subscription_id = subscription["id"].as_str().unwrap_or("");
+        let subscription_name = subscription["name"].as_str().unwrap_or("");
+        if !subscription_id.is_empty() && !subscription_name.is_empty() {
+            options.push((
+                subscription_id.to_string(),
+                format!("{} ({})", subscription_name, subscription_id),
+                None,
+            ));
+        }
+    }
+    Ok(options)
+}
+
+async fn list_aks_clusters(
+    runner: &dyn CommandRunner,
+    subscription: &str,
+) -> DetailedOptionsResult {
+    let clusters = runner
+        .exec_to_json(
+            "az",
+            &[
+                "aks",
+                "list",
+                "--subscription",
+                subscription,
+                "--output",
+                "json",
+            ],
+        )
+        .await?;
+    let mut options = vec![];
+    for cluster in clusters.as_array().ok_or("Unexpected az output")? {
+        let cluster_name = cluster["name"].as_str().unwrap_or("");
+        let resource_group = cluster["resourceGroup"].as_str().unwrap_or("");
+        options.push((
+            (
+                cluster_name.to_string(),
+                format!("{} (RG: {})", cluster_name, resource_group),
+                Some(resource_group.to_string()),
+            ),
+            ClusterDetails {
+                region: field(&cluster["location"]),
+                status: field(&cluster["powerState"]["code"])
+                    .or_else(|| field(&cluster["provisioningState"])),
+                version: field(&cluster["currentKubernetesVersion"])
+                    .or_else(|| field(&cluster["kubernetesVersion"])),
+                tags: tags(&cluster["tags"]),
+            },
+        ));
+    }
+    Ok(options)
+}
+
+// Kubeconfig files in a directory, the way Lens, Teleport or k9s setups keep one file per cluster.
+// The secondary id lists the file's contexts, one per line.
+async fn list_kubeconfig_files(directory: &str) -> OptionsResult {
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    let mut options = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.starts_with('.') || !path.is_file() {
+            continue;
+        }
+        // Whatever else lives in the folder isn't a kubeconfig
+        let Ok(kubeconfig) = Kubeconfig::read_from(&path) else {
+            continue;
+        };
+        let contexts: Vec<String> = kubeconfig.contexts.into_iter().map(|c| c.name).collect();
+        if contexts.is_empty() {
+            continue;
+        }
+        options.push((
+            path.to_string_lossy().into_owned(),
+            format!("{} ({})", file_name, contexts.join(", ")),
+            Some(contexts.join("\n")),
+        ));
+    }
+    options.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(options)
+}
+
+// Lists what can be picked at the next level of the import drilldown
+pub async fn list_options(
+    runner: &dyn CommandRunner,
+    import_path: &CloudImportPath,
+    config: &KtxConfig,
+) -> OptionsResult {
+    if import_path.is_empty() {
+        return list_clouds(runner).await;
+    }
+    match (import_path.get_platform().as_str(), import_path.len()) {
+        ("dir", 1) => list_kubeconfig_files(import_path.get_directory().as_str()).await,
+        ("aws", 1) => list_aws_profiles(runner).await,
+        ("aws", 2) => {
+            list_aws_regions(runner, &config.aws, import_path.get_aws_profile().as_str()).await
+        }
+        ("aws", 3) | ("gcp", 2) | ("azure", 2) => {
+            without_details(list_clusters(runner, import_path, config).await)
+        }
+        ("gcp", 1) => list_gcp_projects(runner).await,
+        ("azure", 1) => list_azure_subscriptions(runner).await,
+        _ => Ok(vec![]),
+    }
+}
+
+async fn list_clusters(
+    runner: &dyn CommandRunner,
+    import_path: &CloudImportPath,
+    config: &KtxConfig,
+) -> DetailedOptionsResult {
+    match import_path.get_platform().as_str() {
+        "aws" => {
+            list_eks_clusters(
+                runner,
+                &config.aws,
+                import_path.get_aws_profile().as_str(),
+                import_path.get_aws_region().as_str(),
+            )
+            .await
+        }
+        "gcp" => list_gke_clusters(runner, import_path.get_gcp_project().as_str()).await,
+        "azure" => list_aks_clusters(runner, import_path.get_azure_subscription().as_str()).await,
+        _ => Ok(vec![]),
+    }
+}
+
+// EKS only lists cluster names, status, version and tags take a describe-cluster call per cluster.
+// Clusters that can't be described are listed without them.
+async fn describe_eks_clusters(
+    runner: &dyn CommandRunner,
+    aws: &AwsConfig,
+    import_path: &CloudImportPath,
+    clusters: &mut [(ImportOption, ClusterDetails)],
+) {
+    let (profile, region) = (import_path.get_aws_profile(), import_path.get_aws_region());
+    let requests: Vec<_> = clusters
+        .iter()
+        .map(|(option, _)| {
+            let args = [
+                "--region",
+                region.as_str(),
+                "--profile",
+                profile.as_str(),
+                "--output",
+                "json",
+                "eks",
+                "describe-cluster",
+                "--name",
+                option.0.as_str(),
+            ]
+            .map(String::from);
+            let profile = profile.as_str();
+            async move {
+                let args = args.each_ref().map(String::as_str);
+                run_aws(runner, aws, profile, &args).await
+            }
+        })
+        .collect();
+    let descriptions: Vec<_> = futures::stream::iter(requests)
+        .buffered(DESCRIBE_CONCURRENCY)
+        .collect()
+        .await;
+    for ((_, details), description) in clusters.iter_mut().zip(descriptions) {
+        if let Ok(description) = description {
+            details.status = field(&description["cluster"]["status"]);
+            details.version = field(&description["cluster"]["version"]);
+            details.tags = tags(&description["cluster"]["tags"]);
+        }
+    }
+}
+
+// What the import wizard offers: list_options narrowed down by the provider sections of the
+// config, along with the details of listed cloud clusters
+pub async fn list_options_with_details(
+    runner: &dyn CommandRunner,
+    import_path: &CloudImportPath,
+    config: &KtxConfig,
+) -> DetailedOptionsResult {
+    let undetailed = |options: Vec<ImportOption>| {
+        options
+            .into_iter()
+            .map(|option| (option, ClusterDetails::default()))
+            .collect()
+    };
+    let configured = |ids: &[String]| {
+        ids.iter()
+            .map(|id| (id.clone(), id.clone(), None))
+            .collect()
+    };
+    if import_path.is_listing_clusters() && !import_path.is_directory() {
+        let mut clusters = list_clusters(runner, import_path, config).await?;
+        if import_path.is_aws() {
+            describe_eks_clusters(runner, &config.aws, import_path, &mut clusters).await;
+        }
+        return Ok(clusters);
+    }
+    // Configured profiles and regions also save the CLI round trip
+    if import_path.is_aws() && import_path.len() == 1 && !config.aws.profiles.is_empty() {
+        return Ok(undetailed(configured(&config.aws.profiles)));
+    }
+    if import_path.is_aws() && import_path.len() == 2 && !config.aws.regions.is_empty() {
+        return Ok(undetailed(configured(&config.aws.regions)));
+    }
+    let mut options = list_options(runner, import_path, config).await?;
+    if import_path.is_gcp() && import_path.len() == 1 && !config.gcp.projects.is_empty() {
+        options.retain(|(id, _, _)| config.gcp.projects.contains(id));
+    }
+    if import_path.is_azure() && import_path.len() == 1 {
+        let excluded = &config.azure.exclude_subscriptions;
+        options.retain(|(id, name, _)| {
+            !excluded
+                .iter()
+                .any(|e| e == id || *name == format!("{} ({})", e, id))
+        });
+    }
+    Ok(undetailed(options))
+}
+
+async fn import_aws_cluster(
+    runner: &dyn CommandRunner,
+    aws: &AwsConfig,
+    import_path: &CloudImportPath,
+    role_arn: Option<&str>,
+) -> KubeconfigResult {
+    let region = import_path.get_aws_region();
+    let profile = import_path.get_aws_profile();
+    let cluster_id = import_path.get_cluster_id();
+    let description = run_aws(
+        runner,
+        aws,
+        profile.as_str(),
+        &[
+            "--region",
+            region.as_str(),
+            "--profile",
+            profile.as_str(),
+            "--output",
+            "json",
+            "eks",
+            "describe-cluster",
+            "--name",
+            cluster_id.as_str(),
+        ],
+    )
+    .await?;
+    let cluster = &description["cluster"];
+    // Clusters still being created have none, an empty server would be merged otherwise
+    let endpoint = cluster["endpoint"]
+        .as_str()
+        .filter(|endpoint| !endpoint.is_empty())
+        .ok_or_else(|| format!("{} has no endpoint yet", cluster_id))?;
+    let mut args = vec![
+        "--region",
+        region.as_str(),
+        "eks",
+        "get-token",
+        "--cluster-name",
+        cluster_id.as_str(),
+        "--output",
+        "json",
+    ];
+    if let Some(role_arn) = role_arn {
+        args.extend(["--role-arn", role_arn]);
+    }
+    let exec = match aws.credentials_wrapper {
+        None => kubeconfig::exec_auth("aws", &args, &[("AWS_PROFILE", profile.as_str())]),
+        Some(_) => {
+            let (command, args) = aws_command(aws, profile.as_str(), &args);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            kubeconfig::exec_auth(command, &args, &[])
+        }
+    };
+    Ok(kubeconfig::single_context(
+        cluster["arn"].as_str().unwrap_or(cluster_id.as_str()),
+        endpoint,
+        cluster["certificateAuthority"]["data"]
+            .as_str()
+            .map(|s| s.to_string()),
+        exec,
+    ))
+}
+
+async fn import_gke_cluster(
+    runner: &dyn CommandRunner,
+    import_path: &CloudImportPath,
+) -> KubeconfigResult {
+    let project = import_path.get_gcp_project();
+    let zone = import_path.get_gke_zone();
+    let cluster_id = import_path.get_cluster_id();
+    let cluster = runner
+        .exec_to_json(
+            "gcloud",
+            &[
+                "--format",
+                "json",
+                "container",
+                "clusters",
+                "describe",
+                cluster_id.as_str(),
+                "--zone",
+                zone.as_str(),
+                "--project",
+                project.as_str(),
+            ],
+        )
+        .await?;
+    let endpoint = cluster["endpoint"]
+        .as_str()
+        .filter(|endpoint| !endpoint.is_empty())
+        .ok_or_else(|| format!("{} has no endpoint yet", cluster_id))?;
+    let exec = kubeconfig::exec_auth("gke-gcloud-auth-plugin", &[], &[]);
+    Ok(kubeconfig::single_context(
+        format!("gke_{}_{}_{}", project, zone, cluster_id).as_str(),
+        format!("https://{}", endpoint).as_str(),
+        cluster["masterAuth"]["clusterCaCertificate"]
+            .as_str()
+            .map(|s| s.to_string()),
+        exec,
+    ))
+}
+
+async fn import_aks_cluster(
+    runner: &dyn CommandRunner,
+    import_path: &CloudImportPath,
+    kubelogin_mode: Option<&str>,
+) -> KubeconfigResult {
+    // AKS only hands out user credentials as a complete kubeconfig document, so it is printed
+    // to stdout and parsed rather than written anywhere.
+    let output = runner
+        .exec_to_str(
+            "az",
+            &[
+                "aks",
+                "get-credentials",
+                "--resource-group",
+                import_path.get_azure_resource_group().as_str(),
+                "--name",
+                import_path.get_cluster_id().as_str(),
+                "--subscription",
+                import_path.get_azure_subscription().as_str(),
+                "--file",
+                "-",
+            ],
+        )
+        .await?;
+    let mut imported = Kubeconfig::from_yaml(&output)?;
+    if let Some(mode) = kubelogin_mode {
+        kubeconfig::convert_kubelogin(&mut imported, mode);
+    }
+    Ok(imported)
+}
+
+// Fills in {cluster}, {account} and {location} (region, zone or resource group)
+fn context_name(template: &str, import_path: &CloudImportPath) -> Option<String> {
+    let provenance = import_path.provenance()?;
+    let location = provenance
+        .location
+        .or(provenance.resource_group)
+        .unwrap_or_default();
+    Some(
+        template
+            .replace("{cluster}", &provenance.cluster)
+            .replace("{account}", &provenance.account)
+            .replace("{location}", &location),
+    )
+}
+
+// Builds an in-memory kubeconfig for the cluster at the end of a full import path
+pub async fn import_cluster(
+    runner: &dyn CommandRunner,
+    import_path: &CloudImportPath,
+    config: &KtxConfig,
+) -> KubeconfigResult {
+    let (mut imported, template) = if import_path.is_directory() {
+        // Relative certificate paths are resolved against the file's folder
+        (Kubeconfig::read_from(import_path.get_cluster_id())?, None)
+    } else if import_path.is_aws() {
+        let role_arn = config.aws.role_arns.get(&import_path.get_aws_profile());
+        (
+            import_aws_cluster(
+                runner,
+                &config.aws,
+                import_path,
+                role_arn.map(|r| r.as_str()),
+            )
+            .await?,
+            config.aws.context_name.as_deref(),
+        )
+    } else if import_path.is_gcp() {
+        (
+            import_gke_cluster(runner, import_path).await?,
+            config.gcp.context_name.as_deref(),
+        )
+    } else if import_path.is_azure() {
+        (
+            import_aks_cluster(runner, import_path, config.azure.kubelogin_mode.as_deref()).await?,
+            config.azure.context_name.as_deref(),
+        )
+    } else {
+        return Err(format!("Unknown cloud provider {}", import_path.get_platform()).into());
+    };
+    if let (Some(name), [context]) = (
+        template.and_then(|t| context_name(t, import_path)),
+        imported.contexts.as_slice(),
+    ) {
+        let imported_name = context.name.clone();
+        kubeconfig::rename_context(&mut imported, &imported_name, &name)?;
+    }
+    Ok(imported)
+}
+
+// Imports every cluster of a provenance manifest under its context name. Clusters the current
+// credentials can't reach come back with the error instead.
+pub async fn import_manifest(
+    runner: &dyn CommandRunner,
+    manifest: &Manifest,
+    config: &KtxConfig,
+) -> Vec<(ManifestEntry, Result<Kubeconfig, String>)> {
+    futures::stream::iter(&manifest.clusters)
+        .map(|entry| async move {
+            let Some(import_path) = CloudImportPath::from_provenance(&entry.provenance) else {
+                let error = format!("Can't import {} clusters", entry.provenance.provider);
+                return (entry.clone(), Err(error));
+            };
+            let config = config.for_preset(entry.provenance.preset.as_deref());
+            let result = match import_cluster(runner, &import_path, &config).await {
+                Ok(mut imported) => {
+                    let renamed = match imported.contexts.as_slice() {
+                        [context] => {
+                            let imported_name = context.name.clone();
+                            kubeconfig::rename_context(
+                                &mut imported,
+                                &imported_name,
+                                &entry.context,
+                            )
+                        }
+                        _ => Ok(()),
+                    };
+                    imported.current_context = None;
+                    renamed.map(|_| imported).map_err(|e| e.to_string())
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            (entry.clone(), result)
+        })
+        .buffered(MANIFEST_IMPORT_CONCURRENCY)
+        .collect()
+        .await
+}
@@ -0,0 +1,618 @@
+// Drives the import drilldown against CLI outputs recorded in tests/fixtures.
+
+use kube::config::Kubeconfig;
+
+use super::{
+    aws_command, import_cluster, import_manifest, list_options, list_options_with_details,
+    ClusterDetails, ImportOption,
+};
+use crate::config::{AwsCredentialsWrapper, KtxConfig};
+use crate::kubeconfig::{self, ConflictResolution};
+use crate::metadata::{Manifest, ManifestEntry};
+use crate::runner::mock::MockCommandRunner;
+use crate::ui::CloudImportPath;
+
+const AWS_PROFILES: &str = include_str!("../../tests/fixtures/aws/list-profiles.txt");
+const AWS_REGIONS: &str = include_str!("../../tests/fixtures/aws/describe-regions.json");
+const EKS_CLUSTERS: &str = include_str!("../../tests/fixtures/aws/list-clusters.json");
+const EKS_CLUSTER: &str = include_str!("../../tests/fixtures/aws/describe-cluster.json");
+const GCLOUD_INFO: &str = include_str!("../../tests/fixtures/gcp/info.json");
+const GCP_PROJECTS: &str = include_str!("../../tests/fixtures/gcp/projects-list.json");
+const GKE_CLUSTERS: &str = include_str!("../../tests/fixtures/gcp/clusters-list.json");
+const GKE_CLUSTER: &str = include_str!("../../tests/fixtures/gcp/clusters-describe.json");
+const AZ_ACCOUNT: &str = include_str!("../../tests/fixtures/azure/account-show.json");
+const AZ_SUBSCRIPTIONS: &str = include_str!("../../tests/fixtures/azure/account-list.json");
+const AKS_CLUSTERS: &str = include_str!("../../tests/fixtures/azure/aks-list.json");
+const AKS_CREDENTIALS: &str = include_str!("../../tests/fixtures/azure/aks-get-credentials.yaml");
+
+const SUBSCRIPTION: &str = "5f3c2b1a-8d7e-4c6b-9a05-1e2d3c4b5a69";
+
+fn option(id: &str, name: &str, secondary: Option<&str>) -> ImportOption {
+    (
+        id.to_string(),
+        name.to_string(),
+        secondary.map(String::from),
+    )
+}
+
+fn path(options: &[ImportOption]) -> CloudImportPath {
+    CloudImportPath::from(options.to_vec())
+}
+
+fn ids(options: &[ImportOption]) -> Vec<&str> {
+    options.iter().map(|o| o.0.as_str()).collect()
+}
+
+fn aws_path() -> Vec<ImportOption> {
+    vec![
+        option("aws", "AWS", None),
+        option("staging", "staging", None),
+        option("eu-west-1", "eu-west-1", None),
+        option("payments", "payments", None),
+    ]
+}
+
+fn gcp_path() -> Vec<ImportOption> {
+    vec![
+        option("gcp", "GCP", None),
+        option("shop-prod-4821", "Shop Production (shop-prod-4821)", None),
+        option("storefront", "storefront", Some("europe-west1-b")),
+    ]
+}
+
+fn azure_path() -> Vec<ImportOption> {
+    vec![
+        option("azure", "Azure", None),
+        option(SUBSCRIPTION, "Platform", None),
+        option("analytics", "analytics (RG: data-rg)", Some("data-rg")),
+    ]
+}
+
+#[tokio::test]
+async fn lists_only_configured_clouds() {
+    let runner = MockCommandRunner::new()
+        .with_output("aws configure list-profiles", AWS_PROFILES)
+        .with_output("gcloud --format json info", GCLOUD_INFO);
+    let options = list_options(&runner, &path(&[]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&options), vec!["aws", "gcp"]);
+
+    let runner = MockCommandRunner::new().with_output("az account show --output json", AZ_ACCOUNT);
+    let options = list_options(&runner, &path(&[]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&options), vec!["azure"]);
+}
+
+#[tokio::test]
+async fn aws_drilldown() {
+    let runner = MockCommandRunner::new()
+        .with_output("aws configure list-profiles", AWS_PROFILES)
+        .with_output(
+            "aws --profile staging --output json ec2 describe-regions",
+            AWS_REGIONS,
+        )
+        .with_output(
+            "aws --profile staging --output json eks list-clusters --region eu-west-1",
+            EKS_CLUSTERS,
+        );
+    let full_path = aws_path();
+
+    let profiles = list_options(&runner, &path(&full_path[..1]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&profiles), vec!["default", "staging", "production"]);
+
+    let regions = list_options(&runner, &path(&full_path[..2]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&regions), vec!["eu-west-1", "us-east-1"]);
+
+    let clusters = list_options(&runner, &path(&full_path[..3]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&clusters), vec!["payments", "search"]);
+    assert!(path(&full_path[..3]).is_listing_clusters());
+}
+
+#[tokio::test]
+async fn eks_listing_describes_each_cluster() {
+    let runner = MockCommandRunner::new()
+        .with_output(
+            "aws --profile staging --output json eks list-clusters --region eu-west-1",
+            EKS_CLUSTERS,
+        )
+        .with_output(
+            "aws --region eu-west-1 --profile staging --output json eks describe-cluster --name payments",
+            EKS_CLUSTER,
+        );
+    let clusters =
+        list_options_with_details(&runner, &path(&aws_path()[..3]), &KtxConfig::default())
+            .await
+            .unwrap();
+    assert_eq!(
+        clusters[0].1,
+        ClusterDetails {
+            region: Some("eu-west-1".to_string()),
+            status: Some("ACTIVE".to_string()),
+            version: Some("1.26".to_string()),
+            tags: [("team".to_string(), "payments".to_string())].into(),
+        }
+    );
+    // search can't be described, which only leaves its details out
+    assert_eq!(clusters[1].0 .0, "search");
+    assert_eq!(clusters[1].1.status, None);
+}
+
+#[tokio::test]
+async fn aws_import_uses_the_profile_for_tokens() {
+    let runner = MockCommandRunner::new().with_output(
+        "aws --region eu-west-1 --profile staging --output json eks describe-cluster --name payments",
+        EKS_CLUSTER,
+    );
+    let import_path = path(&aws_path());
+    let imported = import_cluster(&runner, &import_path, &KtxConfig::default())
+        .await
+        .unwrap();
+
+    let name = "arn:aws:eks:eu-west-1:123456789012:cluster/payments";
+    assert_eq!(imported.current_context.as_deref(), Some(name));
+    let cluster = kubeconfig::context_cluster(&imported, name)
+        .and_then(|c| c.cluster.clone())
+        .unwrap();
+    assert_eq!(
+        cluster.server.as_deref(),
+        Some("https://0A1B2C3D4E5F60718293A4B5C6D7E8F9.gr7.eu-west-1.eks.amazonaws.com")
+    );
+    assert!(cluster.certificate_authority_data.is_some());
+
+    let exec = imported.auth_infos[0]
+        .auth_info
+        .as_ref()
+        .and_then(|a| a.exec.clone())
+        .unwrap();
+    assert_eq!(exec.command.as_deref(), Some("aws"));
+    assert!(exec.args.unwrap().contains(&"payments".to_string()));
+    let env = exec.env.unwrap();
+    assert_eq!(env[0]["name"], "AWS_PROFILE");
+    assert_eq!(env[0]["value"], "staging");
+
+    assert!(!import_path.is_imported(&Kubeconfig::default()));
+    assert!(import_path.is_imported(&imported));
+}
+
+#[tokio::test]
+async fn aws_vault_wraps_listing_and_tokens() {
+    let mut config = KtxConfig::default();
+    config.aws.credentials_wrapper = Some(AwsCredentialsWrapper::AwsVault);
+    let runner = MockCommandRunner::new()
+        .with_output(
+            "aws-vault exec staging -- aws --output json eks list-clusters --region eu-west-1",
+            EKS_CLUSTERS,
+        )
+        .with_output(
+            "aws-vault exec staging -- aws --region eu-west-1 --output json eks describe-cluster --name payments",
+            EKS_CLUSTER,
+        );
+    let clusters = list_options(&runner, &path(&aws_path()[..3]), &config)
+        .await
+        .unwrap();
+    assert_eq!(clusters[0].0, "payments");
+
+    let imported = import_cluster(&runner, &path(&aws_path()), &config)
+        .await
+        .unwrap();
+    let exec = imported.auth_infos[0]
+        .auth_info
+        .as_ref()
+        .and_then(|a| a.exec.clone())
+        .unwrap();
+    assert_eq!(exec.command.as_deref(), Some("aws-vault"));
+    let args = exec.args.unwrap();
+    assert_eq!(args[..4], ["exec", "staging", "--", "aws"]);
+    assert!(!args.contains(&"--profile".to_string()));
+    // aws-vault provides the credentials, so the profile isn't passed on
+    assert!(exec.env.is_none());
+}
+
+#[tokio::test]
+async fn gcp_drilldown_skips_inactive_and_system_projects() {
+    let runner = MockCommandRunner::new()
+        .with_output("gcloud --format json projects list", GCP_PROJECTS)
+        .with_output(
+            "gcloud --format json container clusters list --project shop-prod-4821",
+            GKE_CLUSTERS,
+        );
+    let full_path = gcp_path();
+
+    let projects = list_options(&runner, &path(&full_path[..1]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        projects,
+        vec![option(
+            "shop-prod-4821",
+            "Shop Production (shop-prod-4821)",
+            None
+        )]
+    );
+
+    let clusters = list_options(&runner, &path(&full_path[..2]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        clusters,
+        vec![
+            option("storefront", "storefront", Some("europe-west1-b")),
+            option("batch", "batch", Some("europe-west1")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn cluster_listings_include_details() {
+    let runner = MockCommandRunner::new()
+        .with_output(
+            "gcloud --format json container clusters list --project shop-prod-4821",
+            GKE_CLUSTERS,
+        )
+        .with_output(
+            &format!("az aks list --subscription {} --output json", SUBSCRIPTION),
+            AKS_CLUSTERS,
+        );
+
+    let clusters =
+        list_options_with_details(&runner, &path(&gcp_path()[..2]), &KtxConfig::default())
+            .await
+            .unwrap();
+    assert_eq!(
+        clusters[1].1,
+        ClusterDetails {
+            region: Some("europe-west1".to_string()),
+            status: Some("RUNNING".to_string()),
+            version: Some("1.26.5-gke.1200".to_string()),
+            ..Default::default()
+        }
+    );
+
+    let clusters =
+        list_options_with_details(&runner, &path(&azure_path()[..2]), &KtxConfig::default())
+            .await
+            .unwrap();
+    assert_eq!(
+        clusters[0].1,
+        ClusterDetails {
+            region: Some("westeurope".to_string()),
+            status: Some("Succeeded".to_string()),
+            version: Some("1.27.7".to_string()),
+            ..Default::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn gke_import_follows_gcloud_naming() {
+    let runner = MockCommandRunner::new().with_output(
+        "gcloud --format json container clusters describe storefront --zone europe-west1-b --project shop-prod-4821",
+        GKE_CLUSTER,
+    );
+    let import_path = path(&gcp_path());
+    let imported = import_cluster(&runner, &import_path, &KtxConfig::default())
+        .await
+        .unwrap();
+
+    let name = "gke_shop-prod-4821_europe-west1-b_storefront";
+    assert_eq!(imported.current_context.as_deref(), Some(name));
+    let cluster = kubeconfig::context_cluster(&imported, name)
+        .and_then(|c| c.cluster.clone())
+        .unwrap();
+    assert_eq!(cluster.server.as_deref(), Some("https://34.78.12.201"));
+    let exec = imported.auth_infos[0]
+        .auth_info
+        .as_ref()
+        .and_then(|a| a.exec.clone())
+        .unwrap();
+    assert_eq!(exec.command.as_deref(), Some("gke-gcloud-auth-plugin"));
+    assert!(import_path.is_imported(&imported));
+}
+
+#[tokio::test]
+async fn clusters_without_an_endpoint_are_not_imported() {
+    let provisioning = r#"{"name": "storefront", "status": "PROVISIONING"}"#;
+    let runner = MockCommandRunner::new().with_output(
+        "gcloud --format json container clusters describe storefront --zone europe-west1-b --project shop-prod-4821",
+        provisioning,
+    );
+    let error = import_cluster(&runner, &path(&gcp_path()), &KtxConfig::default())
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "storefront has no endpoint yet");
+}
+
+#[tokio::test]
+async fn azure_drilldown() {
+    let runner = MockCommandRunner::new()
+        .with_output("az account list --output json", AZ_SUBSCRIPTIONS)
+        .with_output(
+            &format!("az aks list --subscription {} --output json", SUBSCRIPTION),
+            AKS_CLUSTERS,
+        );
+    let full_path = azure_path();
+
+    let subscriptions = list_options(&runner, &path(&full_path[..1]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        subscriptions,
+        vec![option(
+            SUBSCRIPTION,
+            &format!("Platform ({})", SUBSCRIPTION),
+            None
+        )]
+    );
+
+    let clusters = list_options(&runner, &path(&full_path[..2]), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        clusters,
+        vec![option(
+            "analytics",
+            "analytics (RG: data-rg)",
+            Some("data-rg")
+        )]
+    );
+}
+
+#[tokio::test]
+async fn aks_import_parses_the_printed_kubeconfig() {
+    let runner = MockCommandRunner::new().with_output(
+        &format!(
+            "az aks get-credentials --resource-group data-rg --name analytics --subscription {} --file -",
+            SUBSCRIPTION
+        ),
+        AKS_CREDENTIALS,
+    );
+    let import_path = path(&azure_path());
+    let imported = import_cluster(&runner, &import_path, &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(imported.current_context.as_deref(), Some("analytics"));
+    assert_eq!(imported.auth_infos[0].name, "clusterUser_data-rg_analytics");
+
+    let mut existing = Kubeconfig::default();
+    let merged = kubeconfig::merge(&mut existing, imported, ConflictResolution::Overwrite);
+    assert_eq!(merged, vec!["analytics".to_string()]);
+    assert!(import_path.is_imported(&existing));
+}
+
+#[tokio::test]
+async fn aks_import_converts_the_kubelogin_mode() {
+    let runner = MockCommandRunner::new().with_output(
+        &format!(
+            "az aks get-credentials --resource-group data-rg --name analytics --subscription {} --file -",
+            SUBSCRIPTION
+        ),
+        AKS_CREDENTIALS,
+    );
+    let mut config = KtxConfig::default();
+    config.azure.kubelogin_mode = Some("msi".to_string());
+    let imported = import_cluster(&runner, &path(&azure_path()), &config)
+        .await
+        .unwrap();
+    let args = imported.auth_infos[0]
+        .auth_info
+        .as_ref()
+        .and_then(|a| a.exec.clone())
+        .and_then(|e| e.args)
+        .unwrap();
+    assert_eq!(
+        args,
+        [
+            "get-token",
+            "--login",
+            "msi",
+            "--server-id",
+            "6dae42f8-4368-4678-94ff-3960e28e3630"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn manifests_import_what_the_credentials_reach() {
+    let runner = MockCommandRunner::new().with_output(
+        &format!(
+            "az aks get-credentials --resource-group data-rg --name analytics --subscription {} --file -",
+            SUBSCRIPTION
+        ),
+        AKS_CREDENTIALS,
+    );
+    let entry = |context: &str, full_path: Vec<ImportOption>| ManifestEntry {
+        context: context.to_string(),
+        provenance: path(&full_path).provenance().unwrap(),
+    };
+    let manifest = Manifest {
+        clusters: vec![
+            entry("team-analytics", azure_path()),
+            entry("team-payments", aws_path()),
+        ],
+    };
+    // Manifests travel as YAML with the provenance inline
+    let manifest: Manifest =
+        serde_yaml::from_str(&serde_yaml::to_string(&manifest).unwrap()).unwrap();
+
+    let results = import_manifest(&runner, &manifest, &KtxConfig::default()).await;
+    let (entry, imported) = &results[0];
+    let imported = imported.as_ref().unwrap();
+    assert_eq!(entry.provenance.cluster, "analytics");
+    assert_eq!(imported.contexts[0].name, "team-analytics");
+    assert_eq!(imported.current_context, None);
+    let (entry, error) = &results[1];
+    assert_eq!(entry.context, "team-payments");
+    assert!(error.is_err());
+}
+
+#[tokio::test]
+async fn cli_failures_are_reported() {
+    let runner = MockCommandRunner::new();
+    let full_path = aws_path();
+    assert!(
+        list_options(&runner, &path(&full_path[..2]), &KtxConfig::default())
+            .await
+            .is_err()
+    );
+    assert!(
+        import_cluster(&runner, &path(&full_path), &KtxConfig::default())
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        runner.calls(),
+        vec![
+            "aws --profile staging --output json ec2 describe-regions",
+            "aws --region eu-west-1 --profile staging --output json eks describe-cluster --name payments",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn directory_lists_kubeconfig_files() {
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/kubeconfigs");
+    let runner = MockCommandRunner::new();
+    let root = vec![option("dir", "Kubeconfig directory", Some(directory))];
+
+    let files = list_options(&runner, &path(&root), &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        files.iter().map(|f| f.1.as_str()).collect::<Vec<_>>(),
+        vec!["edge.yaml (edge)", "lab.yaml (lab-admin, lab-monitoring)"]
+    );
+    assert!(path(&root).is_listing_clusters());
+
+    let import_path = path(&[root[0].clone(), files[1].clone()]);
+    assert!(import_path.is_full());
+    assert!(import_path.provenance().is_none());
+    let imported = import_cluster(&runner, &import_path, &KtxConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(imported.contexts.len(), 2);
+    // Relative certificate paths point into the directory
+    let cluster = kubeconfig::context_cluster(&imported, "lab-admin")
+        .and_then(|c| c.cluster.clone())
+        .unwrap();
+    assert_eq!(
+        cluster.certificate_authority,
+        Some(format!("{}/certs/lab-ca.crt", directory))
+    );
+    assert!(import_path.is_imported(&imported));
+    assert!(!import_path.is_imported(&Kubeconfig::default()));
+}
+
+#[test]
+fn import_paths_are_rebuilt_from_provenance() {
+    for full_path in [aws_path(), gcp_path(), azure_path()] {
+        let mut provenance = path(&full_path).provenance().unwrap();
+        let rebuilt = CloudImportPath::from_provenance(&provenance).unwrap();
+        assert!(rebuilt.is_full());
+        let mut rebuilt = rebuilt.provenance().unwrap();
+        rebuilt.imported_at = None;
+        provenance.imported_at = None;
+        assert_eq!(
+            serde_json::to_value(rebuilt).ok(),
+            serde_json::to_value(provenance).ok()
+        );
+    }
+}
+
+#[tokio::test]
+async fn provider_sections_narrow_down_discovery() {
+    let mut config = KtxConfig::default();
+    config.aws.profiles = vec!["staging".to_string()];
+    config.aws.regions = vec!["eu-west-1".to_string()];
+    config.aws.role_arns.insert(
+        "staging".to_string(),
+        "arn:aws:iam::123456789012:role/admin".to_string(),
+    );
+    config.aws.context_name = Some("{account}-{location}-{cluster}".to_string());
+    config.gcp.projects = vec!["another-project".to_string()];
+    config.azure.exclude_subscriptions = vec!["Platform".to_string()];
+    let runner = MockCommandRunner::new()
+        .with_output("gcloud --format json projects list", GCP_PROJECTS)
+        .with_output("az account list --output json", AZ_SUBSCRIPTIONS)
+        .with_output(
+            "aws --region eu-west-1 --profile staging --output json eks describe-cluster --name payments",
+            EKS_CLUSTER,
+        );
+
+    // Configured profiles and regions are offered without running the CLI
+    let profiles = list_options_with_details(&runner, &path(&aws_path()[..1]), &config)
+        .await
+        .unwrap();
+    assert_eq!(profiles.len(), 1);
+    assert_eq!(profiles[0].0 .0, "staging");
+    let regions = list_options_with_details(&runner, &path(&aws_path()[..2]), &config)
+        .await
+        .unwrap();
+    assert_eq!(regions[0].0 .0, "eu-west-1");
+    assert!(runner.calls().is_empty());
+
+    let projects = list_options_with_details(&runner, &path(&gcp_path()[..1]), &config)
+        .await
+        .unwrap();
+    assert!(projects.is_empty());
+    let subscriptions = list_options_with_details(&runner, &path(&azure_path()[..1]), &config)
+        .await
+        .unwrap();
+    assert!(subscriptions.is_empty());
+
+    let imported = import_cluster(&runner, &path(&aws_path()), &config)
+        .await
+        .unwrap();
+    assert_eq!(
+        imported.current_context.as_deref(),
+        Some("staging-eu-west-1-payments")
+    );
+    let args = imported.auth_infos[0]
+        .auth_info
+        .as_ref()
+        .and_then(|a| a.exec.clone())
+        .and_then(|e| e.args)
+        .unwrap();
+    assert!(args.ends_with(&[
+        "--role-arn".to_string(),
+        "arn:aws:iam::123456789012:role/admin".to_string()
+    ]));
+}
+
+#[test]
+fn granted_gets_the_aws_command_quoted() {
+    let mut config = KtxConfig::default();
+    config.aws.credentials_wrapper = Some(AwsCredentialsWrapper::Granted);
+    let args = [
+        "--profile",
+        "staging",
+        "eks",
+        "get-token",
+        "--cluster-name",
+        "pay ments",
+        "--role-arn",
+        "arn:aws:iam::123456789012:role/it's;id",
+    ];
+    let (command, wrapped) = aws_command(&config.aws, "staging", &args);
+    assert_eq!(command, "assume");
+    assert_eq!(wrapped[..2], ["staging", "--exec"]);
+    assert_eq!(
+        shell_words::split(&wrapped[2]).unwrap(),
+        [
+            "aws",
+            "eks",
+            "get-token",
+            "--cluster-name",
+            "pay ments",
+            "--role-arn",
+            "arn:aws:iam::123456789012:role/it's;id"
+        ]
+    );
+}
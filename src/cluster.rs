@@ -0,0 +1,615 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use k8s_openapi::api::authentication::v1::{TokenRequest, TokenRequestSpec};
+use k8s_openapi::api::core::v1::{Namespace, Node, ServiceAccount};
+use kube::api::{Api, ListParams, ObjectMeta, PostParams};
+use kube::config::{ExecInteractiveMode, KubeConfigOptions, Kubeconfig};
+use kube::{Client, Config};
+use openssl::asn1::Asn1Time;
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::x509::X509;
+
+use crate::config::{HealthProbe, IpFamily};
+use crate::kubeconfig;
+use crate::plugins;
+use crate::proxy;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+#[derive(Debug)]
+struct ConnectionError {}
+
+impl Error for ConnectionError {}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connection is Unhealthy")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterInfo {
+    pub node_count: usize,
+    pub node_versions: Vec<String>,
+    pub platform: String,
+    pub namespace_count: usize,
+}
+
+// Every client of a context is built here, so proxies and the address family apply to all of them
+pub async fn client_for_context(
+    kubeconfig: &Kubeconfig,
+    name: &str,
+    family: IpFamily,
+) -> Result<Client, BoxError> {
+    // Otherwise this only surfaces as a vague connection error
+    if let Some(remediation) = plugins::missing_plugin(kubeconfig, name) {
+        return Err(remediation.into());
+    }
+    let options = KubeConfigOptions {
+        context: Some(name.to_string()),
+        cluster: None,
+        user: None,
+    };
+    let config = Config::from_custom_kubeconfig(kubeconfig.clone(), &options)
+        .await
+        .map_err(|_| ConnectionError {})?;
+    proxy::client_with_proxy(config, proxy::proxy_for_context(kubeconfig, name), family)
+}
+
+// What a healthy context shows next to its status: the server version, or what the probe did
+// when it doesn't read the version
+pub async fn health_check(
+    kubeconfig: &Kubeconfig,
+    name: &str,
+    family: IpFamily,
+    probe: &HealthProbe,
+) -> Result<String, BoxError> {
+    let kubeconfig = &non_interactive(kubeconfig, name)?;
+    let client = client_for_context(kubeconfig, name, family).await?;
+    let result = match precheck(kubeconfig, name, family).await {
+        Ok(()) => run_probe(client, probe).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(summary) => Ok(summary),
+        Err(e) => Err(with_dns_details(e, kubeconfig, name).await),
+    }
+}
+
+// Worded so FailureKind::classify picks it up
+const INTERACTIVE_AUTH: &str = "Interactive auth required";
+
+// Exec plugins run without the terminal during checks: stdin is detached and plugins are told
+// they can't prompt, so gcloud, az or kubelogin asking for input can't draw over the UI. Plugins
+// that insist on a terminal aren't run at all.
+fn non_interactive(kubeconfig: &Kubeconfig, name: &str) -> Result<Kubeconfig, BoxError> {
+    let mut kubeconfig = kubeconfig.clone();
+    let Some(exec) =
+        kubeconfig::context_user_mut(&mut kubeconfig, name).and_then(|user| user.exec.as_mut())
+    else {
+        return Ok(kubeconfig);
+    };
+    if exec.interactive_mode == Some(ExecInteractiveMode::Always) {
+        return Err(format!(
+            "{}: {} always asks for input",
+            INTERACTIVE_AUTH,
+            exec.command.as_deref().unwrap_or("the exec plugin")
+        )
+        .into());
+    }
+    exec.interactive_mode = Some(ExecInteractiveMode::Never);
+    let env = exec.env.get_or_insert_with(Vec::new);
+    for (key, value) in [("CLOUDSDK_CORE_DISABLE_PROMPTS", "1"), ("AWS_PAGER", "")] {
+        env.push(HashMap::from([
+            ("name".to_string(), key.to_string()),
+            ("value".to_string(), value.to_string()),
+        ]));
+    }
+    Ok(kubeconfig)
+}
+
+async fn run_probe(client: Client, probe: &HealthProbe) -> Result<String, BoxError> {
+    let path = match probe {
+        HealthProbe::Version => {
+            let version = client.apiserver_version().await?;
+            return Ok(format!("{}.{}", version.major, version.minor));
+        }
+        HealthProbe::Readyz => "/readyz",
+        HealthProbe::Get(path) => path.as_str(),
+    };
+    client
+        .request_text(http::Request::get(path).body(vec![])?)
+        .await?;
+    Ok(match probe {
+        HealthProbe::Readyz => "ready".to_string(),
+        _ => "GET ok".to_string(),
+    })
+}
+
+const DNS_DETAILS: &str = " (DNS: ";
+
+// Split-horizon DNS and VPNs are behind most unreachable clusters, so connection failures say
+// what the server's name resolved to. Proxies resolve names themselves and are left alone.
+async fn with_dns_details(error: BoxError, kubeconfig: &Kubeconfig, name: &str) -> BoxError {
+    if !matches!(
+        FailureKind::classify(&error.to_string()),
+        FailureKind::Unreachable | FailureKind::Other
+    ) {
+        return error;
+    }
+    let options = KubeConfigOptions {
+        context: Some(name.to_string()),
+        cluster: None,
+        user: None,
+    };
+    let Ok(config) = Config::from_custom_kubeconfig(kubeconfig.clone(), &options).await else {
+        return error;
+    };
+    let Ok((host, port, _)) = server_endpoint(&config) else {
+        return error;
+    };
+    if config.proxy_url.is_some() || host.parse::<IpAddr>().is_ok() {
+        return error;
+    }
+    let lookup = tokio::time::timeout(
+        PRECHECK_TIMEOUT,
+        tokio::net::lookup_host((host.as_str(), port)),
+    )
+    .await;
+    let details = match lookup {
+        Ok(Ok(addresses)) => {
+            let mut ips: Vec<String> = addresses.map(|a| a.ip().to_string()).collect();
+            ips.dedup();
+            format!("{} resolves to {}", host, ips.join(", "))
+        }
+        Ok(Err(e)) => format!("{} doesn't resolve: {}", host, e),
+        Err(_) => format!("resolving {} timed out", host),
+    };
+    format!("{}{}{})", error, DNS_DETAILS, details).into()
+}
+
+// The error and the DNS results appended to it, if any
+pub fn split_dns_details(error: &str) -> (&str, Option<&str>) {
+    match error.split_once(DNS_DETAILS) {
+        Some((error, details)) => (error, Some(details.strip_suffix(')').unwrap_or(details))),
+        None => (error, None),
+    }
+}
+
+// Certificates expiring sooner than this are flagged in the UI
+pub const CERT_EXPIRY_WARNING_DAYS: i64 = 30;
+const CERT_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Reads the certificate the API server presents, without verifying it, since only the expiry
+// matters here. Proxied clusters aren't reachable directly and are skipped.
+pub async fn server_cert_expiry(
+    kubeconfig: &Kubeconfig,
+    name: &str,
+) -> Result<DateTime<Utc>, BoxError> {
+    let options = KubeConfigOptions {
+        context: Some(name.to_string()),
+        cluster: None,
+        user: None,
+    };
+    let config = Config::from_custom_kubeconfig(kubeconfig.clone(), &options).await?;
+    if config.proxy_url.is_some() {
+        return Err("Certificate checks aren't supported through a proxy".into());
+    }
+    if config.cluster_url.scheme_str() != Some("https") {
+        return Err(format!("{} doesn't use TLS", config.cluster_url).into());
+    }
+    let (host, port, server_name) = server_endpoint(&config)?;
+    tokio::task::spawn_blocking(move || {
+        let address = (host.as_str(), port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format!("Unable to resolve {}", host))?;
+        let stream = TcpStream::connect_timeout(&address, CERT_CHECK_TIMEOUT)?;
+        stream.set_read_timeout(Some(CERT_CHECK_TIMEOUT))?;
+        stream.set_write_timeout(Some(CERT_CHECK_TIMEOUT))?;
+        let mut connector = SslConnector::builder(SslMethod::tls_client())?;
+        connector.set_verify(SslVerifyMode::NONE);
+        let stream = connector
+            .build()
+            .configure()?
+            .verify_hostname(false)
+            .connect(&server_name, stream)
+            .map_err(|e| format!("TLS handshake with {} failed: {}", server_name, e))?;
+        let certificate = stream
+            .ssl()
+            .peer_certificate()
+            .ok_or("The server didn't present a certificate")?;
+        let diff = Asn1Time::from_unix(0)?.diff(certificate.not_after())?;
+        Utc.timestamp_opt(diff.days as i64 * 86400 + diff.secs as i64, 0)
+            .single()
+            .ok_or_else(|| "Invalid certificate expiry".into())
+    })
+    .await?
+}
+
+// Host, port and the name the server's certificate should be issued for
+fn server_endpoint(config: &Config) -> Result<(String, u16, String), BoxError> {
+    let host = config
+        .cluster_url
+        .host()
+        .ok_or("The server URL has no host")?
+        .trim_matches(|c| c == '[' || c == ']')
+        .to_string();
+    let default_port = match config.cluster_url.scheme_str() {
+        Some("http") => 80,
+        _ => 443,
+    };
+    let port = config.cluster_url.port_u16().unwrap_or(default_port);
+    let server_name = config
+        .tls_server_name
+        .clone()
+        .unwrap_or_else(|| host.clone());
+    Ok((host, port, server_name))
+}
+
+const PRECHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// A cheap TCP connect and TLS handshake before the API probe, so a dead endpoint fails fast and
+// network, certificate and credential problems can be told apart. Proxied clusters go through the
+// full probe only.
+pub async fn precheck(
+    kubeconfig: &Kubeconfig,
+    name: &str,
+    family: IpFamily,
+) -> Result<(), BoxError> {
+    let options = KubeConfigOptions {
+        context: Some(name.to_string()),
+        cluster: None,
+        user: None,
+    };
+    let config = Config::from_custom_kubeconfig(kubeconfig.clone(), &options).await?;
+    if config.proxy_url.is_some() {
+        return Ok(());
+    }
+    let tls = config.cluster_url.scheme_str() == Some("https");
+    let (host, port, server_name) = server_endpoint(&config)?;
+    let roots = config.root_cert.clone().unwrap_or_default();
+    let verify = !config.accept_invalid_certs;
+    tokio::task::spawn_blocking(move || {
+        let address = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Network unreachable: can't resolve {}: {}", host, e))?
+            .find(|address| family.allows(&address.ip()))
+            .ok_or_else(|| match family {
+                IpFamily::Auto => format!("Network unreachable: can't resolve {}", host),
+                IpFamily::Ipv4 => format!("Network unreachable: {} has no IPv4 address", host),
+                IpFamily::Ipv6 => format!("Network unreachable: {} has no IPv6 address", host),
+            })?;
+        let stream = TcpStream::connect_timeout(&address, PRECHECK_TIMEOUT)
+            .map_err(|e| format!("Network unreachable: {}:{}: {}", host, port, e))?;
+        if !tls {
+            return Ok(());
+        }
+        stream.set_read_timeout(Some(PRECHECK_TIMEOUT))?;
+        stream.set_write_timeout(Some(PRECHECK_TIMEOUT))?;
+        let mut connector = SslConnector::builder(SslMethod::tls_client())?;
+        if verify {
+            for der in &roots {
+                connector.cert_store_mut().add_cert(X509::from_der(der)?)?;
+            }
+        } else {
+            connector.set_verify(SslVerifyMode::NONE);
+        }
+        connector
+            .build()
+            .configure()?
+            .verify_hostname(verify)
+            .connect(&server_name, stream)
+            .map_err(|e| format!("TLS error: {}", e))?;
+        Ok(())
+    })
+    .await?
+}
+
+pub fn cert_expires_soon(expiry: &DateTime<Utc>) -> bool {
+    *expiry - Utc::now() < chrono::Duration::days(CERT_EXPIRY_WARNING_DAYS)
+}
+
+// Coarse human readable time until expiry, e.g. "expires in 12d" or "expired 3d ago"
+pub fn format_cert_expiry(expiry: &DateTime<Utc>) -> String {
+    let days = (*expiry - Utc::now()).num_days();
+    if *expiry < Utc::now() {
+        format!("expired {}d ago", -days)
+    } else {
+        format!("expires in {}d", days)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureKind {
+    MissingPlugin,
+    InteractiveAuth,
+    Tls,
+    AuthExpired,
+    Unreachable,
+    Other,
+}
+
+impl FailureKind {
+    // Guessed from the error text, kube and the exec plugins don't give anything more structured
+    pub fn classify(error: &str) -> Self {
+        let error = split_dns_details(error).0.to_lowercase();
+        let auth = [
+            "401",
+            "unauthorized",
+            "expired",
+            "token",
+            "credentials",
+            "reauthenticate",
+            "sso session",
+            "login",
+        ];
+        let unreachable = [
+            "timed out",
+            "timeout",
+            "connection refused",
+            "connection reset",
+            "no route to host",
+            "dns error",
+            "failed to lookup address",
+            "unreachable",
+        ];
+        let tls = [
+            "tls error",
+            "certificate verify failed",
+            "ssl routines",
+            "handshake",
+        ];
+        // Worded by plugins::missing_plugin and precheck, checked first since hostnames and
+        // certificate errors can contain the auth keywords
+        let interactive = [
+            "interactive auth required",
+            "interactive mode",
+            "device code",
+            "devicelogin",
+            "web browser",
+            "open the following url",
+            "not a terminal",
+            "no tty",
+            "prompts are disabled",
+        ];
+        if error.contains("isn't installed") {
+            FailureKind::MissingPlugin
+        } else if interactive.iter().any(|s| error.contains(s)) {
+            FailureKind::InteractiveAuth
+        } else if error.starts_with("network unreachable") {
+            FailureKind::Unreachable
+        } else if tls.iter().any(|s| error.contains(s)) {
+            FailureKind::Tls
+        } else if auth.iter().any(|s| error.contains(s)) {
+            FailureKind::AuthExpired
+        } else if unreachable.iter().any(|s| error.contains(s)) {
+            FailureKind::Unreachable
+        } else {
+            FailureKind::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureKind::MissingPlugin => "Plugin missing",
+            FailureKind::InteractiveAuth => "Interactive auth required",
+            FailureKind::Tls => "TLS error",
+            FailureKind::AuthExpired => "Credentials expired",
+            FailureKind::Unreachable => "Unreachable",
+            FailureKind::Other => "Unhealthy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyStats {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+}
+
+// Times sequential round-trips over one connection, so connection setup and exec plugins
+// don't skew the numbers
+pub const BENCHMARK_TIMEOUT: Duration = Duration::from_secs(30);
+pub const BENCHMARK_CONCURRENCY: usize = 4;
+
+pub async fn measure_latency(
+    kubeconfig: &Kubeconfig,
+    name: &str,
+    family: IpFamily,
+    rounds: u32,
+) -> Result<LatencyStats, BoxError> {
+    let client = client_for_context(kubeconfig, name, family).await?;
+    client.apiserver_version().await?;
+    let mut samples = vec![];
+    for _ in 0..rounds.max(1) {
+        let started = Instant::now();
+        client.apiserver_version().await?;
+        samples.push(started.elapsed());
+    }
+    Ok(LatencyStats {
+        min: *samples.iter().min().unwrap(),
+        avg: samples.iter().sum::<Duration>() / samples.len() as u32,
+        max: *samples.iter().max().unwrap(),
+    })
+}
+
+pub fn format_millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+// Errors are kept as strings so results can be cloned around the UI
+pub type LatencyResult = Result<LatencyStats, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencySort {
+    Name,
+    Min,
+    Avg,
+    Max,
+}
+
+impl LatencySort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Min,
+            Self::Min => Self::Avg,
+            Self::Avg => Self::Max,
+            Self::Max => Self::Name,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Self::Name, Self::Min, Self::Avg, Self::Max]
+            .into_iter()
+            .find(|sort| sort.label() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Min => "min",
+            Self::Avg => "avg",
+            Self::Max => "max",
+        }
+    }
+
+    // Measured contexts come first, then failed ones, then the ones still being measured
+    pub fn sort(self, results: &mut [(String, Option<LatencyResult>)]) {
+        let key = |result: &Option<LatencyResult>| match result {
+            Some(Ok(stats)) => (
+                0,
+                match self {
+                    Self::Name => Duration::ZERO,
+                    Self::Min => stats.min,
+                    Self::Avg => stats.avg,
+                    Self::Max => stats.max,
+                },
+            ),
+            Some(Err(_)) => (1, Duration::ZERO),
+            None => (2, Duration::ZERO),
+        };
+        results.sort_by(|(a_name, a), (b_name, b)| {
+            key(a).cmp(&key(b)).then_with(|| a_name.cmp(b_name))
+        });
+    }
+}
+
+// Bounded by BENCHMARK_TIMEOUT, with the error flattened for display
+pub async fn benchmark(
+    kubeconfig: &Kubeconfig,
+    name: &str,
+    family: IpFamily,
+    rounds: u32,
+) -> LatencyResult {
+    tokio::time::timeout(
+        BENCHMARK_TIMEOUT,
+        measure_latency(kubeconfig, name, family, rounds),
+    )
+    .await
+    .map_err(|_| "timed out".to_string())
+    .and_then(|r| r.map_err(|e| e.to_string()))
+}
+
+fn platform_from_provider_id(provider_id: &str) -> String {
+    match provider_id.split("://").next().unwrap_or("") {
+        "aws" => "AWS",
+        "gce" => "GCP",
+        "azure" => "Azure",
+        "kind" => "kind",
+        "" => "unknown",
+        other => other,
+    }
+    .to_string()
+}
+
+pub async fn fetch_cluster_info(client: Client) -> Result<ClusterInfo, BoxError> {
+    let nodes = Api::<Node>::all(client.clone())
+        .list(&ListParams::default())
+        .await?;
+    let namespaces = Api::<Namespace>::all(client)
+        .list(&ListParams::default())
+        .await?;
+    let node_versions: BTreeSet<String> = nodes
+        .items
+        .iter()
+        .filter_map(|n| n.status.as_ref()?.node_info.as_ref())
+        .map(|info| info.kubelet_version.clone())
+        .collect();
+    let platform = nodes
+        .items
+        .iter()
+        .find_map(|n| n.spec.as_ref()?.provider_id.clone())
+        .map(|id| platform_from_provider_id(&id))
+        .unwrap_or_else(|| "unknown".to_string());
+    Ok(ClusterInfo {
+        node_count: nodes.items.len(),
+        node_versions: node_versions.into_iter().collect(),
+        platform,
+        namespace_count: namespaces.items.len(),
+    })
+}
+
+pub async fn list_namespaces(client: Client) -> Result<Vec<(String, String)>, BoxError> {
+    let namespaces = Api::<Namespace>::all(client)
+        .list(&ListParams::default())
+        .await?;
+    Ok(namespaces
+        .items
+        .into_iter()
+        .map(|ns| {
+            let phase = ns
+                .status
+                .and_then(|s| s.phase)
+                .unwrap_or_else(|| "Unknown".to_string());
+            (ns.metadata.name.unwrap_or_default(), phase)
+        })
+        .collect())
+}
+
+// Requested lifetime of generated service account tokens, the API server may cap it lower
+pub const SERVICE_ACCOUNT_TOKEN_LIFETIME: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+// Creates the service account unless it exists and requests a token for it
+pub async fn service_account_token(
+    client: Client,
+    namespace: &str,
+    name: &str,
+) -> Result<String, BoxError> {
+    let service_accounts = Api::<ServiceAccount>::namespaced(client, namespace);
+    if service_accounts.get_opt(name).await?.is_none() {
+        let service_account = ServiceAccount {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        service_accounts
+            .create(&PostParams::default(), &service_account)
+            .await?;
+    }
+    let request = TokenRequest {
+        spec: TokenRequestSpec {
+            expiration_seconds: Some(SERVICE_ACCOUNT_TOKEN_LIFETIME.as_secs() as i64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let response = service_accounts
+        .create_token_request(name, &PostParams::default(), &request)
+        .await?;
+    let token = response
+        .status
+        .map(|status| status.token)
+        .ok_or("The API server returned no token")?;
+    Ok(token)
+}
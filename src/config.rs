@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::ephemeral;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconMode {
+    #[default]
+    None,
+    Ascii,
+    NerdFont,
+}
+
+// Address family used to reach API servers in health checks, for networks that publish AAAA
+// records without routing IPv6
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpFamily {
+    // Whatever the resolver returns, in its order
+    #[default]
+    Auto,
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    pub fn allows(self, address: &IpAddr) -> bool {
+        match self {
+            IpFamily::Auto => true,
+            IpFamily::Ipv4 => address.is_ipv4(),
+            IpFamily::Ipv6 => address.is_ipv6(),
+        }
+    }
+
+    // Binding to the unspecified address of a family limits connections to it
+    pub fn local_address(self) -> Option<IpAddr> {
+        match self {
+            IpFamily::Auto => None,
+            IpFamily::Ipv4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpFamily::Ipv6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+// Request a health check makes once the API server is reachable. Users whose RBAC doesn't allow
+// reading /version can probe /readyz or a GET of something they can read instead.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum HealthProbe {
+    #[default]
+    Version,
+    Readyz,
+    // Path and query, e.g. /api/v1/namespaces/team-a/pods?limit=1
+    Get(String),
+}
+
+// Anything else is most likely a typo, which would leave every context Unhealthy
+impl TryFrom<String> for HealthProbe {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "version" => Ok(HealthProbe::Version),
+            "readyz" => Ok(HealthProbe::Readyz),
+            path if path.starts_with('/') => Ok(HealthProbe::Get(value)),
+            _ => Err(format!(
+                "health probes are version, readyz or a path starting with /, got {}",
+                value
+            )),
+        }
+    }
+}
+
+// What 1-9 jump to in the context list. Off keeps digits as vim-style counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuickSwitch {
+    #[default]
+    Off,
+    Recent,
+    Pinned,
+}
+
+// What happens to the namespace of a context being switched to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NamespaceOnSwitch {
+    // Whatever the kubeconfig says
+    #[default]
+    Keep,
+    // The namespace last picked for the context
+    Remember,
+    // Pick one from the cluster's namespaces before switching
+    Prompt,
+}
+
+// What happens when an import sets current-context to another context, which cloud CLIs do for
+// the cluster they just wrote
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportCurrentContext {
+    // The current context stays as it is
+    #[default]
+    Keep,
+    // Ask whether to switch once the import is merged
+    Prompt,
+    // Follow the import like kubectl would
+    Switch,
+}
+
+// Tools that hand out AWS credentials to a single command, for profiles without static keys
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AwsCredentialsWrapper {
+    // aws-vault exec PROFILE -- aws ...
+    AwsVault,
+    // assume PROFILE --exec "aws ..."
+    Granted,
+}
+
+// Narrows down what the import wizard scans. Context name templates can use {cluster}, {account}
+// and {location}.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AwsConfig {
+    // Offered instead of every profile `aws configure list-profiles` finds
+    pub profiles: Vec<String>,
+    // Offered instead of asking EC2 for every region
+    pub regions: Vec<String>,
+    // Role the token command assumes, by profile
+    pub role_arns: HashMap<String, String>,
+    pub context_name: Option<String>,
+    // Runs listing calls and the token command of imported contexts through aws-vault or granted
+    pub credentials_wrapper: Option<AwsCredentialsWrapper>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GcpConfig {
+    // Only these project ids are offered when set
+    pub projects: Vec<String>,
+    pub context_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AzureConfig {
+    // Subscription ids or names that are never offered
+    pub exclude_subscriptions: Vec<String>,
+    pub context_name: Option<String>,
+    // kubelogin login mode imported AAD clusters are converted to, e.g. azurecli so they work
+    // without a browser. Unset keeps what az returns.
+    pub kubelogin_mode: Option<String>,
+}
+
+// A team's standard way of importing the clusters of one account, offered at the root of the
+// import wizard. Its role and context name template win over the provider sections.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportPreset {
+    pub name: String,
+    // aws, gcp or azure
+    pub provider: String,
+    // AWS profile, GCP project or Azure subscription id
+    pub account: String,
+    // AWS only
+    pub region: Option<String>,
+    pub role_arn: Option<String>,
+    pub context_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KtxConfig {
+    // Disables provider detection, imports and connectivity tests
+    pub offline: bool,
+    // Command spawned by the shell action instead of $SHELL, e.g. "k9s"
+    pub shell_command: Option<String>,
+    // Prepended to PS1 in spawned shells, {context} is replaced with the context name
+    pub shell_prompt: Option<String>,
+    // Provider and health markers in front of context names
+    pub icons: IconMode,
+    // Start with the details pane next to the context list
+    pub split_pane: bool,
+    // Number of items PageUp/PageDown move by
+    pub page_size: usize,
+    // Moving past the last item selects the first one and vice versa
+    pub wrap_around: bool,
+    // Writes a log next to the config file when set, e.g. "debug" or "ktx=trace"
+    pub log_level: Option<String>,
+    // Seconds to keep polling newly imported clusters until they respond, 0 disables it
+    pub import_wait_timeout: u64,
+    // Ping the cluster before switching to it and ask for confirmation when it's unreachable
+    pub check_before_switch: bool,
+    // Switches, deletions, renames and imports are appended here when set
+    pub audit_log: Option<String>,
+    // Team kubeconfig layered under the personal one, its contexts can be used but not edited
+    pub shared_kubeconfig: Option<String>,
+    // Switch with a single digit to recently used or pinned contexts
+    pub quick_switch: QuickSwitch,
+    // Seconds between background connectivity checks, 0 only checks on demand
+    pub health_poll_interval: u64,
+    pub namespace_on_switch: NamespaceOnSwitch,
+    // Folder of kubeconfig files offered as an import source when it exists
+    pub import_dir: String,
+    // Seconds the import wizard reuses what a listing returned, 0 lists every time
+    pub import_cache_ttl: u64,
+    // Show the entries a merge would add or replace and ask before writing them
+    pub preview_merges: bool,
+    pub import_current_context: ImportCurrentContext,
+    // Put current-context back after imports, whatever the cloud CLIs or other tools wrote meanwhile
+    pub keep_current_context: bool,
+    // Restore the filter and selection of the context list and import wizard views when they open
+    pub remember_filters: bool,
+    // Reopen the views that were open on exit, with their selection, sort and scroll position
+    pub restore_session: bool,
+    // Contexts of short-lived clusters, e.g. CI preview environments, by name with * wildcards
+    pub ephemeral_name_patterns: Vec<String>,
+    // Cloud tags or labels marking short-lived clusters, as key or key=value
+    pub ephemeral_tags: Vec<String>,
+    // Cloud tag or label keys copied into the ktx tags of imported contexts, * copies all of them
+    pub copy_import_tags: Vec<String>,
+    pub ip_family: IpFamily,
+    // Contexts that need another address family than ip_family, by name
+    pub ip_family_overrides: HashMap<String, IpFamily>,
+    pub health_probe: HealthProbe,
+    // Contexts left out of connectivity sweeps by name with * wildcards, e.g. ones whose exec
+    // plugin opens a browser for MFA
+    pub health_check_exclude: Vec<String>,
+    // Contexts probed differently than health_probe, by name
+    pub health_probe_overrides: HashMap<String, HealthProbe>,
+    pub import_presets: Vec<ImportPreset>,
+    pub aws: AwsConfig,
+    pub gcp: GcpConfig,
+    pub azure: AzureConfig,
+}
+
+impl Default for KtxConfig {
+    fn default() -> Self {
+        Self {
+            offline: false,
+            shell_command: None,
+            shell_prompt: None,
+            icons: IconMode::default(),
+            split_pane: false,
+            page_size: 10,
+            wrap_around: false,
+            log_level: None,
+            import_wait_timeout: 0,
+            check_before_switch: false,
+            audit_log: None,
+            shared_kubeconfig: None,
+            quick_switch: QuickSwitch::default(),
+            health_poll_interval: 0,
+            namespace_on_switch: NamespaceOnSwitch::default(),
+            import_dir: shellexpand::tilde("~/.kube/configs").into_owned(),
+            import_cache_ttl: 300,
+            preview_merges: true,
+            import_current_context: ImportCurrentContext::default(),
+            keep_current_context: true,
+            remember_filters: false,
+            restore_session: false,
+            ephemeral_name_patterns: ["pr-*", "*-pr-*", "preview-*", "*-preview-*", "review-*"]
+                .map(String::from)
+                .to_vec(),
+            ephemeral_tags: ["ephemeral", "preview", "environment=preview"]
+                .map(String::from)
+                .to_vec(),
+            copy_import_tags: vec![],
+            ip_family: IpFamily::default(),
+            ip_family_overrides: HashMap::new(),
+            health_probe: HealthProbe::default(),
+            health_check_exclude: vec![],
+            health_probe_overrides: HashMap::new(),
+            import_presets: vec![],
+            aws: AwsConfig::default(),
+            gcp: GcpConfig::default(),
+            azure: AzureConfig::default(),
+        }
+    }
+}
+
+impl KtxConfig {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        let mut config: Self = serde_yaml::from_str(&content)?;
+        config.audit_log = config
+            .audit_log
+            .map(|path| shellexpand::tilde(&path).into_owned());
+        config.shared_kubeconfig = config
+            .shared_kubeconfig
+            .map(|path| shellexpand::tilde(&path).into_owned());
+        config.import_dir = shellexpand::tilde(&config.import_dir).into_owned();
+        Ok(config)
+    }
+
+    pub fn import_preset(&self, name: &str) -> Option<&ImportPreset> {
+        self.import_presets
+            .iter()
+            .find(|preset| preset.name == name)
+    }
+
+    pub fn ip_family_for(&self, context: &str) -> IpFamily {
+        self.ip_family_overrides
+            .get(context)
+            .copied()
+            .unwrap_or(self.ip_family)
+    }
+
+    // Restoring the session brings back each view's filter and selection too
+    pub fn remembers_views(&self) -> bool {
+        self.remember_filters || self.restore_session
+    }
+
+    // Sweeps skip these, checking a single context on request still works
+    pub fn is_excluded_from_sweeps(&self, context: &str) -> bool {
+        self.health_check_exclude
+            .iter()
+            .any(|pattern| ephemeral::wildcard_match(pattern, context))
+    }
+
+    pub fn health_probe_for(&self, context: &str) -> HealthProbe {
+        self.health_probe_overrides
+            .get(context)
+            .cloned()
+            .unwrap_or_else(|| self.health_probe.clone())
+    }
+
+    // What imports through the preset run with, unknown presets leave the config as it is
+    pub fn for_preset(&self, name: Option<&str>) -> Self {
+        let mut config = self.clone();
+        let Some(preset) = name.and_then(|name| self.import_preset(name)) else {
+            return config;
+        };
+        let context_name = preset.context_name.clone();
+        match preset.provider.as_str() {
+            "aws" => {
+                if let Some(role_arn) = &preset.role_arn {
+                    config
+                        .aws
+                        .role_arns
+                        .insert(preset.account.clone(), role_arn.clone());
+                }
+                config.aws.context_name = context_name.or(config.aws.context_name);
+            }
+            "gcp" => config.gcp.context_name = context_name.or(config.gcp.context_name),
+            "azure" => config.azure.context_name = context_name.or(config.azure.context_name),
+            _ => {}
+        }
+        config
+    }
+
+    // KTX_* variables take precedence over the config file, command line flags over both
+    pub fn apply_env(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(value) = env_var("KTX_OFFLINE") {
+            self.offline = parse_env_bool("KTX_OFFLINE", &value)?;
+        }
+        if let Some(value) = env_var("KTX_SHELL_COMMAND") {
+            self.shell_command = Some(value);
+        }
+        if let Some(value) = env_var("KTX_SHELL_PROMPT") {
+            self.shell_prompt = Some(value);
+        }
+        if let Some(value) = env_var("KTX_ICONS") {
+            self.icons = serde_yaml::from_str(&value).map_err(|_| {
+                format!("KTX_ICONS must be none, ascii or nerd-font, got {}", value)
+            })?;
+        }
+        if let Some(value) = env_var("KTX_QUICK_SWITCH") {
+            self.quick_switch = serde_yaml::from_str(&value).map_err(|_| {
+                format!(
+                    "KTX_QUICK_SWITCH must be off, recent or pinned, got {}",
+                    value
+                )
+            })?;
+        }
+        if let Some(value) = env_var("KTX_NAMESPACE_ON_SWITCH") {
+            self.namespace_on_switch = serde_yaml::from_str(&value).map_err(|_| {
+                format!(
+                    "KTX_NAMESPACE_ON_SWITCH must be keep, remember or prompt, got {}",
+                    value
+                )
+            })?;
+        }
+        if let Some(value) = env_var("KTX_IMPORT_CURRENT_CONTEXT") {
+            self.import_current_context = serde_yaml::from_str(&value).map_err(|_| {
+                format!(
+                    "KTX_IMPORT_CURRENT_CONTEXT must be keep, prompt or switch, got {}",
+                    value
+                )
+            })?;
+        }
+        if let Some(value) = env_var("KTX_KEEP_CURRENT_CONTEXT") {
+            self.keep_current_context = parse_env_bool("KTX_KEEP_CURRENT_CONTEXT", &value)?;
+        }
+        if let Some(value) = env_var("KTX_REMEMBER_FILTERS") {
+            self.remember_filters = parse_env_bool("KTX_REMEMBER_FILTERS", &value)?;
+        }
+        if let Some(value) = env_var("KTX_RESTORE_SESSION") {
+            self.restore_session = parse_env_bool("KTX_RESTORE_SESSION", &value)?;
+        }
+        if let Some(value) = env_var("KTX_EPHEMERAL_NAME_PATTERNS") {
+            self.ephemeral_name_patterns = parse_env_list(&value);
+        }
+        if let Some(value) = env_var("KTX_EPHEMERAL_TAGS") {
+            self.ephemeral_tags = parse_env_list(&value);
+        }
+        if let Some(value) = env_var("KTX_COPY_IMPORT_TAGS") {
+            self.copy_import_tags = parse_env_list(&value);
+        }
+        if let Some(value) = env_var("KTX_IP_FAMILY") {
+            self.ip_family = parse_ip_family("KTX_IP_FAMILY", &value)?;
+        }
+        if let Some(value) = env_var("KTX_IP_FAMILY_OVERRIDES") {
+            self.ip_family_overrides =
+                parse_env_overrides("KTX_IP_FAMILY_OVERRIDES", &value, |family| {
+                    parse_ip_family("KTX_IP_FAMILY_OVERRIDES", family)
+                })?;
+        }
+        if let Some(value) = env_var("KTX_HEALTH_PROBE") {
+            self.health_probe = parse_health_probe("KTX_HEALTH_PROBE", &value)?;
+        }
+        if let Some(value) = env_var("KTX_HEALTH_CHECK_EXCLUDE") {
+            self.health_check_exclude = parse_env_list(&value);
+        }
+        if let Some(value) = env_var("KTX_HEALTH_PROBE_OVERRIDES") {
+            self.health_probe_overrides =
+                parse_env_overrides("KTX_HEALTH_PROBE_OVERRIDES", &value, |probe| {
+                    parse_health_probe("KTX_HEALTH_PROBE_OVERRIDES", probe)
+                })?;
+        }
+        if let Some(value) = env_var("KTX_SPLIT_PANE") {
+            self.split_pane = parse_env_bool("KTX_SPLIT_PANE", &value)?;
+        }
+        if let Some(value) = env_var("KTX_PAGE_SIZE") {
+            self.page_size = value
+                .parse()
+                .map_err(|_| format!("KTX_PAGE_SIZE must be a number, got {}", value))?;
+        }
+        if let Some(value) = env_var("KTX_WRAP_AROUND") {
+            self.wrap_around = parse_env_bool("KTX_WRAP_AROUND", &value)?;
+        }
+        if let Some(value) = env_var("KTX_IMPORT_WAIT_TIMEOUT") {
+            self.import_wait_timeout = value
+                .parse()
+                .map_err(|_| format!("KTX_IMPORT_WAIT_TIMEOUT must be a number, got {}", value))?;
+        }
+        if let Some(value) = env_var("KTX_HEALTH_POLL_INTERVAL") {
+            self.health_poll_interval = value
+                .parse()
+                .map_err(|_| format!("KTX_HEALTH_POLL_INTERVAL must be a number, got {}", value))?;
+        }
+        if let Some(value) = env_var("KTX_IMPORT_CACHE_TTL") {
+            self.import_cache_ttl = value
+                .parse()
+                .map_err(|_| format!("KTX_IMPORT_CACHE_TTL must be a number, got {}", value))?;
+        }
+        if let Some(value) = env_var("KTX_PREVIEW_MERGES") {
+            self.preview_merges = parse_env_bool("KTX_PREVIEW_MERGES", &value)?;
+        }
+        if let Some(value) = env_var("KTX_CHECK_BEFORE_SWITCH") {
+            self.check_before_switch = parse_env_bool("KTX_CHECK_BEFORE_SWITCH", &value)?;
+        }
+        if let Some(value) = env_var("KTX_LOG_LEVEL") {
+            self.log_level = Some(value);
+        }
+        if let Some(value) = env_var("KTX_AUDIT_LOG") {
+            self.audit_log = Some(shellexpand::tilde(&value).into_owned());
+        }
+        if let Some(value) = env_var("KTX_IMPORT_DIR") {
+            self.import_dir = shellexpand::tilde(&value).into_owned();
+        }
+        if let Some(value) = env_var("KTX_SHARED_KUBECONFIG") {
+            self.shared_kubeconfig = Some(shellexpand::tilde(&value).into_owned());
+        }
+        Ok(())
+    }
+}
+
+// Unset and empty variables are treated the same
+pub fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Comma separated, e.g. "pr-*,preview-*"
+fn parse_env_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_env_bool(name: &str, value: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("{} must be true or false, got {}", name, value).into()),
+    }
+}
+
+// Comma separated context=value pairs
+fn parse_env_overrides<T>(
+    name: &str,
+    value: &str,
+    parse: impl Fn(&str) -> Result<T, Box<dyn Error + Send + Sync>>,
+) -> Result<HashMap<String, T>, Box<dyn Error + Send + Sync>> {
+    parse_env_list(value)
+        .iter()
+        .map(|entry| {
+            let (context, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("{} entries must be context=value, got {}", name, entry))?;
+            Ok((context.to_string(), parse(value)?))
+        })
+        .collect()
+}
+
+fn parse_health_probe(
+    name: &str,
+    value: &str,
+) -> Result<HealthProbe, Box<dyn Error + Send + Sync>> {
+    HealthProbe::try_from(value.to_string()).map_err(|_| {
+        format!(
+            "{} must be version, readyz or a path starting with /, got {}",
+            name, value
+        )
+        .into()
+    })
+}
+
+fn parse_ip_family(name: &str, value: &str) -> Result<IpFamily, Box<dyn Error + Send + Sync>> {
+    serde_yaml::from_str(value)
+        .map_err(|_| format!("{} must be auto, ipv4 or ipv6, got {}", name, value).into())
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use super::{HealthProbe, IpFamily, KtxConfig, QuickSwitch};
+
+// The only test setting KTX_* variables, so it doesn't race with others
+#[test]
+fn environment_variables_override_the_config_file() {
+    let vars = [
+        ("KTX_OFFLINE", "yes"),
+        ("KTX_QUICK_SWITCH", "pinned"),
+        ("KTX_HEALTH_POLL_INTERVAL", "30"),
+        ("KTX_HEALTH_CHECK_EXCLUDE", "*-mfa, corp-prod,"),
+        ("KTX_IP_FAMILY_OVERRIDES", "dev=ipv4,edge=ipv6"),
+        // Empty is the same as unset
+        ("KTX_LOG_LEVEL", ""),
+    ];
+    for (name, value) in vars {
+        std::env::set_var(name, value);
+    }
+    let mut config = KtxConfig::default();
+    config.apply_env().unwrap();
+    assert!(config.offline);
+    assert_eq!(config.quick_switch, QuickSwitch::Pinned);
+    assert_eq!(config.health_poll_interval, 30);
+    assert_eq!(config.health_check_exclude, vec!["*-mfa", "corp-prod"]);
+    assert_eq!(
+        config.ip_family_overrides,
+        HashMap::from([
+            ("dev".to_string(), IpFamily::Ipv4),
+            ("edge".to_string(), IpFamily::Ipv6),
+        ])
+    );
+    assert_eq!(config.log_level, None);
+    assert_eq!(config.page_size, KtxConfig::default().page_size);
+
+    for (name, value, error) in [
+        (
+            "KTX_OFFLINE",
+            "maybe",
+            "KTX_OFFLINE must be true or false, got maybe",
+        ),
+        (
+            "KTX_HEALTH_POLL_INTERVAL",
+            "soon",
+            "KTX_HEALTH_POLL_INTERVAL must be a number, got soon",
+        ),
+        (
+            "KTX_IP_FAMILY_OVERRIDES",
+            "dev",
+            "KTX_IP_FAMILY_OVERRIDES entries must be context=value, got dev",
+        ),
+    ] {
+        std::env::set_var(name, value);
+        let error_message = KtxConfig::default().apply_env().unwrap_err().to_string();
+        assert_eq!(error_message, error);
+        std::env::remove_var(name);
+    }
+    for (name, _) in vars {
+        std::env::remove_var(name);
+    }
+}
+
+#[test]
+fn health_probes_must_be_known_or_paths() {
+    let config: KtxConfig = serde_yaml::from_str(
+        "health_probe: readyz\nhealth_probe_overrides:\n  team-a: /api/v1/namespaces/team-a/pods\n",
+    )
+    .unwrap();
+    assert_eq!(config.health_probe, HealthProbe::Readyz);
+    assert_eq!(
+        config.health_probe_for("team-a"),
+        HealthProbe::Get("/api/v1/namespaces/team-a/pods".to_string())
+    );
+    for probe in ["readz", "api/v1/pods"] {
+        let error = serde_yaml::from_str::<KtxConfig>(&format!("health_probe: {}", probe))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("health probes are version, readyz or a path starting with /"));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::config::KtxConfig;
+use crate::metadata::ContextMetadata;
+
+// Tells contexts of short-lived clusters, e.g. CI preview environments, from the long-lived ones.
+// Every detector gets a say, a context is ephemeral as soon as one of them thinks so.
+pub trait EphemeralDetector {
+    fn is_ephemeral(&self, name: &str, metadata: Option<&ContextMetadata>) -> bool;
+}
+
+pub struct NamePatterns(Vec<String>);
+
+impl EphemeralDetector for NamePatterns {
+    fn is_ephemeral(&self, name: &str, _metadata: Option<&ContextMetadata>) -> bool {
+        self.0.iter().any(|pattern| wildcard_match(pattern, name))
+    }
+}
+
+// Matches the tags recorded with the provenance at import time, as key or key=value
+pub struct ProviderTags(Vec<String>);
+
+impl EphemeralDetector for ProviderTags {
+    fn is_ephemeral(&self, _name: &str, metadata: Option<&ContextMetadata>) -> bool {
+        let Some(tags) = metadata
+            .and_then(|m| m.provenance.as_ref())
+            .map(|p| &p.tags)
+        else {
+            return false;
+        };
+        self.0.iter().any(|spec| match spec.split_once('=') {
+            Some((key, value)) => tags.get(key).is_some_and(|v| v.eq_ignore_ascii_case(value)),
+            None => tags.contains_key(spec),
+        })
+    }
+}
+
+pub fn detectors(config: &KtxConfig) -> Vec<Box<dyn EphemeralDetector>> {
+    vec![
+        Box::new(NamePatterns(config.ephemeral_name_patterns.clone())),
+        Box::new(ProviderTags(config.ephemeral_tags.clone())),
+    ]
+}
+
+// Case-insensitive, * matches any run of characters
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.to_lowercase(), text.to_lowercase());
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
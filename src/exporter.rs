@@ -0,0 +1,274 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use kube::config::Kubeconfig;
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::cluster;
+use crate::config::{HealthProbe, IpFamily, KtxConfig};
+use crate::kubeconfig;
+use crate::metadata::KtxMetadata;
+use crate::tunnel::TunnelPool;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const HEALTH_CHECK_CONCURRENCY: usize = 10;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSample {
+    pub context: String,
+    pub healthy: bool,
+    pub version: Option<String>,
+    pub latency_seconds: f64,
+    pub cert_expiry: Option<DateTime<Utc>>,
+    pub checked_at: DateTime<Utc>,
+}
+
+async fn check_context(
+    kubeconfig: &Kubeconfig,
+    metadata: &KtxMetadata,
+    tunnels: &TunnelPool,
+    name: String,
+    family: IpFamily,
+    probe: HealthProbe,
+) -> HealthSample {
+    let checked_at = Utc::now();
+    let started = Instant::now();
+    let result = match tunnels
+        .effective_kubeconfig(kubeconfig, metadata, &name)
+        .await
+    {
+        Ok(kubeconfig) => {
+            match tokio::time::timeout(
+                HEALTH_CHECK_TIMEOUT,
+                cluster::health_check(&kubeconfig, &name, family, &probe),
+            )
+            .await
+            {
+                Ok(Ok(version)) => Some((version, kubeconfig)),
+                _ => None,
+            }
+        }
+        Err(_) => None,
+    };
+    let latency_seconds = started.elapsed().as_secs_f64();
+    let Some((version, kubeconfig)) = result else {
+        return HealthSample {
+            context: name,
+            healthy: false,
+            version: None,
+            latency_seconds,
+            cert_expiry: None,
+            checked_at,
+        };
+    };
+    let cert_expiry = cluster::server_cert_expiry(&kubeconfig, &name).await.ok();
+    HealthSample {
+        context: name,
+        healthy: true,
+        version: Some(version),
+        latency_seconds,
+        cert_expiry,
+        checked_at,
+    }
+}
+
+// The kubeconfig is reloaded every round so added and removed contexts show up without a restart
+async fn check_contexts(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    tunnels: &TunnelPool,
+) -> Result<Vec<HealthSample>, BoxError> {
+    let kubeconfig = kubeconfig::load(kubeconfig_path, config.shared_kubeconfig.as_deref())?.0;
+    let metadata = KtxMetadata::load(metadata_path)?;
+    let names: Vec<String> = kubeconfig
+        .contexts
+        .iter()
+        .map(|c| c.name.clone())
+        .filter(|name| !config.is_excluded_from_sweeps(name))
+        .collect();
+    let (kubeconfig, metadata) = (&kubeconfig, &metadata);
+    Ok(futures::stream::iter(names)
+        .map(|name| {
+            let family = config.ip_family_for(&name);
+            let probe = config.health_probe_for(&name);
+            check_context(kubeconfig, metadata, tunnels, name, family, probe)
+        })
+        .buffered(HEALTH_CHECK_CONCURRENCY)
+        .collect()
+        .await)
+}
+
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Prometheus text exposition format
+pub fn render_metrics(samples: &[HealthSample]) -> String {
+    let mut output = String::new();
+    let mut metric = |name: &str, help: &str, values: Vec<(String, String)>| {
+        output.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} gauge\n",
+            name, help, name
+        ));
+        for (labels, value) in values {
+            output.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+        }
+    };
+    let context = |s: &HealthSample| format!("context=\"{}\"", label_value(&s.context));
+    metric(
+        "ktx_context_up",
+        "Whether the API server of the context answered the last health check.",
+        samples
+            .iter()
+            .map(|s| (context(s), (s.healthy as u8).to_string()))
+            .collect(),
+    );
+    metric(
+        "ktx_context_latency_seconds",
+        "Duration of the last health check.",
+        samples
+            .iter()
+            .map(|s| (context(s), format!("{:.6}", s.latency_seconds)))
+            .collect(),
+    );
+    metric(
+        "ktx_context_info",
+        "Kubernetes version reported by the API server.",
+        samples
+            .iter()
+            .filter_map(|s| {
+                let version = s.version.as_ref()?;
+                Some((
+                    format!("{},version=\"{}\"", context(s), label_value(version)),
+                    "1".to_string(),
+                ))
+            })
+            .collect(),
+    );
+    metric(
+        "ktx_context_cert_expiry_timestamp_seconds",
+        "Expiry of the certificate presented by the API server.",
+        samples
+            .iter()
+            .filter_map(|s| Some((context(s), s.cert_expiry?.timestamp().to_string())))
+            .collect(),
+    );
+    metric(
+        "ktx_context_last_check_timestamp_seconds",
+        "Time of the last health check.",
+        samples
+            .iter()
+            .map(|s| (context(s), s.checked_at.timestamp().to_string()))
+            .collect(),
+    );
+    output
+}
+
+async fn handle_request(
+    request: Request<Body>,
+    samples: Arc<RwLock<Vec<HealthSample>>>,
+) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(render_metrics(&samples.read().await))),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("Not found\n")),
+    };
+    Ok(response.unwrap())
+}
+
+async fn post_webhook(url: &str, samples: &[HealthSample]) -> Result<(), BoxError> {
+    let connector = hyper_openssl::HttpsConnector::new()?;
+    let client: hyper::Client<_, Body> = hyper::Client::builder().build(connector);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_vec(
+            &serde_json::json!({ "contexts": samples }),
+        )?))?;
+    let response = tokio::time::timeout(WEBHOOK_TIMEOUT, client.request(request))
+        .await
+        .map_err(|_| format!("Webhook {} timed out", url))??;
+    if !response.status().is_success() {
+        return Err(format!("Webhook {} responded with {}", url, response.status()).into());
+    }
+    Ok(())
+}
+
+// Checks every context on an interval until interrupted, serving the latest results on /metrics
+// and/or posting each round to a webhook
+pub async fn run(
+    kubeconfig_path: &str,
+    config: &KtxConfig,
+    metadata_path: &str,
+    listen: Option<SocketAddr>,
+    webhook: Option<&str>,
+    interval: Duration,
+) -> Result<(), BoxError> {
+    if config.offline {
+        return Err("The exporter is disabled in offline mode".into());
+    }
+    if listen.is_none() && webhook.is_none() {
+        return Err("Pass --listen, --webhook or both".into());
+    }
+    let samples: Arc<RwLock<Vec<HealthSample>>> = Arc::default();
+    if let Some(address) = listen {
+        let samples = samples.clone();
+        let server = Server::try_bind(&address)?.serve(make_service_fn(move |_| {
+            let samples = samples.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(request, samples.clone())
+                }))
+            }
+        }));
+        eprintln!("Serving metrics on http://{}/metrics", server.local_addr());
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                eprintln!("Metrics server failed: {}", e);
+            }
+        });
+    }
+    let tunnels = TunnelPool::new();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        let round = match check_contexts(kubeconfig_path, config, metadata_path, &tunnels).await {
+            Ok(round) => round,
+            Err(e) => {
+                eprintln!("Unable to check contexts: {}", e);
+                continue;
+            }
+        };
+        if let Some(url) = webhook {
+            if let Err(e) = post_webhook(url, &round).await {
+                eprintln!("{}", e);
+            }
+        }
+        *samples.write().await = round;
+    }
+    tunnels.close_all().await;
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use kube::config::Kubeconfig;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::kubeconfig::{self, same_entry};
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// Older snapshots are pruned past this many
+const HISTORY_LENGTH: usize = 50;
+const HEADER_PREFIX: &str = "# ktx snapshot of ";
+
+// A copy of a kubeconfig file taken right before ktx overwrote it
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub original: String,
+    pub taken_at: DateTime<Utc>,
+}
+
+// Snapshots live next to the metadata file
+pub fn dir(metadata_path: &str) -> PathBuf {
+    Path::new(metadata_path)
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("history")
+}
+
+fn parse_header(content: &str) -> Option<(String, DateTime<Utc>)> {
+    let header = content.lines().next()?.strip_prefix(HEADER_PREFIX)?;
+    let (original, taken_at) = header.rsplit_once(" at ")?;
+    let taken_at = DateTime::parse_from_rfc3339(taken_at).ok()?;
+    Some((original.to_string(), taken_at.with_timezone(&Utc)))
+}
+
+// Newest first
+pub async fn list(history_dir: &Path) -> Result<Vec<Snapshot>, BoxError> {
+    if !history_dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut snapshots = vec![];
+    let mut entries = tokio::fs::read_dir(history_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("yaml") {
+            continue;
+        }
+        let content = tokio::fs::read_to_string(&path).await?;
+        if let Some((original, taken_at)) = parse_header(&content) {
+            snapshots.push(Snapshot {
+                path,
+                original,
+                taken_at,
+            });
+        }
+    }
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.taken_at));
+    Ok(snapshots)
+}
+
+// Keeps the current content of `path` before it gets replaced
+pub async fn snapshot(history_dir: &Path, path: &str, content: &str) -> Result<(), BoxError> {
+    tokio::fs::create_dir_all(history_dir).await?;
+    let taken_at = Utc::now();
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "kubeconfig".to_string());
+    let snapshot_path = history_dir.join(format!(
+        "{}-{}.yaml",
+        taken_at.format("%Y%m%dT%H%M%S%.6fZ"),
+        file_name.trim_end_matches(".yaml")
+    ));
+    let header = format!("{}{} at {}\n", HEADER_PREFIX, path, taken_at.to_rfc3339());
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Snapshots hold credentials just like the kubeconfig itself
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = tokio::fs::OpenOptions::from(options)
+        .open(&snapshot_path)
+        .await?;
+    file.write_all(format!("{}{}", header, content).as_bytes())
+        .await?;
+    file.sync_all().await?;
+
+    for stale in list(history_dir).await?.into_iter().skip(HISTORY_LENGTH) {
+        tokio::fs::remove_file(stale.path).await?;
+    }
+    Ok(())
+}
+
+fn diff_named<T: Serialize>(
+    kind: &str,
+    snapshot: &[T],
+    current: &[T],
+    name: impl Fn(&T) -> &str,
+    changes: &mut Vec<String>,
+) {
+    for entry in snapshot {
+        match current.iter().find(|c| name(c) == name(entry)) {
+            None => changes.push(format!("+ {} {}", kind, name(entry))),
+            Some(c) if !same_entry(c, entry) => changes.push(format!("~ {} {}", kind, name(entry))),
+            _ => {}
+        }
+    }
+    for entry in current {
+        if !snapshot.iter().any(|s| name(s) == name(entry)) {
+            changes.push(format!("- {} {}", kind, name(entry)));
+        }
+    }
+}
+
+// What rolling back to the snapshot would change: + comes back, - goes away, ~ is modified
+pub fn diff(snapshot: &Kubeconfig, current: &Kubeconfig) -> Vec<String> {
+    let mut changes = vec![];
+    if snapshot.current_context != current.current_context {
+        changes.push(format!(
+            "~ current-context {} -> {}",
+            current.current_context.as_deref().unwrap_or("none"),
+            snapshot.current_context.as_deref().unwrap_or("none")
+        ));
+    }
+    diff_named(
+        "context",
+        &snapshot.contexts,
+        &current.contexts,
+        |c| &c.name,
+        &mut changes,
+    );
+    diff_named(
+        "cluster",
+        &snapshot.clusters,
+        &current.clusters,
+        |c| &c.name,
+        &mut changes,
+    );
+    diff_named(
+        "user",
+        &snapshot.auth_infos,
+        &current.auth_infos,
+        |u| &u.name,
+        &mut changes,
+    );
+    changes
+}
+
+pub fn read(snapshot: &Snapshot) -> Result<Kubeconfig, BoxError> {
+    Ok(Kubeconfig::read_from(&snapshot.path)?)
+}
+
+// The current file is snapshotted first, so a rollback can be rolled back too
+pub async fn restore(history_dir: &Path, target: &Snapshot) -> Result<(), BoxError> {
+    let content = tokio::fs::read_to_string(&target.path).await?;
+    let content = content.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+    if let Ok(current) = tokio::fs::read_to_string(&target.original).await {
+        snapshot(history_dir, &target.original, &current).await?;
+    }
+    kubeconfig::write_atomically(&target.original, content).await
+}
@@ -0,0 +1,1482 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+use std::time::SystemTime;
+
+use base64::Engine;
+use kube::config::{
+    AuthInfo, AuthProviderConfig, Cluster, Context, ExecConfig, Kubeconfig, NamedAuthInfo,
+    NamedCluster, NamedContext,
+};
+use secrecy::SecretString;
+use serde::Serialize;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::history;
+
+#[cfg(test)]
+mod tests;
+
+// The sections contexts point into
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntryKind {
+    Cluster,
+    User,
+}
+
+impl EntryKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Cluster => "cluster",
+            EntryKind::User => "user",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictResolution {
+    Overwrite,
+    Skip,
+    RenameWithSuffix,
+}
+
+pub fn same_entry<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+// Returns human-readable descriptions of entries in `incoming` that would replace an existing,
+// different entry with the same name in `existing`.
+pub fn find_conflicts(existing: &Kubeconfig, incoming: &Kubeconfig) -> Vec<String> {
+    let mut conflicts = vec![];
+    for context in &incoming.contexts {
+        if let Some(c) = existing.contexts.iter().find(|c| c.name == context.name) {
+            if !same_entry(c, context) {
+                conflicts.push(format!("context {}", context.name));
+            }
+        }
+    }
+    for cluster in &incoming.clusters {
+        if let Some(c) = existing.clusters.iter().find(|c| c.name == cluster.name) {
+            if !same_entry(c, cluster) {
+                conflicts.push(format!("cluster {}", cluster.name));
+            }
+        }
+    }
+    for user in &incoming.auth_infos {
+        if let Some(u) = existing.auth_infos.iter().find(|u| u.name == user.name) {
+            if !same_entry(u, user) {
+                conflicts.push(format!("user {}", user.name));
+            }
+        }
+    }
+    conflicts
+}
+
+// Flattens an entry to "field" or "section.field" keys, e.g. "cluster.server"
+fn entry_fields<T: Serialize>(entry: &T) -> HashMap<String, serde_json::Value> {
+    let mut fields = HashMap::new();
+    if let Ok(serde_json::Value::Object(object)) = serde_json::to_value(entry) {
+        for (key, value) in object {
+            match value {
+                serde_json::Value::Object(section) => {
+                    for (field, value) in section {
+                        fields.insert(format!("{}.{}", key, field), value);
+                    }
+                }
+                value => {
+                    fields.insert(key, value);
+                }
+            }
+        }
+    }
+    fields
+}
+
+fn preview_section<T: Serialize>(
+    kind: &str,
+    existing: &[T],
+    incoming: &[T],
+    name: impl Fn(&T) -> &str,
+    lines: &mut Vec<String>,
+) {
+    for entry in incoming {
+        match existing.iter().find(|e| name(e) == name(entry)) {
+            None => lines.push(format!("+ {} {}", kind, name(entry))),
+            Some(e) if !same_entry(e, entry) => {
+                let (old, new) = (entry_fields(e), entry_fields(entry));
+                let mut changed: Vec<&str> = old
+                    .keys()
+                    .chain(new.keys())
+                    .filter(|field| old.get(*field) != new.get(*field))
+                    .map(|field| field.rsplit('.').next().unwrap_or(field))
+                    .collect();
+                changed.sort();
+                changed.dedup();
+                lines.push(format!(
+                    "~ {} {} ({})",
+                    kind,
+                    name(entry),
+                    changed.join(", ")
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+// Describes what merging `incoming` with overwrites would change: "+" lines for added entries and
+// "~" lines for replaced ones with the names of their changed fields. Values are left out as they
+// are mostly credentials.
+pub fn merge_preview(existing: &Kubeconfig, incoming: &Kubeconfig) -> Vec<String> {
+    let mut lines = vec![];
+    preview_section(
+        "context",
+        &existing.contexts,
+        &incoming.contexts,
+        |c| &c.name,
+        &mut lines,
+    );
+    preview_section(
+        "cluster",
+        &existing.clusters,
+        &incoming.clusters,
+        |c| &c.name,
+        &mut lines,
+    );
+    preview_section(
+        "user",
+        &existing.auth_infos,
+        &incoming.auth_infos,
+        |u| &u.name,
+        &mut lines,
+    );
+    if let Some(current) = &incoming.current_context {
+        if existing.current_context.as_ref() != Some(current) {
+            lines.push(format!("~ current-context {}", current));
+        }
+    }
+    lines
+}
+
+fn free_name(name: &str, taken: &[String]) -> String {
+    let mut i = 1;
+    loop {
+        let candidate = format!("{}-{}", name, i);
+        if !taken.contains(&candidate) {
+            return candidate;
+        }
+        i += 1;
+    }
+}
+
+// Renames every conflicting entry of `incoming` to the first free `<name>-<n>` and rewrites
+// context references and current-context accordingly.
+fn rename_conflicting(existing: &Kubeconfig, incoming: &mut Kubeconfig) {
+    let mut taken: Vec<String> = existing.clusters.iter().map(|c| c.name.clone()).collect();
+    for cluster in incoming.clusters.iter_mut() {
+        if let Some(c) = existing.clusters.iter().find(|c| c.name == cluster.name) {
+            if !same_entry(c, cluster) {
+                let new_name = free_name(&cluster.name, &taken);
+                taken.push(new_name.clone());
+                for context in incoming.contexts.iter_mut() {
+                    if let Some(ctx) = context.context.as_mut() {
+                        if ctx.cluster == cluster.name {
+                            ctx.cluster = new_name.clone();
+                        }
+                    }
+                }
+                cluster.name = new_name;
+            }
+        }
+    }
+    let mut taken: Vec<String> = existing.auth_infos.iter().map(|u| u.name.clone()).collect();
+    for user in incoming.auth_infos.iter_mut() {
+        if let Some(u) = existing.auth_infos.iter().find(|u| u.name == user.name) {
+            if !same_entry(u, user) {
+                let new_name = free_name(&user.name, &taken);
+                taken.push(new_name.clone());
+                for context in incoming.contexts.iter_mut() {
+                    if let Some(ctx) = context.context.as_mut() {
+                        if ctx.user == user.name {
+                            ctx.user = new_name.clone();
+                        }
+                    }
+                }
+                user.name = new_name;
+            }
+        }
+    }
+    let mut taken: Vec<String> = existing.contexts.iter().map(|c| c.name.clone()).collect();
+    for context in incoming.contexts.iter_mut() {
+        if let Some(c) = existing.contexts.iter().find(|c| c.name == context.name) {
+            if !same_entry(c, context) {
+                let new_name = free_name(&context.name, &taken);
+                taken.push(new_name.clone());
+                if incoming.current_context.as_ref() == Some(&context.name) {
+                    incoming.current_context = Some(new_name.clone());
+                }
+                context.name = new_name;
+            }
+        }
+    }
+}
+
+// Returns the names of the contexts that ended up in the existing kubeconfig. Callers clear
+// incoming's current-context unless the merge should switch to it.
+pub fn merge(
+    existing: &mut Kubeconfig,
+    mut incoming: Kubeconfig,
+    resolution: ConflictResolution,
+) -> Vec<String> {
+    if resolution == ConflictResolution::RenameWithSuffix {
+        rename_conflicting(existing, &mut incoming);
+    }
+    let overwrite = resolution != ConflictResolution::Skip;
+    for cluster in incoming.clusters {
+        match existing
+            .clusters
+            .iter_mut()
+            .find(|c| c.name == cluster.name)
+        {
+            Some(c) if overwrite => *c = cluster,
+            Some(_) => {}
+            None => existing.clusters.push(cluster),
+        }
+    }
+    for user in incoming.auth_infos {
+        match existing.auth_infos.iter_mut().find(|u| u.name == user.name) {
+            Some(u) if overwrite => *u = user,
+            Some(_) => {}
+            None => existing.auth_infos.push(user),
+        }
+    }
+    let mut merged = vec![];
+    for context in incoming.contexts {
+        match existing
+            .contexts
+            .iter_mut()
+            .find(|c| c.name == context.name)
+        {
+            Some(c) if overwrite => {
+                merged.push(context.name.clone());
+                *c = context;
+            }
+            Some(_) => {}
+            None => {
+                merged.push(context.name.clone());
+                existing.contexts.push(context);
+            }
+        }
+    }
+    // Only a context taken from incoming can become current, skipped ones leave it alone
+    if let Some(current) = incoming.current_context.filter(|c| merged.contains(c)) {
+        existing.current_context = Some(current);
+    }
+    merged
+}
+
+pub fn exec_auth(command: &str, args: &[&str], env: &[(&str, &str)]) -> ExecConfig {
+    let env: Vec<HashMap<String, String>> = env
+        .iter()
+        .map(|(name, value)| {
+            HashMap::from([
+                ("name".to_string(), name.to_string()),
+                ("value".to_string(), value.to_string()),
+            ])
+        })
+        .collect();
+    ExecConfig {
+        api_version: Some("client.authentication.k8s.io/v1beta1".to_string()),
+        command: Some(command.to_string()),
+        args: Some(args.iter().map(|a| a.to_string()).collect()),
+        env: if env.is_empty() { None } else { Some(env) },
+        drop_env: None,
+        interactive_mode: None,
+    }
+}
+
+// Builds a kubeconfig holding a single cluster/user/context triple sharing the same name, which
+// is how the cloud CLIs lay out their entries.
+pub fn single_context(
+    name: &str,
+    server: &str,
+    certificate_authority_data: Option<String>,
+    exec: ExecConfig,
+) -> Kubeconfig {
+    Kubeconfig {
+        clusters: vec![NamedCluster {
+            name: name.to_string(),
+            cluster: Some(Cluster {
+                server: Some(server.to_string()),
+                certificate_authority_data,
+                ..Default::default()
+            }),
+        }],
+        auth_infos: vec![NamedAuthInfo {
+            name: name.to_string(),
+            auth_info: Some(AuthInfo {
+                exec: Some(exec),
+                ..Default::default()
+            }),
+        }],
+        contexts: vec![NamedContext {
+            name: name.to_string(),
+            context: Some(Context {
+                cluster: name.to_string(),
+                user: name.to_string(),
+                ..Default::default()
+            }),
+        }],
+        current_context: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+pub fn find_context<'a>(kubeconfig: &'a Kubeconfig, name: &str) -> Option<&'a NamedContext> {
+    kubeconfig.contexts.iter().find(|c| c.name == name)
+}
+
+pub fn context_cluster<'a>(
+    kubeconfig: &'a Kubeconfig,
+    context_name: &str,
+) -> Option<&'a NamedCluster> {
+    let context = find_context(kubeconfig, context_name)?.context.as_ref()?;
+    kubeconfig
+        .clusters
+        .iter()
+        .find(|c| c.name == context.cluster)
+}
+
+pub fn context_user<'a>(
+    kubeconfig: &'a Kubeconfig,
+    context_name: &str,
+) -> Option<&'a NamedAuthInfo> {
+    let context = find_context(kubeconfig, context_name)?.context.as_ref()?;
+    kubeconfig
+        .auth_infos
+        .iter()
+        .find(|u| u.name == context.user)
+}
+
+pub fn context_user_mut<'a>(
+    kubeconfig: &'a mut Kubeconfig,
+    context_name: &str,
+) -> Option<&'a mut AuthInfo> {
+    let user_name = find_context(kubeconfig, context_name)?
+        .context
+        .as_ref()?
+        .user
+        .clone();
+    kubeconfig
+        .auth_infos
+        .iter_mut()
+        .find(|u| u.name == user_name)?
+        .auth_info
+        .as_mut()
+}
+
+pub fn context_cluster_mut<'a>(
+    kubeconfig: &'a mut Kubeconfig,
+    context_name: &str,
+) -> Option<&'a mut Cluster> {
+    let cluster_name = find_context(kubeconfig, context_name)?
+        .context
+        .as_ref()?
+        .cluster
+        .clone();
+    kubeconfig
+        .clusters
+        .iter_mut()
+        .find(|c| c.name == cluster_name)?
+        .cluster
+        .as_mut()
+}
+
+// Returns a standalone kubeconfig holding only the given context together with the cluster and
+// user it references, with current-context pointing at it.
+pub fn extract_context(kubeconfig: &Kubeconfig, name: &str) -> Option<Kubeconfig> {
+    extract_contexts(kubeconfig, &[name])
+}
+
+// Same for a set of contexts. The current context is kept when it's part of the set, otherwise
+// the first one becomes current.
+pub fn extract_contexts(kubeconfig: &Kubeconfig, names: &[&str]) -> Option<Kubeconfig> {
+    let contexts = names
+        .iter()
+        .map(|name| find_context(kubeconfig, name).cloned())
+        .collect::<Option<Vec<NamedContext>>>()?;
+    let referenced = |pick: fn(&Context) -> &String| -> Vec<String> {
+        contexts
+            .iter()
+            .filter_map(|c| c.context.as_ref())
+            .map(|c| pick(c).clone())
+            .collect()
+    };
+    let (cluster_names, user_names) = (referenced(|c| &c.cluster), referenced(|c| &c.user));
+    let current_context = kubeconfig
+        .current_context
+        .clone()
+        .filter(|current| names.contains(&current.as_str()))
+        .or_else(|| names.first().map(|name| name.to_string()));
+    Some(Kubeconfig {
+        clusters: kubeconfig
+            .clusters
+            .iter()
+            .filter(|c| cluster_names.contains(&c.name))
+            .cloned()
+            .collect(),
+        auth_infos: kubeconfig
+            .auth_infos
+            .iter()
+            .filter(|a| user_names.contains(&a.name))
+            .cloned()
+            .collect(),
+        contexts,
+        current_context,
+        preferences: kubeconfig.preferences.clone(),
+        api_version: kubeconfig.api_version.clone(),
+        kind: kubeconfig.kind.clone(),
+        ..Default::default()
+    })
+}
+
+pub fn rename_context(
+    kubeconfig: &mut Kubeconfig,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if new_name.is_empty() {
+        return Err("Context name can't be empty".into());
+    }
+    if old_name != new_name && find_context(kubeconfig, new_name).is_some() {
+        return Err(format!("A context named {} already exists", new_name).into());
+    }
+    let context = kubeconfig
+        .contexts
+        .iter_mut()
+        .find(|c| c.name == old_name)
+        .ok_or(format!("No context named {}", old_name))?;
+    context.name = new_name.to_string();
+    if kubeconfig.current_context.as_deref() == Some(old_name) {
+        kubeconfig.current_context = Some(new_name.to_string());
+    }
+    Ok(())
+}
+
+// The copy shares the cluster, and the user unless another one is given
+pub fn clone_context(
+    kubeconfig: &mut Kubeconfig,
+    name: &str,
+    new_name: &str,
+    namespace: Option<&str>,
+    user: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if new_name.is_empty() {
+        return Err("Context name can't be empty".into());
+    }
+    if find_context(kubeconfig, new_name).is_some() {
+        return Err(format!("A context named {} already exists", new_name).into());
+    }
+    let mut context = find_context(kubeconfig, name)
+        .and_then(|c| c.context.clone())
+        .ok_or(format!("No context named {}", name))?;
+    if let Some(namespace) = namespace {
+        context.namespace = Some(namespace.to_string());
+    }
+    if let Some(user) = user {
+        if !kubeconfig.auth_infos.iter().any(|u| u.name == user) {
+            return Err(format!("No user named {}", user).into());
+        }
+        context.user = user.to_string();
+    }
+    kubeconfig.contexts.push(NamedContext {
+        name: new_name.to_string(),
+        context: Some(context),
+    });
+    Ok(())
+}
+
+pub fn assign_entry(
+    kubeconfig: &mut Kubeconfig,
+    name: &str,
+    kind: EntryKind,
+    entry: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !entry_names(kubeconfig, kind).iter().any(|n| n == entry) {
+        return Err(format!("No {} named {}", kind.label(), entry).into());
+    }
+    let context = kubeconfig
+        .contexts
+        .iter_mut()
+        .find(|c| c.name == name)
+        .and_then(|c| c.context.as_mut())
+        .ok_or(format!("No context named {}", name))?;
+    match kind {
+        EntryKind::Cluster => context.cluster = entry.to_string(),
+        EntryKind::User => context.user = entry.to_string(),
+    }
+    Ok(())
+}
+
+pub fn entry_names(kubeconfig: &Kubeconfig, kind: EntryKind) -> Vec<String> {
+    match kind {
+        EntryKind::Cluster => kubeconfig.clusters.iter().map(|c| c.name.clone()).collect(),
+        EntryKind::User => kubeconfig
+            .auth_infos
+            .iter()
+            .map(|u| u.name.clone())
+            .collect(),
+    }
+}
+
+// Contexts pointing at a cluster or user
+pub fn entry_references(kubeconfig: &Kubeconfig, kind: EntryKind, name: &str) -> Vec<String> {
+    kubeconfig
+        .contexts
+        .iter()
+        .filter(|c| {
+            c.context.as_ref().map(|c| match kind {
+                EntryKind::Cluster => c.cluster.as_str(),
+                EntryKind::User => c.user.as_str(),
+            }) == Some(name)
+        })
+        .map(|c| c.name.clone())
+        .collect()
+}
+
+// Renames a cluster or user along with every context referencing it
+pub fn rename_entry(
+    kubeconfig: &mut Kubeconfig,
+    kind: EntryKind,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if new_name.is_empty() {
+        return Err(format!("The {} name can't be empty", kind.label()).into());
+    }
+    let names = entry_names(kubeconfig, kind);
+    if !names.iter().any(|n| n == old_name) {
+        return Err(format!("No {} named {}", kind.label(), old_name).into());
+    }
+    if old_name != new_name && names.iter().any(|n| n == new_name) {
+        return Err(format!("A {} named {} already exists", kind.label(), new_name).into());
+    }
+    match kind {
+        EntryKind::Cluster => {
+            for cluster in kubeconfig
+                .clusters
+                .iter_mut()
+                .filter(|c| c.name == old_name)
+            {
+                cluster.name = new_name.to_string();
+            }
+        }
+        EntryKind::User => {
+            for user in kubeconfig
+                .auth_infos
+                .iter_mut()
+                .filter(|u| u.name == old_name)
+            {
+                user.name = new_name.to_string();
+            }
+        }
+    }
+    for context in kubeconfig
+        .contexts
+        .iter_mut()
+        .filter_map(|c| c.context.as_mut())
+    {
+        let reference = match kind {
+            EntryKind::Cluster => &mut context.cluster,
+            EntryKind::User => &mut context.user,
+        };
+        if reference == old_name {
+            *reference = new_name.to_string();
+        }
+    }
+    Ok(())
+}
+
+// Refuses to leave contexts pointing at nothing
+pub fn delete_entry(
+    kubeconfig: &mut Kubeconfig,
+    kind: EntryKind,
+    name: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let references = entry_references(kubeconfig, kind, name);
+    if !references.is_empty() {
+        return Err(format!(
+            "The {} {} is still used by {}",
+            kind.label(),
+            name,
+            references.join(", ")
+        )
+        .into());
+    }
+    match kind {
+        EntryKind::Cluster => kubeconfig.clusters.retain(|c| c.name != name),
+        EntryKind::User => kubeconfig.auth_infos.retain(|u| u.name != name),
+    }
+    Ok(())
+}
+
+pub fn check_server_url(url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let uri = url.parse::<http::Uri>()?;
+    if !matches!(uri.scheme_str(), Some("https") | Some("http")) || uri.host().is_none() {
+        return Err(format!("{} is not a valid server URL", url).into());
+    }
+    Ok(())
+}
+
+// A CA given as a file path, PEM or base64 data, as (path, data). Empty means the system roots.
+fn certificate_authority_source(
+    value: &str,
+) -> Result<(Option<String>, Option<String>), Box<dyn Error + Send + Sync>> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    if value.is_empty() {
+        Ok((None, None))
+    } else if value.starts_with("-----BEGIN") {
+        Ok((None, Some(engine.encode(value))))
+    } else if Path::new(value).exists() {
+        Ok((Some(value.to_string()), None))
+    } else if engine.decode(value).is_ok() {
+        Ok((None, Some(value.to_string())))
+    } else {
+        Err(format!("{} is neither a file nor certificate data", value).into())
+    }
+}
+
+const DRAFT_PROMPTS: [&str; 4] = [
+    "Name for the new context, also used for its cluster and user",
+    "API server URL",
+    "CA certificate as a file, PEM or base64 data\n(empty to trust the system roots)",
+    "Authentication: token, cert or exec",
+];
+
+// Answers given to the new context prompts so far, in the order they were asked
+#[derive(Debug, Clone, Default)]
+pub struct ContextDraft {
+    pub answers: Vec<String>,
+}
+
+impl ContextDraft {
+    // None once everything is known
+    pub fn next_prompt(&self) -> Option<&'static str> {
+        let credentials: &[&str] = match self.answers.get(3).map(|a| a.as_str()) {
+            Some("cert") => &["Client certificate file", "Client key file"],
+            Some("exec") => {
+                &["Command printing an ExecCredential, with its arguments,\ne.g. vault-k8s-login --role dev"]
+            }
+            _ => &["Bearer token"],
+        };
+        DRAFT_PROMPTS
+            .iter()
+            .chain(credentials)
+            .nth(self.answers.len())
+            .copied()
+    }
+
+    // Checks the latest answer so a bad one can be asked again right away
+    pub fn check_last_answer(
+        &self,
+        kubeconfig: &Kubeconfig,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(answer) = self.answers.last() else {
+            return Ok(());
+        };
+        match self.answers.len() - 1 {
+            0 if answer.is_empty() => Err("The context name can't be empty".into()),
+            0 if find_context(kubeconfig, answer).is_some()
+                || entry_names(kubeconfig, EntryKind::Cluster).contains(answer)
+                || entry_names(kubeconfig, EntryKind::User).contains(answer) =>
+            {
+                Err(format!("A context, cluster or user named {} already exists", answer).into())
+            }
+            1 => check_server_url(answer),
+            2 => certificate_authority_source(answer).map(|_| ()),
+            3 if !matches!(answer.as_str(), "token" | "cert" | "exec") => {
+                Err("Authentication must be token, cert or exec".into())
+            }
+            4.. if answer.is_empty() => Err("This can't be empty".into()),
+            4.. if self.answers[3] == "cert" && !Path::new(answer).exists() => {
+                Err(format!("{} does not exist", answer).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Appends a cluster, user and context all named after the context. Returns the context name.
+pub fn add_context(
+    kubeconfig: &mut Kubeconfig,
+    draft: &ContextDraft,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let [name, server, certificate_authority, auth, credentials @ ..] = draft.answers.as_slice()
+    else {
+        return Err("The new context is missing answers".into());
+    };
+    let (certificate_authority, certificate_authority_data) =
+        certificate_authority_source(certificate_authority)?;
+    let mut auth_info = AuthInfo::default();
+    match (auth.as_str(), credentials) {
+        ("token", [token]) => auth_info.token = Some(SecretString::new(token.clone())),
+        ("cert", [certificate, key]) => {
+            auth_info.client_certificate = Some(certificate.clone());
+            auth_info.client_key = Some(key.clone());
+        }
+        ("exec", [command_line]) => {
+            let mut words = command_line.split_whitespace();
+            let command = words
+                .next()
+                .ok_or("The credential command can't be empty")?;
+            let args: Vec<&str> = words.collect();
+            auth_info.exec = Some(exec_auth(command, &args, &[]));
+        }
+        _ => return Err("The new context is missing answers".into()),
+    }
+    kubeconfig.clusters.push(NamedCluster {
+        name: name.clone(),
+        cluster: Some(Cluster {
+            server: Some(server.clone()),
+            certificate_authority,
+            certificate_authority_data,
+            ..Default::default()
+        }),
+    });
+    kubeconfig.auth_infos.push(NamedAuthInfo {
+        name: name.clone(),
+        auth_info: Some(auth_info),
+    });
+    kubeconfig.contexts.push(NamedContext {
+        name: name.clone(),
+        context: Some(Context {
+            cluster: name.clone(),
+            user: name.clone(),
+            ..Default::default()
+        }),
+    });
+    Ok(name.clone())
+}
+
+// A context on the same cluster as `source` authenticating as a service account, named
+// {service account}@{source}. Generating it again only replaces the token.
+pub fn add_service_account_context(
+    kubeconfig: &mut Kubeconfig,
+    source: &str,
+    namespace: &str,
+    service_account: &str,
+    token: String,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let cluster = find_context(kubeconfig, source)
+        .and_then(|c| c.context.as_ref())
+        .map(|c| c.cluster.clone())
+        .ok_or(format!("Context {} has no cluster", source))?;
+    let name = format!("{}@{}", service_account, source);
+    let auth_info = AuthInfo {
+        token: Some(SecretString::new(token)),
+        ..Default::default()
+    };
+    match kubeconfig.auth_infos.iter_mut().find(|u| u.name == name) {
+        Some(user) => user.auth_info = Some(auth_info),
+        None => kubeconfig.auth_infos.push(NamedAuthInfo {
+            name: name.clone(),
+            auth_info: Some(auth_info),
+        }),
+    }
+    let context = Context {
+        cluster,
+        user: name.clone(),
+        namespace: Some(namespace.to_string()),
+        ..Default::default()
+    };
+    match kubeconfig.contexts.iter_mut().find(|c| c.name == name) {
+        Some(existing) => existing.context = Some(context),
+        None => kubeconfig.contexts.push(NamedContext {
+            name: name.clone(),
+            context: Some(context),
+        }),
+    }
+    Ok(name)
+}
+
+// Makes the context current, optionally pinning its namespace. Returns false when nothing changed.
+pub fn switch_context(
+    kubeconfig: &mut Kubeconfig,
+    name: &str,
+    namespace: Option<&str>,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let context = kubeconfig
+        .contexts
+        .iter_mut()
+        .find(|c| c.name == name)
+        .ok_or(format!("No context named {}", name))?;
+    let mut changed = false;
+    if let Some(namespace) = namespace {
+        let context = context.context.get_or_insert_with(Default::default);
+        if context.namespace.as_deref() != Some(namespace) {
+            context.namespace = Some(namespace.to_string());
+            changed = true;
+        }
+    }
+    if kubeconfig.current_context.as_deref() != Some(name) {
+        kubeconfig.current_context = Some(name.to_string());
+        changed = true;
+    }
+    Ok(changed)
+}
+
+// Relative references have already been resolved against the kubeconfig directory when reading it
+fn read_base64(path: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let path = shellexpand::tilde(path);
+    let content =
+        std::fs::read(path.as_ref()).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(content))
+}
+
+// Returns a copy with every certificate and key file reference inlined as data, along with the
+// number of files inlined
+pub fn flatten(
+    kubeconfig: &Kubeconfig,
+) -> Result<(Kubeconfig, usize), Box<dyn Error + Send + Sync>> {
+    let mut flattened = kubeconfig.clone();
+    let mut inlined = 0;
+    for cluster in flattened
+        .clusters
+        .iter_mut()
+        .filter_map(|c| c.cluster.as_mut())
+    {
+        if let Some(path) = cluster.certificate_authority.take() {
+            cluster.certificate_authority_data = Some(read_base64(&path)?);
+            inlined += 1;
+        }
+    }
+    for auth_info in flattened
+        .auth_infos
+        .iter_mut()
+        .filter_map(|a| a.auth_info.as_mut())
+    {
+        if let Some(path) = auth_info.client_certificate.take() {
+            auth_info.client_certificate_data = Some(read_base64(&path)?);
+            inlined += 1;
+        }
+        if let Some(path) = auth_info.client_key.take() {
+            auth_info.client_key_data = Some(SecretString::new(read_base64(&path)?));
+            inlined += 1;
+        }
+    }
+    Ok((flattened, inlined))
+}
+
+// The exec plugin replacing an auth-provider removed from kubectl 1.26, if there is one. Azure
+// users get kubelogin's device code login, the same as `kubelogin convert-kubeconfig`.
+fn upgrade_auth_provider(provider: &AuthProviderConfig) -> Option<ExecConfig> {
+    match provider.name.as_str() {
+        "gcp" => Some(exec_auth("gke-gcloud-auth-plugin", &[], &[])),
+        "azure" => {
+            let mut args = vec!["get-token", "--login", "devicecode"];
+            for (flag, key) in [
+                ("--server-id", "apiserver-id"),
+                ("--client-id", "client-id"),
+                ("--tenant-id", "tenant-id"),
+            ] {
+                if let Some(value) = provider.config.get(key) {
+                    args.extend([flag, value.as_str()]);
+                }
+            }
+            let environment = provider
+                .config
+                .get("environment")
+                .map(|e| e.as_str())
+                .unwrap_or("AzurePublicCloud");
+            args.extend(["--environment", environment]);
+            Some(exec_auth("kubelogin", &args, &[]))
+        }
+        _ => None,
+    }
+}
+
+// Rewrites the deprecated auth-provider stanzas of the users `writable` accepts to exec plugins.
+// Returns a preview line per rewritten user, in the style of `merge_preview`.
+pub fn normalize(kubeconfig: &mut Kubeconfig, writable: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut lines = vec![];
+    for user in kubeconfig
+        .auth_infos
+        .iter_mut()
+        .filter(|u| writable(&u.name))
+    {
+        let Some(auth_info) = user.auth_info.as_mut() else {
+            continue;
+        };
+        let Some(provider) = &auth_info.auth_provider else {
+            continue;
+        };
+        let Some(exec) = upgrade_auth_provider(provider) else {
+            continue;
+        };
+        lines.push(format!(
+            "~ user {} ({} auth-provider -> exec {})",
+            user.name,
+            provider.name,
+            exec.command.as_deref().unwrap_or_default()
+        ));
+        auth_info.auth_provider = None;
+        auth_info.exec = Some(exec);
+    }
+    lines
+}
+
+// Like `kubelogin convert-kubeconfig -l MODE`: points kubelogin users at another login mode,
+// upgrading azure auth-providers first. azurecli reuses the az login, so it only keeps the server id.
+// Returns the number of users converted.
+pub fn convert_kubelogin(kubeconfig: &mut Kubeconfig, mode: &str) -> usize {
+    let mut converted = 0;
+    for auth_info in kubeconfig
+        .auth_infos
+        .iter_mut()
+        .filter_map(|u| u.auth_info.as_mut())
+    {
+        if let Some(exec) = auth_info
+            .auth_provider
+            .as_ref()
+            .filter(|p| p.name == "azure")
+            .and_then(upgrade_auth_provider)
+        {
+            auth_info.auth_provider = None;
+            auth_info.exec = Some(exec);
+        }
+        let Some(exec) = auth_info.exec.as_mut() else {
+            continue;
+        };
+        let args = exec.args.clone().unwrap_or_default();
+        if exec.command.as_deref() != Some("kubelogin")
+            || args.first().map(String::as_str) != Some("get-token")
+        {
+            continue;
+        }
+        let mut upgraded = vec![
+            "get-token".to_string(),
+            "--login".to_string(),
+            mode.to_string(),
+        ];
+        let mut rest = args.iter().skip(1);
+        while let Some(flag) = rest.next() {
+            let value = rest.next();
+            let dropped = flag == "--login"
+                || (mode == "azurecli"
+                    && ["--client-id", "--tenant-id", "--environment"].contains(&flag.as_str()));
+            if !dropped {
+                upgraded.push(flag.clone());
+                upgraded.extend(value.cloned());
+            }
+        }
+        exec.args = Some(upgraded);
+        converted += 1;
+    }
+    converted
+}
+
+// Like `kubectl config view --minify --flatten`: just the given contexts, with no file references
+pub fn minify(
+    kubeconfig: &Kubeconfig,
+    names: &[&str],
+) -> Result<Kubeconfig, Box<dyn Error + Send + Sync>> {
+    if let Some(missing) = names.iter().find(|n| find_context(kubeconfig, n).is_none()) {
+        return Err(format!("No context named {}", missing).into());
+    }
+    let extracted = extract_contexts(kubeconfig, names).ok_or("No contexts to minify")?;
+    Ok(flatten(&extracted)?.0)
+}
+
+pub async fn write_atomically(
+    path: &str,
+    content: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = fs::canonicalize(path)
+        .await
+        .unwrap_or_else(|_| Path::new(path).to_path_buf());
+    // Write next to the target and rename over it, so a crash mid-write can't leave a
+    // truncated file behind.
+    let temp_path = path.with_extension("ktx-tmp");
+    let existing = fs::metadata(&path).await.ok();
+    // Left behind by a crash, the new copy has to be created by us
+    let _ = fs::remove_file(&temp_path).await;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Credentials never sit in a file more open than the target, or than the owner for new ones
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(
+        &mut options,
+        existing
+            .as_ref()
+            .map(|m| std::os::unix::fs::PermissionsExt::mode(&m.permissions()) & 0o7777)
+            .unwrap_or(0o600),
+    );
+    let mut file = fs::OpenOptions::from(options).open(&temp_path).await?;
+    file.write_all(content.as_bytes()).await?;
+    file.sync_all().await?;
+    // The umask may have narrowed the mode
+    if let Some(metadata) = existing {
+        fs::set_permissions(&temp_path, metadata.permissions()).await?;
+    }
+    fs::rename(&temp_path, &path).await?;
+    Ok(())
+}
+
+// Unchanged files aren't touched, changed ones are snapshotted first so they can be rolled back
+async fn save(
+    kubeconfig: &Kubeconfig,
+    path: &str,
+    history_dir: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let serialized_kubeconfig = serde_yaml::to_string(kubeconfig)?;
+    if let Ok(existing) = fs::read_to_string(path).await {
+        if existing == serialized_kubeconfig {
+            return Ok(());
+        }
+        history::snapshot(history_dir, path, &existing).await?;
+    }
+    write_atomically(path, &serialized_kubeconfig).await
+}
+
+// For copies holding credentials outside of the main kubeconfig, readable by the owner only.
+// Existing files and symlinks are refused, their permissions or target could expose the copy.
+pub async fn save_private(
+    kubeconfig: &Kubeconfig,
+    path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = fs::OpenOptions::from(options)
+        .open(path)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => format!("{} already exists", path.display()),
+            _ => format!("Unable to create {}: {}", path.display(), e),
+        })?;
+    file.write_all(serde_yaml::to_string(kubeconfig)?.as_bytes())
+        .await?;
+    file.sync_all().await?;
+    Ok(())
+}
+
+// Which file each entry came from when the kubeconfig path lists several files, the way
+// KUBECONFIG does. Entries ktx adds go to the first file, like kubectl.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sources {
+    pub paths: Vec<String>,
+    contexts: HashMap<String, usize>,
+    clusters: HashMap<String, usize>,
+    users: HashMap<String, usize>,
+    // Later definitions of a name, hidden by the merge but kept in their files
+    shadowed: HashSet<(usize, String)>,
+    // The shared team kubeconfig, never written to
+    read_only: Option<usize>,
+    // Modification times of the files when they were read
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl Sources {
+    // Reloading is skipped when no file was touched since it was read
+    pub fn unchanged_on_disk(&self) -> bool {
+        !self.paths.is_empty() && modified_times(&self.paths) == self.modified
+    }
+
+    pub fn is_multi_file(&self) -> bool {
+        self.paths.len() > 1
+    }
+
+    // Where new entries go, skipping the shared file
+    fn default_index(&self) -> usize {
+        if self.read_only == Some(0) && self.paths.len() > 1 {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn context_index(&self, name: &str) -> usize {
+        self.contexts
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| self.default_index())
+    }
+
+    pub fn context_file(&self, name: &str) -> &str {
+        &self.paths[self.context_index(name)]
+    }
+
+    pub fn is_read_only(&self, name: &str) -> bool {
+        self.read_only.is_some() && self.read_only == self.contexts.get(name).copied()
+    }
+
+    pub fn check_writable(&self, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.is_read_only(name) {
+            return Err(format!(
+                "{} comes from the shared kubeconfig and can't be changed",
+                name
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn entry_owners(&mut self, kind: EntryKind) -> &mut HashMap<String, usize> {
+        match kind {
+            EntryKind::Cluster => &mut self.clusters,
+            EntryKind::User => &mut self.users,
+        }
+    }
+
+    // Renaming touches the entry's file and the files of every context referencing it
+    pub fn check_entry_writable(
+        &self,
+        kubeconfig: &Kubeconfig,
+        kind: EntryKind,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let owner = match kind {
+            EntryKind::Cluster => self.clusters.get(name),
+            EntryKind::User => self.users.get(name),
+        };
+        if self.read_only.is_some() && self.read_only == owner.copied() {
+            return Err(format!(
+                "The {} {} comes from the shared kubeconfig and can't be changed",
+                kind.label(),
+                name
+            )
+            .into());
+        }
+        for context in entry_references(kubeconfig, kind, name) {
+            self.check_writable(&context)?;
+        }
+        Ok(())
+    }
+
+    pub fn rename_entry(&mut self, kind: EntryKind, old_name: &str, new_name: &str) {
+        let owners = self.entry_owners(kind);
+        if let Some(index) = owners.remove(old_name) {
+            owners.insert(new_name.to_string(), index);
+        }
+    }
+
+    pub fn rename_context(&mut self, old_name: &str, new_name: &str) {
+        if let Some(index) = self.contexts.remove(old_name) {
+            self.contexts.insert(new_name.to_string(), index);
+        }
+    }
+
+    // The context's cluster and user come along unless other contexts in the old file use them
+    pub fn move_context(
+        &mut self,
+        kubeconfig: &Kubeconfig,
+        name: &str,
+        path: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let target = self
+            .paths
+            .iter()
+            .position(|p| p == path)
+            .ok_or(format!("{} isn't one of the kubeconfig files", path))?;
+        self.check_writable(name)?;
+        if self.read_only == Some(target) {
+            return Err("The shared kubeconfig can't be changed".into());
+        }
+        let source = self.context_index(name);
+        if source == target {
+            return Ok(());
+        }
+        let context = find_context(kubeconfig, name)
+            .and_then(|c| c.context.clone())
+            .ok_or(format!("No context named {}", name))?;
+        let others: Vec<&Context> = kubeconfig
+            .contexts
+            .iter()
+            .filter(|c| {
+                c.name != name && self.contexts.get(&c.name).copied().unwrap_or(0) == source
+            })
+            .filter_map(|c| c.context.as_ref())
+            .collect();
+        if !others.iter().any(|c| c.cluster == context.cluster)
+            && self.clusters.get(&context.cluster).copied() == Some(source)
+        {
+            self.clusters.insert(context.cluster.clone(), target);
+            self.shadowed
+                .remove(&(target, shadow_key("cluster", &context.cluster)));
+        }
+        if !others.iter().any(|c| c.user == context.user)
+            && self.users.get(&context.user).copied() == Some(source)
+        {
+            self.users.insert(context.user.clone(), target);
+            self.shadowed
+                .remove(&(target, shadow_key("user", &context.user)));
+        }
+        self.contexts.insert(name.to_string(), target);
+        self.shadowed.remove(&(target, shadow_key("context", name)));
+        Ok(())
+    }
+}
+
+fn shadow_key(kind: &str, name: &str) -> String {
+    format!("{}/{}", kind, name)
+}
+
+fn record_owner(
+    owners: &mut HashMap<String, usize>,
+    shadowed: &mut HashSet<(usize, String)>,
+    kind: &str,
+    name: &str,
+    index: usize,
+) {
+    if owners.contains_key(name) {
+        shadowed.insert((index, shadow_key(kind, name)));
+    } else {
+        owners.insert(name.to_string(), index);
+    }
+}
+
+pub fn split_paths(path: &str) -> Vec<String> {
+    std::env::split_paths(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+// Reads one kubeconfig or merges a list of them, first definition winning like in kubectl.
+// Missing files in a list are skipped. The shared kubeconfig, if any, is merged in last.
+pub fn load(
+    path: &str,
+    shared: Option<&str>,
+) -> Result<(Kubeconfig, Sources), Box<dyn Error + Send + Sync>> {
+    let mut paths = split_paths(path);
+    if paths.len() <= 1 && shared.is_none() {
+        let paths = vec![path.to_string()];
+        let sources = Sources {
+            modified: modified_times(&paths),
+            paths,
+            ..Default::default()
+        };
+        return Ok((Kubeconfig::read_from(path)?, sources));
+    }
+    let read_only = shared.map(|shared| {
+        paths.iter().position(|p| p == shared).unwrap_or_else(|| {
+            paths.push(shared.to_string());
+            paths.len() - 1
+        })
+    });
+    let mut merged: Option<Kubeconfig> = None;
+    let mut sources = Sources {
+        paths: paths.clone(),
+        read_only,
+        modified: modified_times(&paths),
+        ..Default::default()
+    };
+    for (index, path) in paths.iter().enumerate() {
+        if !Path::new(path).exists() {
+            if Some(index) == read_only {
+                log::warn!("The shared kubeconfig {} doesn't exist", path);
+            }
+            continue;
+        }
+        let kubeconfig = Kubeconfig::read_from(path)?;
+        for context in &kubeconfig.contexts {
+            let owners = &mut sources.contexts;
+            record_owner(
+                owners,
+                &mut sources.shadowed,
+                "context",
+                &context.name,
+                index,
+            );
+        }
+        for cluster in &kubeconfig.clusters {
+            let owners = &mut sources.clusters;
+            record_owner(
+                owners,
+                &mut sources.shadowed,
+                "cluster",
+                &cluster.name,
+                index,
+            );
+        }
+        for user in &kubeconfig.auth_infos {
+            let owners = &mut sources.users;
+            record_owner(owners, &mut sources.shadowed, "user", &user.name, index);
+        }
+        merged = Some(match merged {
+            Some(merged) => merged.merge(kubeconfig)?,
+            None => kubeconfig,
+        });
+    }
+    let merged = merged.ok_or(format!("None of the kubeconfig files in {} exist", path))?;
+    Ok((merged, sources))
+}
+
+fn modified_times(paths: &[String]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextChange {
+    Added(String),
+    Removed(String),
+    // The context, its cluster or its user entry differ
+    Changed(String),
+}
+
+// What a reload changed, context by context
+pub fn diff_contexts(old: &Kubeconfig, new: &Kubeconfig) -> Vec<ContextChange> {
+    let fingerprints = |kubeconfig: &Kubeconfig| -> HashMap<String, serde_json::Value> {
+        kubeconfig
+            .contexts
+            .iter()
+            .map(|context| {
+                let entries = (
+                    context,
+                    context_cluster(kubeconfig, &context.name),
+                    context_user(kubeconfig, &context.name),
+                );
+                (
+                    context.name.clone(),
+                    serde_json::to_value(entries).unwrap_or_default(),
+                )
+            })
+            .collect()
+    };
+    let (before, after) = (fingerprints(old), fingerprints(new));
+    let mut changes: Vec<ContextChange> = old
+        .contexts
+        .iter()
+        .filter(|c| !after.contains_key(&c.name))
+        .map(|c| ContextChange::Removed(c.name.clone()))
+        .collect();
+    for context in &new.contexts {
+        match before.get(&context.name) {
+            None => changes.push(ContextChange::Added(context.name.clone())),
+            Some(fingerprint) if after.get(&context.name) != Some(fingerprint) => {
+                changes.push(ContextChange::Changed(context.name.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    changes
+}
+
+// Entries nobody owns yet follow the first context that references them
+fn entry_file(
+    owners: &HashMap<String, usize>,
+    name: &str,
+    referencing_contexts: impl Iterator<Item = usize>,
+    default: usize,
+) -> usize {
+    owners
+        .get(name)
+        .copied()
+        .or_else(|| referencing_contexts.into_iter().next())
+        .unwrap_or(default)
+}
+
+// Writes each entry back to the file it belongs to. Entries a file shadows, because an earlier
+// file defines the same name, are left alone. The current context is written to the file holding
+// it and cleared everywhere else, so it wins the merge.
+pub async fn save_sources(
+    kubeconfig: &Kubeconfig,
+    sources: &Sources,
+    history_dir: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !sources.is_multi_file() {
+        return save(kubeconfig, &sources.paths[0], history_dir).await;
+    }
+    let context_file = |name: &str| sources.context_index(name);
+    for (index, path) in sources.paths.iter().enumerate() {
+        if sources.read_only == Some(index) {
+            continue;
+        }
+        let on_disk = if Path::new(path).exists() {
+            Kubeconfig::read_from(path)?
+        } else {
+            Kubeconfig::default()
+        };
+        let shadowed =
+            |kind: &str, name: &str| sources.shadowed.contains(&(index, shadow_key(kind, name)));
+        let mut file = Kubeconfig {
+            preferences: on_disk.preferences.clone(),
+            extensions: on_disk.extensions.clone(),
+            kind: on_disk.kind.clone().or(Some("Config".to_string())),
+            api_version: on_disk.api_version.clone().or(Some("v1".to_string())),
+            ..Default::default()
+        };
+        file.contexts = kubeconfig
+            .contexts
+            .iter()
+            .filter(|c| context_file(&c.name) == index)
+            .cloned()
+            .chain(
+                on_disk
+                    .contexts
+                    .into_iter()
+                    .filter(|c| shadowed("context", &c.name)),
+            )
+            .collect();
+        file.clusters = kubeconfig
+            .clusters
+            .iter()
+            .filter(|cluster| {
+                let referencing = kubeconfig
+                    .contexts
+                    .iter()
+                    .filter(|c| c.context.as_ref().map(|c| &c.cluster) == Some(&cluster.name))
+                    .map(|c| context_file(&c.name));
+                entry_file(
+                    &sources.clusters,
+                    &cluster.name,
+                    referencing,
+                    sources.default_index(),
+                ) == index
+            })
+            .cloned()
+            .chain(
+                on_disk
+                    .clusters
+                    .into_iter()
+                    .filter(|c| shadowed("cluster", &c.name)),
+            )
+            .collect();
+        file.auth_infos = kubeconfig
+            .auth_infos
+            .iter()
+            .filter(|user| {
+                let referencing = kubeconfig
+                    .contexts
+                    .iter()
+                    .filter(|c| c.context.as_ref().map(|c| &c.user) == Some(&user.name))
+                    .map(|c| context_file(&c.name));
+                entry_file(
+                    &sources.users,
+                    &user.name,
+                    referencing,
+                    sources.default_index(),
+                ) == index
+            })
+            .cloned()
+            .chain(
+                on_disk
+                    .auth_infos
+                    .into_iter()
+                    .filter(|u| shadowed("user", &u.name)),
+            )
+            .collect();
+        // Shared contexts can still be switched to, that only touches a personal file
+        file.current_context = kubeconfig.current_context.clone().filter(|name| {
+            let file = context_file(name);
+            file == index || (sources.read_only == Some(file) && index == sources.default_index())
+        });
+        save(&file, path, history_dir).await?;
+    }
+    Ok(())
+}
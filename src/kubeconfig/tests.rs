@@ -0,0 +1,406 @@
+// Merging, reloading and writing kubeconfigs, partly against the files in tests/fixtures.
+
+use std::path::{Path, PathBuf};
+
+use kube::config::Kubeconfig;
+
+use super::{
+    diff_contexts, find_conflicts, flatten, load, merge, minify, normalize, save_sources,
+    write_atomically, ConflictResolution, ContextChange,
+};
+
+const LAB: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/kubeconfigs/lab.yaml"
+);
+const EDGE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/kubeconfigs/edge.yaml"
+);
+
+// A fresh directory per test, so they can run side by side
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ktx-test-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Copies of the fixtures, which are written to
+fn copy_fixtures(dir: &Path) -> (String, String) {
+    let (lab, edge) = (dir.join("lab.yaml"), dir.join("edge.yaml"));
+    std::fs::copy(LAB, &lab).unwrap();
+    std::fs::copy(EDGE, &edge).unwrap();
+    (
+        lab.to_string_lossy().into_owned(),
+        edge.to_string_lossy().into_owned(),
+    )
+}
+
+fn context_names(kubeconfig: &Kubeconfig) -> Vec<&str> {
+    kubeconfig
+        .contexts
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect()
+}
+
+fn single(name: &str, server: &str) -> Kubeconfig {
+    serde_yaml::from_str(&format!(
+        r#"
+clusters:
+- name: {name}
+  cluster:
+    server: {server}
+contexts:
+- name: {name}
+  context:
+    cluster: {name}
+    user: {name}
+current-context: {name}
+users:
+- name: {name}
+  user:
+    token: {name}-token
+"#
+    ))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn sources_write_entries_back_to_their_files() {
+    let dir = scratch_dir("round-trip");
+    let (lab, edge) = copy_fixtures(&dir);
+    let (kubeconfig, sources) = load(&format!("{}:{}", lab, edge), None).unwrap();
+    assert_eq!(
+        context_names(&kubeconfig),
+        vec!["lab-admin", "lab-monitoring", "edge"]
+    );
+
+    save_sources(&kubeconfig, &sources, &dir.join("history"))
+        .await
+        .unwrap();
+    let lab_file = Kubeconfig::read_from(&lab).unwrap();
+    let edge_file = Kubeconfig::read_from(&edge).unwrap();
+    assert_eq!(
+        context_names(&lab_file),
+        vec!["lab-admin", "lab-monitoring"]
+    );
+    assert_eq!(lab_file.clusters[0].name, "lab");
+    assert_eq!(lab_file.current_context.as_deref(), Some("lab-admin"));
+    assert_eq!(context_names(&edge_file), vec!["edge"]);
+    assert_eq!(edge_file.auth_infos[0].name, "edge");
+    // Only the first file's current-context counts, the others lose theirs
+    assert_eq!(edge_file.current_context, None);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn sources_add_new_entries_to_the_first_file() {
+    let dir = scratch_dir("new-entry");
+    let (lab, edge) = copy_fixtures(&dir);
+    let (mut kubeconfig, sources) = load(&format!("{}:{}", lab, edge), None).unwrap();
+    merge(
+        &mut kubeconfig,
+        single("staging", "https://staging.example.com"),
+        ConflictResolution::Overwrite,
+    );
+
+    save_sources(&kubeconfig, &sources, &dir.join("history"))
+        .await
+        .unwrap();
+    let lab_file = Kubeconfig::read_from(&lab).unwrap();
+    let edge_file = Kubeconfig::read_from(&edge).unwrap();
+    assert_eq!(
+        context_names(&lab_file),
+        vec!["lab-admin", "lab-monitoring", "staging"]
+    );
+    assert!(lab_file.clusters.iter().any(|c| c.name == "staging"));
+    assert!(lab_file.auth_infos.iter().any(|u| u.name == "staging"));
+    assert_eq!(lab_file.current_context.as_deref(), Some("staging"));
+    assert_eq!(context_names(&edge_file), vec!["edge"]);
+    assert_eq!(edge_file.clusters.len(), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn sources_never_write_the_shared_kubeconfig() {
+    let dir = scratch_dir("shared");
+    let (lab, edge) = copy_fixtures(&dir);
+    let shared_before = std::fs::read_to_string(&edge).unwrap();
+    let (mut kubeconfig, mut sources) = load(&lab, Some(&edge)).unwrap();
+    assert!(sources.is_read_only("edge"));
+    assert_eq!(
+        sources.check_writable("edge").unwrap_err().to_string(),
+        "edge comes from the shared kubeconfig and can't be changed"
+    );
+    assert!(sources.check_writable("lab-admin").is_ok());
+    assert!(sources
+        .move_context(&kubeconfig, "lab-admin", &edge)
+        .is_err());
+
+    // Even an edit slipping through leaves the shared file alone
+    kubeconfig.contexts.retain(|c| c.name != "edge");
+    kubeconfig.current_context = Some("edge".to_string());
+    save_sources(&kubeconfig, &sources, &dir.join("history"))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&edge).unwrap(), shared_before);
+    // Switching to a shared context is written to the personal file instead
+    let lab_file = Kubeconfig::read_from(&lab).unwrap();
+    assert_eq!(lab_file.current_context.as_deref(), Some("edge"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn atomic_writes_keep_the_mode_of_the_target() {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let dir = scratch_dir("atomic-mode");
+    let (existing, created) = (dir.join("existing.yaml"), dir.join("created.yaml"));
+    std::fs::write(&existing, "").unwrap();
+    std::fs::set_permissions(&existing, std::fs::Permissions::from_mode(0o640)).unwrap();
+    // A leftover from a crash is replaced rather than written through
+    std::fs::write(dir.join("existing.ktx-tmp"), "stale").unwrap();
+
+    write_atomically(existing.to_str().unwrap(), "kind: Config\n")
+        .await
+        .unwrap();
+    write_atomically(created.to_str().unwrap(), "kind: Config\n")
+        .await
+        .unwrap();
+
+    assert_eq!(mode(&existing), 0o640);
+    assert_eq!(mode(&created), 0o600);
+    assert_eq!(
+        std::fs::read_to_string(&existing).unwrap(),
+        "kind: Config\n"
+    );
+    assert!(!dir.join("existing.ktx-tmp").exists());
+}
+
+#[test]
+fn conflicts_are_entries_that_differ() {
+    let existing = single("staging", "https://staging.example.com");
+    assert!(find_conflicts(&existing, &existing.clone()).is_empty());
+    let incoming = single("staging", "https://new.staging.example.com");
+    assert_eq!(
+        find_conflicts(&existing, &incoming),
+        vec!["cluster staging"]
+    );
+}
+
+#[test]
+fn merging_overwrites_conflicting_entries() {
+    let mut existing = single("staging", "https://staging.example.com");
+    let incoming = single("staging", "https://new.staging.example.com");
+    let merged = merge(&mut existing, incoming, ConflictResolution::Overwrite);
+    assert_eq!(merged, vec!["staging"]);
+    assert_eq!(existing.clusters.len(), 1);
+    assert_eq!(
+        existing.clusters[0]
+            .cluster
+            .as_ref()
+            .unwrap()
+            .server
+            .as_deref(),
+        Some("https://new.staging.example.com")
+    );
+}
+
+#[test]
+fn merging_can_skip_conflicting_entries() {
+    let mut existing = single("staging", "https://staging.example.com");
+    merge(
+        &mut existing,
+        single("prod", "https://prod.example.com"),
+        ConflictResolution::Overwrite,
+    );
+    let mut incoming = single("staging", "https://new.staging.example.com");
+    incoming
+        .contexts
+        .extend(single("dev", "https://dev.example.com").contexts);
+    let merged = merge(&mut existing, incoming, ConflictResolution::Skip);
+    assert_eq!(merged, vec!["dev"]);
+    assert_eq!(
+        existing.clusters[0]
+            .cluster
+            .as_ref()
+            .unwrap()
+            .server
+            .as_deref(),
+        Some("https://staging.example.com")
+    );
+    // The skipped context doesn't become current either
+    assert_eq!(existing.current_context.as_deref(), Some("prod"));
+}
+
+#[test]
+fn merging_keeps_both_by_renaming_the_incoming_entries() {
+    let mut existing = single("staging", "https://staging.example.com");
+    existing
+        .clusters
+        .extend(single("staging-1", "https://other.example.com").clusters);
+    let incoming = single("staging", "https://new.staging.example.com");
+    let merged = merge(
+        &mut existing,
+        incoming,
+        ConflictResolution::RenameWithSuffix,
+    );
+    assert_eq!(merged, vec!["staging-1"]);
+    assert_eq!(context_names(&existing), vec!["staging", "staging-1"]);
+    let renamed = existing.contexts[1].context.as_ref().unwrap();
+    // staging-1 was taken by another cluster, the user only needed the first suffix
+    assert_eq!(renamed.cluster, "staging-2");
+    assert_eq!(renamed.user, "staging");
+    assert_eq!(existing.current_context.as_deref(), Some("staging-1"));
+}
+
+#[test]
+fn merging_switches_only_when_incoming_has_a_current_context() {
+    let mut existing = single("staging", "https://staging.example.com");
+    let mut incoming = single("prod", "https://prod.example.com");
+    incoming.current_context = None;
+    merge(&mut existing, incoming, ConflictResolution::Overwrite);
+    assert_eq!(existing.current_context.as_deref(), Some("staging"));
+    merge(
+        &mut existing,
+        single("dev", "https://dev.example.com"),
+        ConflictResolution::Overwrite,
+    );
+    assert_eq!(existing.current_context.as_deref(), Some("dev"));
+}
+
+#[test]
+fn reloads_are_diffed_context_by_context() {
+    let mut old = single("staging", "https://staging.example.com");
+    merge(
+        &mut old,
+        single("prod", "https://prod.example.com"),
+        ConflictResolution::Overwrite,
+    );
+    let mut new = single("staging", "https://new.staging.example.com");
+    merge(
+        &mut new,
+        single("dev", "https://dev.example.com"),
+        ConflictResolution::Overwrite,
+    );
+    // The current context alone isn't a change of any context
+    new.current_context = Some("staging".to_string());
+    assert_eq!(
+        diff_contexts(&old, &new),
+        vec![
+            ContextChange::Removed("prod".to_string()),
+            ContextChange::Changed("staging".to_string()),
+            ContextChange::Added("dev".to_string()),
+        ]
+    );
+    assert!(diff_contexts(&new, &new.clone()).is_empty());
+}
+
+#[test]
+fn flattening_inlines_certificate_files() {
+    let dir = scratch_dir("flatten");
+    let ca = dir.join("ca.crt");
+    std::fs::write(&ca, "lab-ca").unwrap();
+    let mut kubeconfig = single("lab", "https://10.20.0.10:6443");
+    let cluster = kubeconfig.clusters[0].cluster.as_mut().unwrap();
+    cluster.certificate_authority = Some(ca.to_string_lossy().into_owned());
+
+    let (flattened, inlined) = flatten(&kubeconfig).unwrap();
+    assert_eq!(inlined, 1);
+    let cluster = flattened.clusters[0].cluster.as_ref().unwrap();
+    assert_eq!(cluster.certificate_authority, None);
+    assert_eq!(
+        cluster.certificate_authority_data.as_deref(),
+        Some("bGFiLWNh")
+    );
+    assert_eq!(flatten(&flattened).unwrap().1, 0);
+
+    std::fs::remove_file(&ca).unwrap();
+    assert!(flatten(&kubeconfig)
+        .unwrap_err()
+        .to_string()
+        .starts_with("Unable to read"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn minifying_keeps_only_the_given_contexts() {
+    let mut kubeconfig = single("staging", "https://staging.example.com");
+    merge(
+        &mut kubeconfig,
+        single("prod", "https://prod.example.com"),
+        ConflictResolution::Overwrite,
+    );
+    let minified = minify(&kubeconfig, &["staging"]).unwrap();
+    assert_eq!(context_names(&minified), vec!["staging"]);
+    assert_eq!(minified.clusters.len(), 1);
+    assert_eq!(minified.auth_infos.len(), 1);
+    assert_eq!(minified.auth_infos[0].name, "staging");
+    assert_eq!(
+        minify(&kubeconfig, &["staging", "dev"])
+            .unwrap_err()
+            .to_string(),
+        "No context named dev"
+    );
+}
+
+#[test]
+fn normalizing_upgrades_writable_auth_providers() {
+    let mut kubeconfig: Kubeconfig = serde_yaml::from_str(
+        r#"
+users:
+- name: gke
+  user:
+    auth-provider:
+      name: gcp
+- name: aks
+  user:
+    auth-provider:
+      name: azure
+      config:
+        apiserver-id: server
+        client-id: client
+        tenant-id: tenant
+- name: shared-gke
+  user:
+    auth-provider:
+      name: gcp
+- name: oidc
+  user:
+    auth-provider:
+      name: oidc
+"#,
+    )
+    .unwrap();
+    let preview = normalize(&mut kubeconfig, |user| user != "shared-gke");
+    assert_eq!(
+        preview,
+        vec![
+            "~ user gke (gcp auth-provider -> exec gke-gcloud-auth-plugin)",
+            "~ user aks (azure auth-provider -> exec kubelogin)",
+        ]
+    );
+    let auth_info = |index: usize| kubeconfig.auth_infos[index].auth_info.as_ref().unwrap();
+    assert!(auth_info(0).auth_provider.is_none());
+    assert_eq!(
+        auth_info(1).exec.as_ref().unwrap().args.as_deref().unwrap(),
+        [
+            "get-token",
+            "--login",
+            "devicecode",
+            "--server-id",
+            "server",
+            "--client-id",
+            "client",
+            "--tenant-id",
+            "tenant",
+            "--environment",
+            "AzurePublicCloud",
+        ]
+    );
+    assert!(auth_info(2).exec.is_none());
+    assert!(auth_info(3).auth_provider.is_some());
+}
@@ -1,12 +1,29 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use crossterm::{event, execute};
 use futures::StreamExt;
-use std::{io, sync::Arc};
+use std::{io, path::Path, sync::Arc};
 use tokio::sync::mpsc;
 use tui::{backend::CrosstermBackend, Terminal};
 
+mod audit;
+mod cli;
+mod cloud;
+mod cluster;
+mod config;
+mod ephemeral;
+mod exporter;
+mod history;
+mod kubeconfig;
+mod metadata;
+mod plugins;
+mod project;
+mod proxy;
+mod runner;
+mod session;
+mod tunnel;
 mod ui;
 
+use config::KtxConfig;
 use ui::{KtxApp, KtxEvent, RendererMessage};
 
 #[tokio::main]
@@ -22,26 +39,466 @@ async fn main() {
                 .value_name("FILE")
                 .help("Sets a custom kubeconfig file"),
         )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Sets a custom ktx config file"),
+        )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .action(ArgAction::SetTrue)
+                .help("Disables cloud provider detection, imports and connectivity tests"),
+        )
+        .arg(
+            Arg::new("plain")
+                .long("plain")
+                .action(ArgAction::SetTrue)
+                .help("Uses a line based prompt instead of the full screen interface"),
+        )
+        .arg(
+            Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Merges and normalizes without showing what would change and asking first"),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("Prints all contexts with their server, provider, health and last use")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FORMAT")
+                        .value_parser(["csv", "markdown"])
+                        .default_value("csv")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            Command::new("rename")
+                .about("Renames a context")
+                .arg(Arg::new("old").value_name("OLD").required(true))
+                .arg(Arg::new("new").value_name("NEW").required(true)),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Exports a single context as a standalone kubeconfig")
+                .arg(Arg::new("name").value_name("NAME").required(true))
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Writes the kubeconfig to FILE instead of stdout"),
+                ),
+        )
+        .subcommand(
+            Command::new("provenance")
+                .about("Works with where imported contexts came from")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Prints the provider, account, region and cluster of every imported context")
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Writes the manifest to FILE instead of stdout"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("bootstrap")
+                .about("Imports the clusters of a team manifest that your credentials can access")
+                .arg(
+                    Arg::new("manifest")
+                        .value_name("MANIFEST")
+                        .required(true)
+                        .help("URL or path of a manifest written by `ktx provenance export`"),
+                ),
+        )
+        .subcommand(
+            Command::new("merge")
+                .about("Merges kubeconfig files, replacing entries with the same name")
+                .arg(
+                    Arg::new("files")
+                        .value_name("FILE")
+                        .required(true)
+                        .action(ArgAction::Append),
+                ),
+        )
+        .subcommand(Command::new("normalize").about(
+            "Rewrites gcp and azure auth-providers, removed in kubectl 1.26, to exec plugins",
+        ))
+        .subcommand(
+            Command::new("flatten")
+                .about("Inlines certificate and key files referenced by the kubeconfig")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Writes the flattened kubeconfig to FILE instead of in place"),
+                ),
+        )
+        .subcommand(
+            Command::new("minify")
+                .about("Exports the given contexts, or the current one, as a flattened kubeconfig")
+                .arg(
+                    Arg::new("names")
+                        .value_name("NAME")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Writes the kubeconfig to FILE instead of stdout"),
+                ),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Measures API round-trip latency of the given contexts, or all of them")
+                .arg(
+                    Arg::new("names")
+                        .value_name("NAME")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("rounds")
+                        .short('r')
+                        .long("rounds")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32))
+                        .default_value("5")
+                        .help("Number of round-trips per context"),
+                )
+                .arg(
+                    Arg::new("sort")
+                        .short('s')
+                        .long("sort")
+                        .value_parser(["name", "min", "avg", "max"])
+                        .default_value("avg")
+                        .help("Column to sort by"),
+                ),
+        )
+        .subcommand(
+            Command::new("exporter")
+                .about("Periodically checks all contexts and exposes or posts the results")
+                .arg(
+                    Arg::new("listen")
+                        .short('l')
+                        .long("listen")
+                        .value_name("ADDRESS")
+                        .value_parser(clap::value_parser!(std::net::SocketAddr))
+                        .help("Serves Prometheus metrics on ADDRESS, e.g. 127.0.0.1:9797"),
+                )
+                .arg(
+                    Arg::new("webhook")
+                        .short('w')
+                        .long("webhook")
+                        .value_name("URL")
+                        .help("Posts the results of every round to URL as JSON"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .short('i')
+                        .long("interval")
+                        .value_name("SECONDS")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .help("Seconds between rounds, 60 by default"),
+                ),
+        )
+        .subcommand(
+            Command::new("use")
+                .about("Switches the current context")
+                .arg(
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .required_unless_present("project"),
+                )
+                .arg(
+                    Arg::new("namespace")
+                        .short('n')
+                        .long("namespace")
+                        .value_name("NAMESPACE")
+                        .conflicts_with("project")
+                        .help("Also sets the namespace of the context"),
+                )
+                .arg(
+                    Arg::new("project")
+                        .long("project")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("name")
+                        .help("Switches to the context pinned in the closest .ktx file"),
+                )
+                .arg(
+                    Arg::new("quiet")
+                        .short('q')
+                        .long("quiet")
+                        .action(ArgAction::SetTrue)
+                        .help("Does nothing outside of projects instead of failing"),
+                ),
+        )
+        .subcommand(
+            Command::new("hook")
+                .about("Prints shell code that switches contexts when entering a project")
+                .arg(
+                    Arg::new("shell")
+                        .value_name("SHELL")
+                        .value_parser(["bash", "zsh", "fish"])
+                        .required(true),
+                ),
+        )
         .get_matches();
 
-    let default_config = shellexpand::tilde("~/.kube/config").into_owned();
+    // KUBECONFIG may list several files, which ktx merges like kubectl does
+    let default_config = config::env_var("KTX_KUBECONFIG")
+        .or_else(|| config::env_var("KUBECONFIG"))
+        .unwrap_or_else(|| shellexpand::tilde("~/.kube/config").into_owned());
     let config_path = matches
         .get_one::<String>("kubeconfig")
         .unwrap_or(&default_config)
         .clone();
 
+    let default_ktx_config = config::env_var("KTX_CONFIG")
+        .unwrap_or_else(|| shellexpand::tilde("~/.config/ktx/config.yaml").into_owned());
+    let ktx_config_path = matches
+        .get_one::<String>("config")
+        .unwrap_or(&default_ktx_config)
+        .clone();
+    let mut ktx_config = KtxConfig::load(&ktx_config_path).expect("Unable to read ktx config");
+    if let Err(e) = ktx_config.apply_env() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    // Metadata lives next to the config file
+    let metadata_path = Path::new(&ktx_config_path)
+        .with_file_name("metadata.yaml")
+        .to_string_lossy()
+        .into_owned();
+    if matches.get_flag("offline") {
+        ktx_config.offline = true;
+    }
+    if matches.get_flag("yes") {
+        ktx_config.preview_merges = false;
+    }
+    if let Some(level) = &ktx_config.log_level {
+        // The terminal belongs to the UI, so logs go to a file
+        let log_path = Path::new(&ktx_config_path).with_file_name("ktx.log");
+        init_logging(level, &log_path).expect("Unable to open log file");
+    }
+
+    if let Some((command, sub_matches)) = matches.subcommand() {
+        let result = match command {
+            "list" => {
+                let format = match sub_matches.get_one::<String>("output").map(|s| s.as_str()) {
+                    Some("markdown") => cli::OutputFormat::Markdown,
+                    _ => cli::OutputFormat::Csv,
+                };
+                cli::list(&config_path, &ktx_config, &metadata_path, format).await
+            }
+            "rename" => {
+                cli::rename(
+                    &config_path,
+                    &ktx_config,
+                    &metadata_path,
+                    sub_matches.get_one::<String>("old").unwrap(),
+                    sub_matches.get_one::<String>("new").unwrap(),
+                )
+                .await
+            }
+            "export" => {
+                cli::export(
+                    &config_path,
+                    &ktx_config,
+                    sub_matches.get_one::<String>("name").unwrap(),
+                    sub_matches.get_one::<String>("output").map(|s| s.as_str()),
+                )
+                .await
+            }
+            "provenance" => match sub_matches.subcommand() {
+                Some(("export", export_matches)) => {
+                    cli::export_provenance(
+                        &config_path,
+                        &ktx_config,
+                        &metadata_path,
+                        export_matches
+                            .get_one::<String>("output")
+                            .map(|s| s.as_str()),
+                    )
+                    .await
+                }
+                _ => unreachable!(),
+            },
+            "bootstrap" => {
+                cli::bootstrap(
+                    &config_path,
+                    &ktx_config,
+                    &metadata_path,
+                    sub_matches.get_one::<String>("manifest").unwrap(),
+                )
+                .await
+            }
+            "merge" => {
+                let files: Vec<&str> = sub_matches
+                    .get_many::<String>("files")
+                    .unwrap_or_default()
+                    .map(|s| s.as_str())
+                    .collect();
+                cli::merge(&config_path, &ktx_config, &metadata_path, &files).await
+            }
+            "normalize" => cli::normalize(&config_path, &ktx_config, &metadata_path).await,
+            "flatten" => {
+                cli::flatten(
+                    &config_path,
+                    &metadata_path,
+                    sub_matches.get_one::<String>("output").map(|s| s.as_str()),
+                )
+                .await
+            }
+            "minify" => {
+                let names: Vec<&str> = sub_matches
+                    .get_many::<String>("names")
+                    .unwrap_or_default()
+                    .map(|s| s.as_str())
+                    .collect();
+                cli::minify(
+                    &config_path,
+                    &ktx_config,
+                    &names,
+                    sub_matches.get_one::<String>("output").map(|s| s.as_str()),
+                )
+                .await
+            }
+            "bench" => {
+                let names: Vec<&str> = sub_matches
+                    .get_many::<String>("names")
+                    .unwrap_or_default()
+                    .map(|s| s.as_str())
+                    .collect();
+                cli::bench(
+                    &config_path,
+                    &ktx_config,
+                    &names,
+                    *sub_matches.get_one::<u32>("rounds").unwrap(),
+                    cluster::LatencySort::parse(sub_matches.get_one::<String>("sort").unwrap())
+                        .unwrap(),
+                )
+                .await
+            }
+            "exporter" => {
+                exporter::run(
+                    &config_path,
+                    &ktx_config,
+                    &metadata_path,
+                    sub_matches
+                        .get_one::<std::net::SocketAddr>("listen")
+                        .copied(),
+                    sub_matches.get_one::<String>("webhook").map(|s| s.as_str()),
+                    sub_matches
+                        .get_one::<u64>("interval")
+                        .map(|secs| std::time::Duration::from_secs(*secs))
+                        .unwrap_or(exporter::DEFAULT_INTERVAL),
+                )
+                .await
+            }
+            "use" if sub_matches.get_flag("project") => {
+                cli::use_project(
+                    &config_path,
+                    &ktx_config,
+                    &metadata_path,
+                    sub_matches.get_flag("quiet"),
+                )
+                .await
+            }
+            "use" => cli::use_context(
+                &config_path,
+                &ktx_config,
+                &metadata_path,
+                sub_matches.get_one::<String>("name").unwrap(),
+                sub_matches
+                    .get_one::<String>("namespace")
+                    .map(|s| s.as_str()),
+            )
+            .await
+            .map(|_| ()),
+            "hook" => project::hook(sub_matches.get_one::<String>("shell").unwrap())
+                .map(|script| print!("{}", script)),
+            _ => unreachable!(),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if matches.get_flag("plain") {
+        if let Err(e) = cli::plain(&config_path, &ktx_config, &metadata_path).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let mut stdout = io::stdout();
-    execute!(stdout, crossterm::terminal::EnterAlternateScreen)
-        .expect("Failed to enter alternate screen");
+    execute!(
+        stdout,
+        crossterm::terminal::EnterAlternateScreen,
+        event::EnableBracketedPaste
+    )
+    .expect("Failed to enter alternate screen");
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend).expect("Failed to create terminal");
     terminal.clear().unwrap();
-    let (renderer_tx, renderer_rx) = mpsc::channel(1024);
+    let (renderer_tx, renderer_rx) = mpsc::channel(1);
     let (event_bus_tx, mut event_bus_rx) = mpsc::channel(1024);
-    let app = Arc::new(KtxApp::new(config_path.clone(), terminal, event_bus_tx));
+    let (task_events_tx, mut task_events_rx) = mpsc::unbounded_channel();
+    let app = Arc::new(KtxApp::new(
+        config_path.clone(),
+        ktx_config,
+        metadata_path,
+        Arc::new(runner::SystemCommandRunner),
+        terminal,
+        event_bus_tx,
+        task_events_tx,
+    ));
 
     app.start().await;
 
+    tokio::spawn({
+        let app = app.clone();
+        async move {
+            shutdown_signal(&app).await;
+            app.interrupt().await;
+        }
+    });
+    #[cfg(unix)]
+    tokio::spawn({
+        let app = app.clone();
+        async move {
+            let mut resumed = tokio::signal::unix::signal(
+                tokio::signal::unix::SignalKind::from_raw(libc::SIGCONT),
+            )
+            .expect("Failed to install the SIGCONT handler");
+            while resumed.recv().await.is_some() {
+                if app.is_in_shell() {
+                    continue;
+                }
+                if let Err(e) = app.resume().await {
+                    log::error!("Unable to restore the terminal: {}", e);
+                }
+            }
+        }
+    });
+
     let renderer = tokio::spawn({
         let app = app.clone();
         async move {
@@ -54,24 +511,45 @@ async fn main() {
         async move {
             let mut reader = event::EventStream::new();
             loop {
-                renderer_tx.send(RendererMessage::Render).await.unwrap();
                 tokio::select! {
                     terminal_event = reader.next() => {
                         let evt = terminal_event.expect("Failed to read event").unwrap();
                         app.handle_event(KtxEvent::TerminalEvent(evt)).await;
                     },
+                    Some(evt) = task_events_rx.recv() => {
+                        app.handle_event(evt).await;
+                    },
                     app_event = event_bus_rx.recv() => {
                         let evt = app_event.expect("Failed to read event");
                         match evt {
                             KtxEvent::Exit => {
                                 break;
                             },
+                            KtxEvent::OpenShell(name) => {
+                                // The child process owns the terminal until it exits, so stop
+                                // reading from it in the meantime
+                                drop(reader);
+                                app.open_shell(name).await;
+                                reader = event::EventStream::new();
+                            },
                             _ => {
                                 app.handle_event(evt).await;
                             },
                         }
                     },
                 }
+                // A full channel means a redraw is already pending, which will pick this
+                // change up as well
+                let _ = renderer_tx.try_send(RendererMessage::Render);
+            }
+            // Imports that finished in the meantime are still written, unless they need an answer
+            while let Ok(evt) = event_bus_rx.try_recv() {
+                if matches!(
+                    evt,
+                    KtxEvent::MergeImportedConfig(_) | KtxEvent::ResolveImportConflict(_)
+                ) {
+                    app.handle_event(evt).await;
+                }
             }
             renderer_tx.send(RendererMessage::Stop).await.unwrap();
         }
@@ -79,3 +557,45 @@ async fn main() {
     let (_, _) = tokio::join!(renderer, event_handler);
     app.shutdown().await;
 }
+
+// SIGINT only arrives while a spawned shell owns the terminal or when sent with kill, and is left to
+// the shell in the first case
+async fn shutdown_signal<B>(app: &KtxApp<B>)
+where
+    B: tui::backend::Backend + Send + Sync,
+{
+    #[cfg(unix)]
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to install the SIGTERM handler");
+    loop {
+        #[cfg(unix)]
+        let terminated = async {
+            terminate.recv().await;
+        };
+        #[cfg(not(unix))]
+        let terminated = futures::future::pending::<()>();
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                if !app.is_in_shell() {
+                    return;
+                }
+            }
+            _ = terminated => return,
+        }
+    }
+}
+
+fn init_logging(level: &str, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    env_logger::Builder::new()
+        .parse_filters(level)
+        .target(env_logger::Target::Pipe(Box::new(file)))
+        .init();
+    Ok(())
+}
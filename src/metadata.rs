@@ -0,0 +1,368 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use kube::config::Kubeconfig;
+use serde::{Deserialize, Serialize};
+
+use crate::kubeconfig;
+use crate::tunnel::SshTunnel;
+
+// Where a context was imported from, so it can be traced back to its cloud resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    // aws, gcp or azure
+    pub provider: String,
+    // AWS profile, GCP project or Azure subscription
+    pub account: String,
+    // AWS region or GKE zone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_group: Option<String>,
+    pub cluster: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imported_at: Option<DateTime<Utc>>,
+    // Labels or tags the cluster had in the cloud when it was imported
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    // Import preset used, refreshing the credentials goes through it again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ContextMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tunnel: Option<SshTunnel>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    // Last namespace picked when switching to the context
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    // Temporary clusters, e.g. preview environments, are offered for cleanup after this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    // Ownership tags like team or env, copied from the cloud per copy_import_tags
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+// A context and where it was imported from, one entry of a provenance manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub context: String,
+    #[serde(flatten)]
+    pub provenance: Provenance,
+}
+
+// Enough to import the same clusters again on another machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    pub clusters: Vec<ManifestEntry>,
+}
+
+// Everything ktx knows about contexts that doesn't belong in the kubeconfig itself
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KtxMetadata {
+    pub contexts: BTreeMap<String, ContextMetadata>,
+}
+
+impl KtxMetadata {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    pub async fn save(&self, path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let serialized_metadata = serde_yaml::to_string(self)?;
+        kubeconfig::write_atomically(path, &serialized_metadata).await
+    }
+
+    pub fn context_mut(&mut self, name: &str) -> &mut ContextMetadata {
+        self.contexts.entry(name.to_string()).or_default()
+    }
+
+    // Cloud tags listed in copy_import_tags ("*" for all) are copied next to the provenance
+    pub fn record_import(&mut self, name: &str, provenance: Provenance, copy_tags: &[String]) {
+        let entry = self.context_mut(name);
+        entry.tags.extend(
+            provenance
+                .tags
+                .iter()
+                .filter(|(key, _)| copy_tags.iter().any(|k| k == "*" || k == *key))
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        entry.provenance = Some(provenance);
+    }
+
+    pub fn provenance(&self, name: &str) -> Option<Provenance> {
+        self.contexts.get(name).and_then(|m| m.provenance.clone())
+    }
+
+    pub fn rename(&mut self, old_name: &str, new_name: &str) {
+        if let Some(entry) = self.contexts.remove(old_name) {
+            self.contexts.insert(new_name.to_string(), entry);
+        }
+    }
+
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.contexts.get(name).map(|m| m.pinned).unwrap_or(false)
+    }
+
+    pub fn namespace(&self, name: &str) -> Option<String> {
+        self.contexts.get(name).and_then(|m| m.namespace.clone())
+    }
+
+    pub fn last_used(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.contexts.get(name).and_then(|m| m.last_used)
+    }
+
+    pub fn expires_at(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.contexts.get(name).and_then(|m| m.expires_at)
+    }
+
+    pub fn tags(&self, name: &str) -> BTreeMap<String, String> {
+        self.contexts
+            .get(name)
+            .map(|m| m.tags.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn is_expired(&self, name: &str) -> bool {
+        self.expires_at(name).is_some_and(|at| at <= Utc::now())
+    }
+
+    // Contexts without provenance were added by hand and can't be imported again
+    pub fn manifest<'a>(&self, contexts: impl Iterator<Item = &'a str>) -> Manifest {
+        Manifest {
+            clusters: contexts
+                .filter_map(|name| {
+                    Some(ManifestEntry {
+                        context: name.to_string(),
+                        provenance: self.provenance(name)?,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+pub fn format_tags(tags: &BTreeMap<String, String>) -> String {
+    tags.iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// TTLs like 45m, 12h, 7d or 2w
+pub fn parse_ttl(ttl: &str) -> Result<chrono::Duration, String> {
+    let ttl = ttl.trim();
+    let error = || format!("Invalid TTL {}, expected e.g. 45m, 12h, 7d or 2w", ttl);
+    let split = ttl.len().saturating_sub(1);
+    let amount: i64 = ttl
+        .get(..split)
+        .and_then(|a| a.parse().ok())
+        .filter(|a| (1..=100_000).contains(a))
+        .ok_or_else(error)?;
+    match &ttl[split..] {
+        "m" => Ok(chrono::Duration::minutes(amount)),
+        "h" => Ok(chrono::Duration::hours(amount)),
+        "d" => Ok(chrono::Duration::days(amount)),
+        "w" => Ok(chrono::Duration::weeks(amount)),
+        _ => Err(error()),
+    }
+}
+
+// Best guess at which cloud a context lives in: recorded provenance first, then the context
+// naming schemes and API endpoints of the managed offerings
+pub fn context_provider(
+    metadata: &KtxMetadata,
+    kubeconfig: &Kubeconfig,
+    name: &str,
+) -> Option<String> {
+    if let Some(provenance) = metadata
+        .provenance(name)
+        .or_else(|| Provenance::from_context_name(name))
+    {
+        return Some(provenance.provider);
+    }
+    let server = kubeconfig::context_cluster(kubeconfig, name)?
+        .cluster
+        .as_ref()?
+        .server
+        .clone()?;
+    if server.contains(".eks.amazonaws.com") {
+        Some("aws".to_string())
+    } else if server.contains(".azmk8s.io") {
+        Some("azure".to_string())
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AwsIdentity {
+    pub account: Option<String>,
+    pub profile: Option<String>,
+}
+
+impl AwsIdentity {
+    pub fn label(&self) -> String {
+        match (&self.account, &self.profile) {
+            (Some(account), Some(profile)) => format!("{} ({})", account, profile),
+            (Some(account), None) => account.clone(),
+            (None, Some(profile)) => profile.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+// The account id of an ARN such as arn:aws:eks:{region}:{account}:cluster/{name} or
+// arn:aws:iam::{account}:role/{name}
+fn arn_account(arn: &str) -> Option<String> {
+    let account = arn.strip_prefix("arn:aws")?.split(':').nth(4)?;
+    (account.len() == 12 && account.chars().all(|c| c.is_ascii_digit()))
+        .then(|| account.to_string())
+}
+
+// Tells apart EKS contexts with the same cluster name in different accounts. The account comes
+// from the ARNs `aws eks update-kubeconfig` uses as names or from a role the token is fetched
+// with, the profile from the token command's AWS_PROFILE or --profile.
+pub fn aws_identity(
+    metadata: &KtxMetadata,
+    kubeconfig: &Kubeconfig,
+    name: &str,
+) -> Option<AwsIdentity> {
+    if context_provider(metadata, kubeconfig, name).as_deref() != Some("aws") {
+        return None;
+    }
+    let exec = kubeconfig::context_user(kubeconfig, name)
+        .and_then(|u| u.auth_info.as_ref())
+        .and_then(|a| a.exec.clone());
+    let args = exec
+        .as_ref()
+        .and_then(|e| e.args.clone())
+        .unwrap_or_default();
+    let arg_value = |flags: &[&str]| {
+        args.iter()
+            .position(|a| flags.contains(&a.as_str()))
+            .and_then(|i| args.get(i + 1).cloned())
+    };
+    let cluster_name = kubeconfig::find_context(kubeconfig, name)
+        .and_then(|c| c.context.as_ref())
+        .map(|c| c.cluster.clone())
+        .unwrap_or_default();
+    let account = arn_account(name)
+        .or_else(|| arn_account(&cluster_name))
+        .or_else(|| arg_value(&["--role-arn", "--role", "-r"]).and_then(|arn| arn_account(&arn)))
+        .or_else(|| args.iter().find_map(|a| arn_account(a)));
+    let env_profile = exec
+        .as_ref()
+        .and_then(|e| e.env.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .find(|var| var.get("name").map(|n| n.as_str()) == Some("AWS_PROFILE"))
+        .and_then(|var| var.get("value").cloned());
+    let profile = env_profile
+        .or_else(|| arg_value(&["--profile"]))
+        .or_else(|| {
+            metadata
+                .provenance(name)
+                .map(|p| p.account)
+                .filter(|account| !account.is_empty())
+        });
+    if account.is_none() && profile.is_none() {
+        return None;
+    }
+    Some(AwsIdentity { account, profile })
+}
+
+impl Provenance {
+    pub fn same_cluster(&self, other: &Provenance) -> bool {
+        self.provider == other.provider
+            && self.account == other.account
+            && self.location == other.location
+            && self.resource_group == other.resource_group
+            && self.cluster == other.cluster
+    }
+
+    // Recovers what the naming schemes of the cloud CLIs encode, for contexts imported before
+    // provenance was recorded
+    pub fn from_context_name(name: &str) -> Option<Self> {
+        if let Some(rest) = name.strip_prefix("gke_") {
+            // gke_{project}_{zone}_{cluster}, project ids can't contain underscores
+            let mut parts = rest.splitn(3, '_');
+            let (project, zone, cluster) = (parts.next()?, parts.next()?, parts.next()?);
+            return Some(Self {
+                provider: "gcp".to_string(),
+                account: project.to_string(),
+                location: Some(zone.to_string()),
+                resource_group: None,
+                cluster: cluster.to_string(),
+                imported_at: None,
+                tags: BTreeMap::new(),
+                preset: None,
+            });
+        }
+        if let Some(rest) = name.strip_prefix("arn:aws:eks:") {
+            // arn:aws:eks:{region}:{account}:cluster/{cluster}
+            let mut parts = rest.splitn(3, ':');
+            let (region, _, resource) = (parts.next()?, parts.next()?, parts.next()?);
+            return Some(Self {
+                provider: "aws".to_string(),
+                // The profile used to import the cluster isn't part of the ARN
+                account: String::new(),
+                location: Some(region.to_string()),
+                resource_group: None,
+                cluster: resource.strip_prefix("cluster/")?.to_string(),
+                imported_at: None,
+                tags: BTreeMap::new(),
+                preset: None,
+            });
+        }
+        None
+    }
+
+    pub fn console_url(&self) -> Option<String> {
+        match self.provider.as_str() {
+            "aws" => {
+                let region = self.location.as_ref()?;
+                Some(format!(
+                    "https://{}.console.aws.amazon.com/eks/home?region={}#/clusters/{}",
+                    region, region, self.cluster
+                ))
+            }
+            "gcp" => Some(format!(
+                "https://console.cloud.google.com/kubernetes/clusters/details/{}/{}/details?project={}",
+                self.location.as_ref()?,
+                self.cluster,
+                self.account
+            )),
+            "azure" => Some(format!(
+                "https://portal.azure.com/#@/resource/subscriptions/{}/resourceGroups/{}/providers/Microsoft.ContainerService/managedClusters/{}/overview",
+                self.account,
+                self.resource_group.as_ref()?,
+                self.cluster
+            )),
+            _ => None,
+        }
+    }
+}
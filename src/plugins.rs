@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use kube::config::Kubeconfig;
+
+use crate::kubeconfig;
+
+// How to get the credential plugins cloud kubeconfigs usually point at
+fn install_command(command: &str) -> Option<&'static str> {
+    let macos = cfg!(target_os = "macos");
+    match command {
+        "gke-gcloud-auth-plugin" => Some("gcloud components install gke-gcloud-auth-plugin"),
+        "aws" if macos => Some("brew install awscli"),
+        "aws" => Some("sudo snap install aws-cli --classic"),
+        "kubelogin" => Some("az aks install-cli"),
+        "aws-vault" => Some("brew install aws-vault"),
+        "assume" => Some("brew install common-fate/granted/granted"),
+        "tsh" => Some("brew install teleport"),
+        "kubectl-oidc_login" => Some("kubectl krew install oidc-login"),
+        _ => None,
+    }
+}
+
+// Bare names are looked up like the OS would, paths are taken as they are
+fn is_installed(command: &str) -> bool {
+    if command.contains(std::path::MAIN_SEPARATOR) {
+        return Path::new(command).is_file();
+    }
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(command).is_file()))
+        .unwrap_or(false)
+}
+
+// Explains how to fix a context whose user runs an exec plugin that isn't installed
+pub fn missing_plugin(kubeconfig: &Kubeconfig, name: &str) -> Option<String> {
+    let command = kubeconfig::context_user(kubeconfig, name)?
+        .auth_info
+        .as_ref()?
+        .exec
+        .as_ref()?
+        .command
+        .clone()?;
+    if is_installed(&command) {
+        return None;
+    }
+    let binary = Path::new(&command)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| command.clone());
+    Some(match install_command(&binary) {
+        Some(install) => format!("{} isn't installed, install it with: {}", command, install),
+        None => format!("{} isn't installed or isn't on the PATH", command),
+    })
+}
@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+pub const PROJECT_FILE: &str = ".ktx";
+
+// Either a bare context name or a map with the context and an optional namespace
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ProjectFile {
+    Context(String),
+    Pin(ProjectPin),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectPin {
+    pub context: String,
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+// Looks in the directory and its parents, so the pin applies anywhere inside the project
+pub fn find(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+pub fn load(path: &Path) -> Result<ProjectPin, Box<dyn Error + Send + Sync>> {
+    let content = std::fs::read_to_string(path)?;
+    let pin = match serde_yaml::from_str(&content)
+        .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?
+    {
+        ProjectFile::Context(context) => ProjectPin {
+            context: context.trim().to_string(),
+            namespace: None,
+        },
+        ProjectFile::Pin(pin) => pin,
+    };
+    if pin.context.is_empty() {
+        return Err(format!("{} doesn't name a context", path.display()).into());
+    }
+    Ok(pin)
+}
+
+pub fn hook(shell: &str) -> Result<&'static str, Box<dyn Error + Send + Sync>> {
+    match shell {
+        "bash" => Ok(r#"_ktx_hook() {
+  if [ "$PWD" != "$_KTX_LAST_PWD" ]; then
+    _KTX_LAST_PWD="$PWD"
+    ktx use --project --quiet
+  fi
+}
+case ";${PROMPT_COMMAND};" in
+  *";_ktx_hook;"*) ;;
+  *) PROMPT_COMMAND="_ktx_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+"#),
+        "zsh" => Ok(r#"_ktx_hook() {
+  ktx use --project --quiet
+}
+autoload -U add-zsh-hook
+add-zsh-hook chpwd _ktx_hook
+_ktx_hook
+"#),
+        "fish" => Ok(r#"function _ktx_hook --on-variable PWD
+    ktx use --project --quiet
+end
+_ktx_hook
+"#),
+        _ => Err(format!("Unsupported shell {}, expected bash, zsh or fish", shell).into()),
+    }
+}
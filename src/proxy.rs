@@ -0,0 +1,235 @@
+use std::error::Error;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use base64::Engine;
+use http::Uri;
+use hyper::client::HttpConnector;
+use hyper_timeout::TimeoutConnector;
+use kube::client::ConfigExt;
+use kube::config::Kubeconfig;
+use kube::{Client, Config};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tower::ServiceBuilder;
+
+use crate::config::IpFamily;
+use crate::kubeconfig;
+
+#[cfg(test)]
+mod tests;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn env_var(lookup: &impl Fn(&str) -> Option<String>, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| lookup(name))
+        .find(|value| !value.is_empty())
+}
+
+// Drops the port of "host:port" and "[::1]:port" entries, bare IPv6 addresses are kept whole
+fn strip_port(entry: &str) -> &str {
+    if let Some(rest) = entry.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    match entry.split_once(':') {
+        Some((host, port)) if !port.contains(':') => host,
+        _ => entry,
+    }
+}
+
+// Whether an address is in a range like 10.0.0.0/8 or fd00::/8
+fn in_cidr(ip: IpAddr, cidr: &str) -> bool {
+    let Some((network, prefix)) = cidr.split_once('/') else {
+        return false;
+    };
+    let (Ok(network), Ok(prefix)) = (network.parse::<IpAddr>(), prefix.parse::<u32>()) else {
+        return false;
+    };
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+fn matches_no_proxy(no_proxy: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let ip = host.parse::<IpAddr>().ok();
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry.contains('/') {
+                return ip.map(|ip| in_cidr(ip, entry)).unwrap_or(false);
+            }
+            let entry = strip_port(entry);
+            let domain = entry.trim_start_matches('.');
+            entry == "*" || host == domain || host.ends_with(&format!(".{}", domain))
+        })
+}
+
+// Picks the proxy for a context: an explicit `proxy-url` on its cluster always wins, otherwise
+// HTTPS_PROXY/HTTP_PROXY apply unless the server host is listed in NO_PROXY.
+pub fn proxy_for_context(kubeconfig: &Kubeconfig, context_name: &str) -> Option<String> {
+    proxy_from_env(kubeconfig, context_name, |name| std::env::var(name).ok())
+}
+
+fn proxy_from_env(
+    kubeconfig: &Kubeconfig,
+    context_name: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let cluster = kubeconfig::context_cluster(kubeconfig, context_name)?
+        .cluster
+        .as_ref()?;
+    if let Some(proxy_url) = cluster.proxy_url.clone().filter(|p| !p.is_empty()) {
+        return Some(proxy_url);
+    }
+    let server = cluster.server.as_ref()?.parse::<Uri>().ok()?;
+    let no_proxy = env_var(&lookup, &["NO_PROXY", "no_proxy"]).unwrap_or_default();
+    if matches_no_proxy(&no_proxy, server.host().unwrap_or("")) {
+        return None;
+    }
+    env_var(
+        &lookup,
+        &["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"],
+    )
+}
+
+// Only plain HTTP proxies are spoken to, TLS to the proxy and SOCKS aren't supported
+fn proxy_address(proxy: &Uri) -> Result<(&str, u16), BoxError> {
+    match proxy.scheme_str() {
+        None | Some("http") => {}
+        Some(scheme) => return Err(format!("Unsupported proxy scheme {}", scheme).into()),
+    }
+    let host = proxy.host().ok_or("Proxy URL has no host")?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Ok((host, proxy.port_u16().unwrap_or(80)))
+}
+
+async fn tunnel(proxy: Uri, dst: Uri) -> Result<TcpStream, BoxError> {
+    let (proxy_host, proxy_port) = proxy_address(&proxy)?;
+    let dst_host = dst.host().ok_or("Server URL has no host")?;
+    let dst_port = dst.port_u16().unwrap_or(443);
+    let mut stream = tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        TcpStream::connect((proxy_host, proxy_port)),
+    )
+    .await??;
+    let mut request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+        host = dst_host,
+        port = dst_port
+    );
+    if let Some((userinfo, _)) = proxy.authority().and_then(|a| a.as_str().rsplit_once('@')) {
+        let credentials = base64::engine::general_purpose::STANDARD.encode(userinfo);
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !response.ends_with(b"\r\n\r\n") {
+        let n = tokio::time::timeout(CONNECT_TIMEOUT, stream.read(&mut buf)).await??;
+        if n == 0 {
+            return Err("Proxy closed the connection".into());
+        }
+        response.extend_from_slice(&buf[..n]);
+    }
+    let status_line = String::from_utf8_lossy(&response);
+    let status_line = status_line.lines().next().unwrap_or("");
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("Proxy refused to connect: {}", status_line).into());
+    }
+    Ok(stream)
+}
+
+#[derive(Clone)]
+struct ProxyConnector {
+    proxy: Uri,
+}
+
+impl tower::Service<Uri> for ProxyConnector {
+    type Response = TcpStream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<TcpStream, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        Box::pin(tunnel(self.proxy.clone(), dst))
+    }
+}
+
+// kube builds its own connector and ignores `proxy_url`, so proxied clients get a custom stack
+// that tunnels TLS through an HTTP CONNECT proxy. The same goes for clients limited to one
+// address family.
+pub fn client_with_proxy(
+    config: Config,
+    proxy: Option<String>,
+    family: IpFamily,
+) -> Result<Client, BoxError> {
+    let proxy = match proxy {
+        Some(proxy) => {
+            let proxy = proxy.parse::<Uri>()?;
+            proxy_address(&proxy)?;
+            proxy
+        }
+        None if family == IpFamily::Auto => return Ok(Client::try_from(config)?),
+        None => {
+            let mut http = HttpConnector::new();
+            http.enforce_http(false);
+            http.set_local_address(family.local_address());
+            http.set_connect_timeout(config.connect_timeout);
+            return client_with_connector(config, http);
+        }
+    };
+    client_with_connector(config, ProxyConnector { proxy })
+}
+
+fn client_with_connector<C>(config: Config, connector: C) -> Result<Client, BoxError>
+where
+    C: tower::Service<Uri, Response = TcpStream> + Clone + Send + Sync + 'static,
+    C::Future: Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    let mut https = hyper_openssl::HttpsConnector::with_connector(
+        connector,
+        config.openssl_ssl_connector_builder()?,
+    )?;
+    if config.accept_invalid_certs {
+        https.set_callback(|ssl, _uri| {
+            ssl.set_verify(openssl::ssl::SslVerifyMode::NONE);
+            Ok(())
+        });
+    }
+    // The same timeouts kube sets up for the clients it builds itself
+    let mut connector = TimeoutConnector::new(https);
+    connector.set_connect_timeout(config.connect_timeout);
+    connector.set_read_timeout(config.read_timeout);
+    connector.set_write_timeout(config.write_timeout);
+    let client: hyper::Client<_, hyper::Body> = hyper::Client::builder().build(connector);
+    let service = ServiceBuilder::new()
+        .layer(config.base_uri_layer())
+        .option_layer(config.auth_layer()?)
+        .layer(config.extra_headers_layer()?)
+        .service(client);
+    Ok(Client::new(service, config.default_namespace))
+}
@@ -0,0 +1,100 @@
+use http::Uri;
+use kube::config::Kubeconfig;
+
+use super::{matches_no_proxy, proxy_address, proxy_from_env};
+
+fn kubeconfig(server: &str, proxy_url: Option<&str>) -> Kubeconfig {
+    let proxy_url = proxy_url
+        .map(|url| format!("\n    proxy-url: {}", url))
+        .unwrap_or_default();
+    serde_yaml::from_str(&format!(
+        r#"
+clusters:
+- name: lab
+  cluster:
+    server: {}{}
+contexts:
+- name: lab
+  context:
+    cluster: lab
+    user: lab
+users:
+- name: lab
+  user: {{}}
+"#,
+        server, proxy_url
+    ))
+    .unwrap()
+}
+
+#[test]
+fn no_proxy_matches_domains_addresses_and_ranges() {
+    let no_proxy = "localhost, .corp.example:8443, 10.0.0.0/8, fd00::/8, [::1]:6443, ::2";
+    assert!(matches_no_proxy(no_proxy, "localhost"));
+    assert!(matches_no_proxy(no_proxy, "api.corp.example"));
+    assert!(matches_no_proxy(no_proxy, "corp.example"));
+    assert!(matches_no_proxy(no_proxy, "10.12.0.5"));
+    assert!(matches_no_proxy(no_proxy, "[fd00::12]"));
+    assert!(matches_no_proxy(no_proxy, "[::1]"));
+    assert!(matches_no_proxy(no_proxy, "::2"));
+    assert!(!matches_no_proxy(no_proxy, "11.0.0.1"));
+    assert!(!matches_no_proxy(no_proxy, "api.example"));
+    assert!(!matches_no_proxy(no_proxy, "[fe80::1]"));
+    assert!(matches_no_proxy("*", "anything"));
+}
+
+#[test]
+fn only_http_proxies_are_supported() {
+    let address = |url: &str| {
+        proxy_address(&url.parse::<Uri>().unwrap())
+            .map(|(host, port)| (host.to_string(), port))
+            .map_err(|e| e.to_string())
+    };
+    assert_eq!(address("http://proxy"), Ok(("proxy".to_string(), 80)));
+    assert_eq!(address("http://[::1]:3128"), Ok(("::1".to_string(), 3128)));
+    assert_eq!(
+        address("https://proxy"),
+        Err("Unsupported proxy scheme https".to_string())
+    );
+    assert_eq!(
+        address("socks5://proxy:1080"),
+        Err("Unsupported proxy scheme socks5".to_string())
+    );
+}
+
+#[test]
+fn proxy_url_wins_over_the_environment() {
+    let env = |name: &str| match name {
+        "HTTPS_PROXY" => Some("http://env-proxy:3128".to_string()),
+        "NO_PROXY" => Some("10.0.0.0/8".to_string()),
+        _ => None,
+    };
+    let proxied = kubeconfig("https://api.example:6443", None);
+    assert_eq!(
+        proxy_from_env(&proxied, "lab", env).as_deref(),
+        Some("http://env-proxy:3128")
+    );
+    let excluded = kubeconfig("https://10.1.2.3:6443", None);
+    assert_eq!(proxy_from_env(&excluded, "lab", env), None);
+    let explicit = kubeconfig("https://10.1.2.3:6443", Some("http://cluster-proxy:8080"));
+    assert_eq!(
+        proxy_from_env(&explicit, "lab", env).as_deref(),
+        Some("http://cluster-proxy:8080")
+    );
+    assert_eq!(proxy_from_env(&explicit, "missing", env), None);
+}
+
+#[test]
+fn empty_proxy_variables_fall_through_to_the_next() {
+    let env = |name: &str| match name {
+        "HTTPS_PROXY" => Some(String::new()),
+        "http_proxy" => Some("http://lower-proxy:3128".to_string()),
+        _ => None,
+    };
+    let proxied = kubeconfig("https://api.example:6443", None);
+    assert_eq!(
+        proxy_from_env(&proxied, "lab", env).as_deref(),
+        Some("http://lower-proxy:3128")
+    );
+    assert_eq!(proxy_from_env(&proxied, "lab", |_| None), None);
+}
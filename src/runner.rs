@@ -0,0 +1,90 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// Everything that shells out to the cloud CLIs goes through a runner, so the provider flows can
+// be exercised against recorded outputs.
+#[async_trait]
+pub trait CommandRunner: Send + Sync {
+    async fn exec_to_str(&self, cmd: &str, args: &[&str]) -> Result<String, BoxError>;
+
+    async fn exec_to_json(&self, cmd: &str, args: &[&str]) -> Result<serde_json::Value, BoxError> {
+        let output = self.exec_to_str(cmd, args).await?;
+        let json: serde_json::Value = serde_json::from_str(&output)?;
+        Ok(json)
+    }
+}
+
+pub type DynCommandRunner = Arc<dyn CommandRunner>;
+
+pub struct SystemCommandRunner;
+
+#[async_trait]
+impl CommandRunner for SystemCommandRunner {
+    async fn exec_to_str(&self, cmd: &str, args: &[&str]) -> Result<String, BoxError> {
+        log::debug!("Running {} {}", cmd, args.join(" "));
+        let output = tokio::process::Command::new(cmd)
+            .args(args)
+            // Cancelled background tasks drop the future, which shouldn't leave the CLI running
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            log::warn!("{} exited with {}: {}", cmd, output.status, stderr.trim());
+            return Err(Box::new(std::io::Error::other(stderr.to_string())));
+        }
+        let output = String::from_utf8_lossy(&output.stdout);
+        Ok(output.to_string())
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // Replays canned outputs keyed by the full command line and fails for anything else, like a
+    // machine where the CLI isn't installed.
+    #[derive(Default)]
+    pub struct MockCommandRunner {
+        outputs: HashMap<String, String>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl MockCommandRunner {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn with_output(mut self, command_line: &str, output: &str) -> Self {
+            self.outputs
+                .insert(command_line.to_string(), output.to_string());
+            self
+        }
+
+        pub fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl CommandRunner for MockCommandRunner {
+        async fn exec_to_str(&self, cmd: &str, args: &[&str]) -> Result<String, BoxError> {
+            let command_line = std::iter::once(cmd)
+                .chain(args.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.calls.lock().unwrap().push(command_line.clone());
+            self.outputs
+                .get(&command_line)
+                .cloned()
+                .ok_or_else(|| format!("unexpected command: {}", command_line).into())
+        }
+    }
+}
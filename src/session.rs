@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cloud::ImportOption;
+use crate::kubeconfig;
+
+// Filter and selected item a view had when it was last closed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewMemory {
+    pub filter: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+    // First visible row
+    #[serde(skip_serializing_if = "is_zero")]
+    pub offset: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+// An import wizard listing that was open on exit, reopened on top of the context list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenView {
+    pub path: Vec<ImportOption>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+}
+
+// UI state carried over between runs, unlike metadata nothing here is worth keeping in history
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    // Keyed by the view, e.g. "contexts" or "import/aws/default"
+    pub views: BTreeMap<String, ViewMemory>,
+    // Views above the context list, bottom first, when restore_session is on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stack: Vec<OpenView>,
+}
+
+impl SessionState {
+    // The session lives next to the metadata file
+    pub fn path(metadata_path: &str) -> String {
+        Path::new(metadata_path)
+            .with_file_name("session.yaml")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    // A broken session file only loses remembered filters, so it starts over instead of failing
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn save(&self, path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        kubeconfig::write_atomically(path, &serde_yaml::to_string(self)?).await
+    }
+}
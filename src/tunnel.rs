@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::process::Stdio;
+use std::time::Duration;
+
+use http::Uri;
+use kube::config::Kubeconfig;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+use crate::kubeconfig;
+use crate::metadata::KtxMetadata;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+const TUNNEL_READY_TIMEOUT: Duration = Duration::from_secs(15);
+const TUNNEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Forwards a local port to the API server through an SSH jump host, for clusters with private
+// endpoints. Host aliases, keys and users come from the regular ssh configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshTunnel {
+    pub jump_host: String,
+    // A free port is picked when left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+}
+
+struct Tunnel {
+    spec: SshTunnel,
+    local_port: u16,
+    // Killed when the tunnel is dropped
+    child: Child,
+}
+
+impl Tunnel {
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+async fn free_port() -> Result<u16, BoxError> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    Ok(listener.local_addr()?.port())
+}
+
+async fn open(spec: &SshTunnel, host: &str, port: u16) -> Result<Tunnel, BoxError> {
+    let local_port = match spec.local_port {
+        Some(port) => port,
+        None => free_port().await?,
+    };
+    log::info!(
+        "Opening tunnel to {}:{} via {} on port {}",
+        host,
+        port,
+        spec.jump_host,
+        local_port
+    );
+    let mut child = Command::new("ssh")
+        .args([
+            "-N",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "BatchMode=yes",
+        ])
+        .arg("-L")
+        .arg(format!("127.0.0.1:{}:{}:{}", local_port, host, port))
+        .arg(&spec.jump_host)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Unable to start ssh: {}", e))?;
+    let started = tokio::time::Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Err(format!("ssh to {} exited with {}", spec.jump_host, status).into());
+        }
+        if TcpStream::connect(("127.0.0.1", local_port)).await.is_ok() {
+            break;
+        }
+        if started.elapsed() > TUNNEL_READY_TIMEOUT {
+            return Err(format!("Timed out opening a tunnel via {}", spec.jump_host).into());
+        }
+        tokio::time::sleep(TUNNEL_POLL_INTERVAL).await;
+    }
+    Ok(Tunnel {
+        spec: spec.clone(),
+        local_port,
+        child,
+    })
+}
+
+// Tunnels stay open for the whole session so repeated checks and shells reuse them
+#[derive(Default)]
+pub struct TunnelPool {
+    tunnels: Mutex<HashMap<String, Tunnel>>,
+}
+
+impl TunnelPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns the kubeconfig to use for a context: unchanged without a tunnel, otherwise with the
+    // cluster's server pointing at the local end of the tunnel
+    pub async fn effective_kubeconfig(
+        &self,
+        kubeconfig: &Kubeconfig,
+        metadata: &KtxMetadata,
+        name: &str,
+    ) -> Result<Kubeconfig, BoxError> {
+        let Some(spec) = metadata.contexts.get(name).and_then(|m| m.tunnel.clone()) else {
+            return Ok(kubeconfig.clone());
+        };
+        let server = kubeconfig::context_cluster(kubeconfig, name)
+            .and_then(|c| c.cluster.as_ref())
+            .and_then(|c| c.server.clone())
+            .ok_or(format!("Context {} has no server", name))?
+            .parse::<Uri>()?;
+        let host = server
+            .host()
+            .ok_or("The server URL has no host")?
+            .trim_matches(|c| c == '[' || c == ']')
+            .to_string();
+        let port = server.port_u16().unwrap_or(443);
+
+        let mut tunnels = self.tunnels.lock().await;
+        let reusable = tunnels
+            .get_mut(name)
+            .map(|tunnel| tunnel.spec == spec && tunnel.is_alive())
+            .unwrap_or(false);
+        if !reusable {
+            tunnels.remove(name);
+            tunnels.insert(name.to_string(), open(&spec, &host, port).await?);
+        }
+        let local_port = tunnels[name].local_port;
+        drop(tunnels);
+
+        let mut kubeconfig = kubeconfig.clone();
+        let cluster = kubeconfig::context_cluster_mut(&mut kubeconfig, name)
+            .ok_or(format!("Context {} has no cluster", name))?;
+        cluster.server = Some(format!("https://127.0.0.1:{}", local_port));
+        // The certificate is still issued for the real host
+        if cluster.tls_server_name.is_none() {
+            cluster.tls_server_name = Some(host);
+        }
+        Ok(kubeconfig)
+    }
+
+    pub async fn close_all(&self) {
+        self.tunnels.lock().await.clear();
+    }
+}
@@ -1,22 +1,51 @@
-use crate::ui::types::ViewState;
+use crate::audit;
+use crate::cloud::{self, ImportOption};
+use crate::cluster::{self, ClusterInfo};
+use crate::config::{ImportCurrentContext, IpFamily, KtxConfig, NamespaceOnSwitch, QuickSwitch};
+use crate::ephemeral;
+use crate::history;
+use crate::kubeconfig::{self, ConflictResolution, ContextChange, EntryKind};
+use crate::metadata::{self, KtxMetadata, Provenance};
+use crate::runner::DynCommandRunner;
+use crate::session::{OpenView, SessionState, ViewMemory};
+use crate::tunnel::TunnelPool;
+use crate::ui::tasks::{TaskId, TaskOutcome, TaskPool, TASK_QUEUE_CAPACITY, TASK_WORKERS};
+use crate::ui::types::{ImportListing, ImportOutcome, MessageLevel, Shared, UiMessage, ViewState};
+use crate::ui::views::audit::AuditLogView;
+use crate::ui::views::banner::render_banner;
+use crate::ui::views::bench::{BenchmarkView, BENCHMARK_ROUNDS};
 use crate::ui::views::confirmation::ConfirmationDialogView;
+use crate::ui::views::conflict::ImportConflictDialogView;
+use crate::ui::views::dashboard::{self, HealthDashboardView};
+use crate::ui::views::detail::ContextDetailView;
+use crate::ui::views::entries::EntriesView;
+use crate::ui::views::history::HistoryView;
+use crate::ui::views::input::InputDialogView;
 use crate::ui::views::list::ContextListView;
-use crate::ui::{KtxEvent, KubeContextStatus, RendererMessage};
+use crate::ui::views::namespaces::NamespaceListView;
+use crate::ui::views::picker::EntryPickerView;
+use crate::ui::views::recent::RecentContextsView;
+use crate::ui::views::summary::ImportSummaryView;
+use crate::ui::views::toast;
+use crate::ui::views::unhealthy::UnhealthySwitchDialogView;
+use crate::ui::{CloudImportPath, HealthSample, KtxEvent, KubeContextStatus, RendererMessage};
 use async_trait::async_trait;
-use crossterm::event::{self, Event, KeyCode};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use crossterm::event::{
+    self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyModifiers,
+};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
 use futures::stream::StreamExt;
-use k8s_openapi::apimachinery::pkg::version::Info;
-use kube::config::{KubeConfigOptions, Kubeconfig, NamedContext};
-use kube::{Client, Config};
+use kube::config::{Kubeconfig, NamedContext};
 use std::error::Error;
-use std::fmt;
+use std::io;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, watch, Mutex};
 use tui::layout::{Alignment, Constraint, Direction, Layout};
 use tui::style::{Color, Style};
 use tui::widgets::{Block, Borders, Paragraph, Wrap};
@@ -25,7 +54,26 @@ use tui::{backend::Backend, layout::Rect, Frame};
 use super::types::EmptyResult;
 use super::views::import::ImportView;
 
-pub type DynAppView<B> = Box<dyn AppView<B> + Send + Sync>;
+#[cfg(test)]
+mod tests;
+
+const RENDER_INTERVAL: Duration = Duration::from_millis(500);
+// Enough for about half an hour of dashboard polling
+const HEALTH_HISTORY_LENGTH: usize = 120;
+const SWITCH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const IMPORT_WAIT_INTERVAL: Duration = Duration::from_secs(5);
+const RECENT_CONTEXTS_LENGTH: usize = 5;
+const MAX_TOASTS: usize = 5;
+const REFRESH_CONCURRENCY: usize = 8;
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(250);
+// Smaller terminals only get asked to be enlarged
+const MIN_TERMINAL_WIDTH: u16 = 40;
+const MIN_TERMINAL_HEIGHT: u16 = 10;
+// Below this the margins and the bordered top bar are dropped to leave room for the view
+const COMPACT_TERMINAL_WIDTH: u16 = 80;
+const COMPACT_TERMINAL_HEIGHT: u16 = 20;
+
+pub type DynAppView<B> = Arc<dyn AppView<B> + Send + Sync>;
 pub type HandleEventResult = Result<Option<KtxEvent>, Box<dyn Error + Send + Sync>>;
 
 #[async_trait]
@@ -41,48 +89,110 @@ where
     async fn get_filter(&self) -> String {
         "".to_string()
     }
+    // Views that remember their filter and selection between visits are stored under this key
+    fn memory_key(&self) -> Option<String> {
+        None
+    }
+    async fn get_memory(&self, _state: &AppState) -> Option<ViewMemory> {
+        None
+    }
+    async fn restore_memory(&self, _memory: ViewMemory, _state: &AppState) {}
+    // Views that can be reopened on the next launch
+    fn open_view(&self) -> Option<OpenView> {
+        None
+    }
+    // Moves the selection to the first item matching what was typed in jump mode
+    async fn jump_to(&self, _text: &str, _state: &AppState) {}
 }
 
-#[derive(Debug)]
-struct ConnectionError {}
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppState {
+    pub is_filter_on: bool,
+    // Typed text while jump mode is on, unlike the filter it only moves the selection
+    pub jump: Option<String>,
+    pub kubeconfig: Shared<Kubeconfig>,
+    pub kubeconfig_path: String,
+    pub kubeconfig_sources: kubeconfig::Sources,
+    pub config: Shared<KtxConfig>,
+    pub connectivity_status: std::collections::HashMap<String, KubeContextStatus>,
+    pub cluster_info: std::collections::HashMap<String, ClusterInfo>,
+    pub health_history: std::collections::HashMap<String, Vec<HealthSample>>,
+    // Error of the last failed check, until the context is healthy again
+    pub health_failures: std::collections::HashMap<String, String>,
+    pub cert_expiry: std::collections::HashMap<String, chrono::DateTime<chrono::Utc>>,
+    pub metadata: Shared<KtxMetadata>,
+    pub metadata_path: String,
+    // Providers detected when the import view was first opened, kept for the session
+    pub cloud_providers: Option<Vec<ImportOption>>,
+    // What each import wizard level listed and when, by preset and path key
+    pub listing_cache: std::collections::HashMap<
+        (Option<String>, String),
+        (chrono::DateTime<chrono::Utc>, ImportListing),
+    >,
+    // Background tasks by id, with their label
+    pub queued_tasks: std::collections::BTreeMap<TaskId, String>,
+    pub running_tasks: std::collections::BTreeMap<TaskId, String>,
+    // current-context when the imports still running started, restored after each of them
+    pub context_before_import: Option<String>,
+    pub session: SessionState,
+    // Toasts still on screen, oldest first
+    pub messages: Vec<UiMessage>,
+}
 
-impl Error for ConnectionError {}
+// The view on top and a copy of its state, taken after each event for the renderer
+struct ViewSnapshot<B: Backend + Send + Sync> {
+    view: DynAppView<B>,
+    view_state: ViewState,
+    filter: String,
+}
 
-impl fmt::Display for ConnectionError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Connection is Unhealthy")
+impl<B: Backend + Send + Sync> ViewSnapshot<B> {
+    fn offset(&mut self) -> Option<usize> {
+        self.view_state.list_state().map(|s| s.offset())
     }
-}
 
-#[derive(Debug, Clone)]
-enum UiMessage {
-    Error(String),
-    Info(String),
-    Success(String),
+    // Scroll offsets are only worked out while drawing
+    fn scroll_like(&mut self, drawn: &mut ViewSnapshot<B>) {
+        if let (Some(next), Some(drawn)) = (self.view_state.list_state(), drawn.offset()) {
+            *next.offset_mut() = drawn;
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct AppState {
-    pub is_filter_on: bool,
-    pub kubeconfig: Kubeconfig,
-    pub kubeconfig_path: String,
-    pub connectivity_status: std::collections::HashMap<String, KubeContextStatus>,
-    pub config_lock: Arc<Mutex<()>>,
-    last_message: Option<UiMessage>,
-    last_message_timestamp: Option<chrono::DateTime<chrono::Utc>>,
+impl<B: Backend + Send + Sync> Clone for ViewSnapshot<B> {
+    fn clone(&self) -> Self {
+        Self {
+            view: self.view.clone(),
+            view_state: self.view_state.clone(),
+            filter: self.filter.clone(),
+        }
+    }
 }
 
 pub struct KtxApp<B: Backend + Send + Sync> {
-    state: Arc<Mutex<AppState>>,
+    // Only the event loop touches the live state and the views, the renderer draws published
+    // snapshots
+    state: Mutex<AppState>,
+    state_tx: watch::Sender<Arc<AppState>>,
+    view_tx: watch::Sender<Option<ViewSnapshot<B>>>,
+    runner: DynCommandRunner,
     view_stack: Arc<Mutex<Vec<DynAppView<B>>>>,
     event_bus_tx: mpsc::Sender<KtxEvent>,
     terminal: Mutex<tui::Terminal<B>>,
+    tunnels: Arc<TunnelPool>,
+    tasks: Arc<TaskPool>,
+    // Set while a spawned shell owns the terminal, Ctrl-C is meant for it then
+    in_shell: AtomicBool,
+    // A reload is scheduled, further RefreshConfig events ride along with it
+    refresh_pending: AtomicBool,
 }
 
 impl AppState {
+    // Ephemeral contexts are grouped after the others, they pile up quickly
     pub fn get_filtered_contexts(&self, filter: &str) -> Vec<(NamedContext, KubeContextStatus)> {
         let kubeconfig = &self.kubeconfig;
         let connectivity_status = &self.connectivity_status;
+        let ephemeral = self.ephemeral_contexts();
         let mut filtered_contexts = Vec::new();
         for context in &kubeconfig.contexts {
             if context
@@ -96,7 +206,198 @@ impl AppState {
                 filtered_contexts.push((context.clone(), status.clone()));
             }
         }
-        return filtered_contexts;
+        filtered_contexts.sort_by_key(|(context, _)| ephemeral.contains(&context.name));
+        filtered_contexts
+    }
+
+    pub fn ephemeral_contexts(&self) -> std::collections::HashSet<String> {
+        let detectors = ephemeral::detectors(&self.config);
+        self.kubeconfig
+            .contexts
+            .iter()
+            .map(|c| &c.name)
+            .filter(|name| {
+                let metadata = self.metadata.contexts.get(*name);
+                detectors.iter().any(|d| d.is_ephemeral(name, metadata))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn context_provider(&self, name: &str) -> Option<String> {
+        metadata::context_provider(&self.metadata, &self.kubeconfig, name)
+    }
+
+    pub fn aws_identity(&self, name: &str) -> Option<metadata::AwsIdentity> {
+        metadata::aws_identity(&self.metadata, &self.kubeconfig, name)
+    }
+
+    // Repeating a message that is still shown restarts it instead of stacking a copy
+    fn push_message(&mut self, message: UiMessage) {
+        let now = chrono::Utc::now();
+        self.messages.retain(|m| {
+            m.expires_at > now && !(m.level == message.level && m.text == message.text)
+        });
+        self.messages.push(message);
+        if self.messages.len() > MAX_TOASTS {
+            self.messages.remove(0);
+        }
+    }
+
+    fn forget_status(&mut self, name: &str) {
+        self.connectivity_status.remove(name);
+        self.cluster_info.remove(name);
+        self.health_failures.remove(name);
+        self.cert_expiry.remove(name);
+    }
+
+    // Takes over the kubeconfig read again from disk, forgetting what was found out about
+    // contexts that are gone or point somewhere else now
+    fn apply_reload(
+        &mut self,
+        kubeconfig: Kubeconfig,
+        sources: kubeconfig::Sources,
+    ) -> Vec<ContextChange> {
+        let changes = kubeconfig::diff_contexts(&self.kubeconfig, &kubeconfig);
+        (self.kubeconfig, self.kubeconfig_sources) = (kubeconfig.into(), sources);
+        for change in &changes {
+            match change {
+                ContextChange::Added(_) => {}
+                ContextChange::Removed(name) => {
+                    self.forget_status(name);
+                    self.health_history.remove(name);
+                }
+                // Whatever was checked was checked against the old server or credentials
+                ContextChange::Changed(name) => self.forget_status(name),
+            }
+        }
+        changes
+    }
+
+    fn record_health(
+        &mut self,
+        name: String,
+        status: KubeContextStatus,
+        latency: Option<Duration>,
+    ) {
+        // Progress updates while waiting for a new cluster aren't check results
+        if !matches!(status, KubeContextStatus::Waiting(_)) {
+            let history = self.health_history.entry(name.clone()).or_default();
+            history.push(HealthSample {
+                checked_at: chrono::Utc::now(),
+                status: status.clone(),
+                latency,
+            });
+            if history.len() > HEALTH_HISTORY_LENGTH {
+                history.remove(0);
+            }
+        }
+        if matches!(status, KubeContextStatus::Healthy(_)) {
+            self.health_failures.remove(&name);
+        }
+        self.connectivity_status.insert(name, status);
+    }
+
+    // Targets of the 1-9 shortcuts. Recent leaves out the current context, so 1 goes back to the
+    // previous one.
+    pub fn quick_switch_targets(&self) -> Vec<String> {
+        let mut targets: Vec<String> = match self.config.quick_switch {
+            QuickSwitch::Off => vec![],
+            QuickSwitch::Pinned => self
+                .kubeconfig
+                .contexts
+                .iter()
+                .map(|c| c.name.clone())
+                .filter(|n| self.metadata.is_pinned(n))
+                .collect(),
+            QuickSwitch::Recent => self.recent_contexts(),
+        };
+        targets.truncate(9);
+        targets
+    }
+
+    // Most recently used first, without the current context
+    pub fn recent_contexts(&self) -> Vec<String> {
+        let mut recent: Vec<(chrono::DateTime<chrono::Utc>, String)> = self
+            .kubeconfig
+            .contexts
+            .iter()
+            .map(|c| c.name.clone())
+            .filter(|n| self.kubeconfig.current_context.as_ref() != Some(n))
+            .filter_map(|n| self.metadata.last_used(&n).map(|used| (used, n)))
+            .collect();
+        recent.sort_by_key(|(used, _)| std::cmp::Reverse(*used));
+        recent.into_iter().map(|(_, n)| n).collect()
+    }
+
+    // Shared contexts can't have their namespace changed
+    fn remembered_namespace(&self, name: &str) -> Option<String> {
+        if self.config.namespace_on_switch == NamespaceOnSwitch::Keep
+            || self.kubeconfig_sources.is_read_only(name)
+        {
+            return None;
+        }
+        self.metadata.namespace(name)
+    }
+
+    // Finishes a switch to a context that is fine to switch to, going through the namespace
+    // popup first when asked to
+    fn switch_event(&self, name: String) -> KtxEvent {
+        if self.config.namespace_on_switch == NamespaceOnSwitch::Prompt
+            && !self.config.offline
+            && !self.kubeconfig_sources.is_read_only(&name)
+        {
+            return KtxEvent::ShowNamespaces(name);
+        }
+        let namespace = self.remembered_namespace(&name);
+        KtxEvent::SetContext((name, namespace))
+    }
+
+    // The context an import would make current in place of another one. Imports into a kubeconfig
+    // without a current context just set it.
+    fn retargeted_context(&self, incoming: &Kubeconfig) -> Option<String> {
+        let current = self.kubeconfig.current_context.as_ref()?;
+        incoming
+            .current_context
+            .clone()
+            .filter(|name| name != current)
+    }
+
+    // Imports overlapping each other keep the context from before the first one
+    fn record_context_before_import(&mut self) {
+        if self.context_before_import.is_none() {
+            self.context_before_import = self.kubeconfig.current_context.clone();
+        }
+    }
+
+    pub fn has_background_tasks(&self) -> bool {
+        !self.queued_tasks.is_empty() || !self.running_tasks.is_empty()
+    }
+
+    // Users of the shared kubeconfig are left alone
+    fn normalized_kubeconfig(&self) -> (Kubeconfig, Vec<String>) {
+        let mut normalized = (*self.kubeconfig).clone();
+        let preview = kubeconfig::normalize(&mut normalized, |user| {
+            self.kubeconfig_sources
+                .check_entry_writable(&self.kubeconfig, EntryKind::User, user)
+                .is_ok()
+        });
+        (normalized, preview)
+    }
+
+    // Contexts still in the kubeconfig whose cloud import can be run again. Shared ones are left
+    // out since their file is never written.
+    fn refreshable_contexts(&self) -> Vec<(String, Provenance, CloudImportPath)> {
+        self.kubeconfig
+            .contexts
+            .iter()
+            .filter(|c| !self.kubeconfig_sources.is_read_only(&c.name))
+            .filter_map(|c| {
+                let provenance = self.metadata.provenance(&c.name)?;
+                let import_path = CloudImportPath::from_provenance(&provenance)?;
+                Some((c.name.clone(), provenance, import_path))
+            })
+            .collect()
     }
 
     pub fn is_current_context(&self, context: &NamedContext) -> bool {
@@ -113,85 +414,497 @@ where
 {
     pub fn new(
         kubeconfig_path: String,
+        config: KtxConfig,
+        metadata_path: String,
+        runner: DynCommandRunner,
         terminal: tui::Terminal<B>,
         event_bus_tx: mpsc::Sender<KtxEvent>,
+        task_events_tx: mpsc::UnboundedSender<KtxEvent>,
     ) -> Self {
-        let kubeconfig =
-            Kubeconfig::read_from(&kubeconfig_path).expect("Unable to read kubeconfig");
+        let (kubeconfig, kubeconfig_sources) =
+            kubeconfig::load(&kubeconfig_path, config.shared_kubeconfig.as_deref())
+                .expect("Unable to read kubeconfig");
+        let metadata = KtxMetadata::load(&metadata_path).expect("Unable to read ktx metadata");
+        let session = if config.remembers_views() {
+            SessionState::load(&SessionState::path(&metadata_path))
+        } else {
+            SessionState::default()
+        };
+        let state = AppState {
+            is_filter_on: false,
+            jump: None,
+            kubeconfig_path,
+            kubeconfig_sources,
+            config: config.into(),
+            connectivity_status: std::collections::HashMap::new(),
+            cluster_info: std::collections::HashMap::new(),
+            health_history: std::collections::HashMap::new(),
+            health_failures: std::collections::HashMap::new(),
+            cert_expiry: std::collections::HashMap::new(),
+            metadata: metadata.into(),
+            metadata_path,
+            kubeconfig: kubeconfig.into(),
+            cloud_providers: None,
+            listing_cache: std::collections::HashMap::new(),
+            queued_tasks: std::collections::BTreeMap::new(),
+            running_tasks: std::collections::BTreeMap::new(),
+            context_before_import: None,
+            session,
+            messages: vec![],
+        };
+        let (state_tx, _) = watch::channel(Arc::new(state.clone()));
+        let (view_tx, _) = watch::channel(None);
         Self {
-            state: Arc::new(Mutex::new(AppState {
-                is_filter_on: false,
-                kubeconfig_path,
-                connectivity_status: std::collections::HashMap::new(),
-                kubeconfig,
-                last_message: None,
-                last_message_timestamp: None,
-                config_lock: Arc::new(Mutex::new(())),
-            })),
-            event_bus_tx,
+            state: Mutex::new(state),
+            state_tx,
+            view_tx,
+            runner,
+            event_bus_tx: event_bus_tx.clone(),
             view_stack: Arc::new(Mutex::new(Vec::new())),
             terminal: Mutex::new(terminal),
+            tunnels: Arc::new(TunnelPool::new()),
+            tasks: Arc::new(TaskPool::new(
+                TASK_WORKERS,
+                TASK_QUEUE_CAPACITY,
+                task_events_tx,
+            )),
+            in_shell: AtomicBool::new(false),
+            refresh_pending: AtomicBool::new(false),
         }
     }
 
     pub async fn start(&self) {
         let mut view_stack = self.view_stack.lock().await;
-        view_stack.push(Box::new(ContextListView::new::<B>(
+        let state = self.state.lock().await;
+        let poll_interval = state.config.health_poll_interval;
+        let list_view: DynAppView<B> = Arc::new(ContextListView::new(
             self.event_bus_tx.clone(),
-        )));
+            state.config.split_pane,
+        ));
+        self.restore_view(list_view.as_ref(), &state).await;
+        view_stack.push(list_view);
+        // Reopened through the usual events, which load each listing again
+        let reopen = if state.config.restore_session && !state.config.offline {
+            state.session.stack.clone()
+        } else {
+            vec![]
+        };
+        drop(state);
+        drop(view_stack);
+        self.publish_view().await;
+        for view in reopen {
+            let event = match view.preset {
+                Some(name) => KtxEvent::ShowImportPreset(name),
+                None => KtxEvent::ShowImportView(CloudImportPath::from(view.path)),
+            };
+            let _ = self.event_bus_tx.send(event).await;
+        }
+        if poll_interval > 0 {
+            let event_bus_tx = self.event_bus_tx.clone();
+            // A timer for the whole session, it would hold a task pool worker forever
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_secs(poll_interval));
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                // The first tick fires right away, statuses are checked on demand until then
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    if event_bus_tx.send(KtxEvent::PollConnections).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
     }
 
-    async fn test_connections(&self, state: &AppState) -> EmptyResult {
+    // Quiet checks only update statuses, for polling without flooding the message bar
+    async fn test_connections(&self, state: &AppState, quiet: bool) -> EmptyResult {
         let kubeconfig = state.kubeconfig.clone();
-        let contexts = state.kubeconfig.contexts.clone();
+        let metadata = state.metadata.clone();
+        let (contexts, excluded): (Vec<_>, Vec<_>) = state
+            .kubeconfig
+            .contexts
+            .iter()
+            .cloned()
+            .partition(|c| !state.config.is_excluded_from_sweeps(&c.name));
         let event_bus = self.event_bus_tx.clone();
-        tokio::spawn(async move {
-            let mut handles: Vec<_> = vec![];
-            for context in contexts {
+        if !quiet && !excluded.is_empty() {
+            let _ = event_bus
+                .send(KtxEvent::PushInfoMessage(format!(
+                    "Skipping {} context(s) excluded from health checks",
+                    excluded.len()
+                )))
+                .await;
+        }
+        let tunnels = self.tunnels.clone();
+        let checks: Vec<_> = contexts
+            .into_iter()
+            .enumerate()
+            .map(|(index, context)| {
+                let family = state.config.ip_family_for(&context.name);
+                let probe = state.config.health_probe_for(&context.name);
                 let kubeconfig = kubeconfig.clone();
+                let metadata = metadata.clone();
                 let event_bus = event_bus.clone();
-                let context = context.clone();
-                let handle = tokio::spawn(async move {
+                let tunnels = tunnels.clone();
+                async move {
+                    // Let the eventloop chill for a bit to avoid freezing the UI
+                    tokio::time::sleep(Duration::from_millis(100) * index as u32).await;
                     let name = context.name.clone();
-                    let options = KubeConfigOptions {
-                        context: Some(name.clone()),
-                        cluster: None,
-                        user: None,
-                    };
-                    let status = match async {
-                        let config = Config::from_custom_kubeconfig(kubeconfig.clone(), &options)
-                            .await
-                            .map_err(|_| ConnectionError {})?;
-                        let client = Client::try_from(config)?;
-                        Ok::<Info, Box<dyn Error + Sync + Send>>(client.apiserver_version().await?)
-                    }
-                    .await
+                    let kubeconfig = match tunnels
+                        .effective_kubeconfig(&kubeconfig, &metadata, &name)
+                        .await
                     {
-                        Ok(version) => KtxEvent::SetConnectivityStatus((
-                            name,
-                            KubeContextStatus::Healthy(format!(
-                                "{}.{}",
-                                version.major, version.minor
-                            )),
+                        Ok(kubeconfig) => kubeconfig,
+                        Err(e) => {
+                            if !quiet {
+                                let _ = event_bus
+                                    .send(KtxEvent::PushMessage(UiMessage::about(
+                                        MessageLevel::Info,
+                                        format!("{}: {}", name, e),
+                                        &name,
+                                    )))
+                                    .await;
+                            }
+                            let _ = event_bus
+                                .send(KtxEvent::RecordHealthFailure((name.clone(), e.to_string())))
+                                .await;
+                            let _ = event_bus
+                                .send(KtxEvent::SetConnectivityStatus((
+                                    name,
+                                    KubeContextStatus::Unhealthy,
+                                )))
+                                .await;
+                            return None;
+                        }
+                    };
+                    let started = std::time::Instant::now();
+                    let result = cluster::health_check(&kubeconfig, &name, family, &probe).await;
+                    let latency = started.elapsed();
+                    let healthy = result.is_ok();
+                    let status = match result {
+                        Ok(version) => KtxEvent::RecordHealthCheck((
+                            name.clone(),
+                            KubeContextStatus::Healthy(version),
+                            latency,
                         )),
                         Err(e) => {
+                            if !quiet {
+                                let _ = event_bus
+                                    .send(KtxEvent::PushMessage(UiMessage::about(
+                                        MessageLevel::Info,
+                                        format!("{}: {}", name, e),
+                                        &name,
+                                    )))
+                                    .await;
+                            }
                             let _ = event_bus
-                                .send(KtxEvent::PushInfoMessage(e.to_string()))
+                                .send(KtxEvent::RecordHealthFailure((name.clone(), e.to_string())))
                                 .await;
-                            KtxEvent::SetConnectivityStatus((name, KubeContextStatus::Unhealthy))
+                            KtxEvent::RecordHealthCheck((
+                                name.clone(),
+                                KubeContextStatus::Unhealthy,
+                                latency,
+                            ))
                         }
                     };
                     let _ = event_bus.send(status).await;
-                });
-                handles.push(handle);
-                // Let the eventloop chill for a bit to avoid freezing the UI
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-            futures::stream::iter(handles)
-                .buffer_unordered(10)
-                .collect::<Vec<_>>()
+                    if !healthy {
+                        return None;
+                    }
+                    match cluster::server_cert_expiry(&kubeconfig, &name).await {
+                        Ok(expiry) => {
+                            let _ = event_bus
+                                .send(KtxEvent::SetCertExpiry((name.clone(), expiry)))
+                                .await;
+                            cluster::cert_expires_soon(&expiry).then_some(name)
+                        }
+                        Err(e) => {
+                            log::debug!("Unable to check the certificate of {}: {}", name, e);
+                            None
+                        }
+                    }
+                }
+            })
+            .collect();
+        self.tasks
+            .submit("Testing connections", async move {
+                let expiring: Vec<String> = futures::future::join_all(checks)
+                    .await
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                if !quiet && !expiring.is_empty() {
+                    let _ = event_bus
+                        .send(KtxEvent::PushInfoMessage(format!(
+                            "Server certificates expire within {} days: {}",
+                            cluster::CERT_EXPIRY_WARNING_DAYS,
+                            expiring.join(", ")
+                        )))
+                        .await;
+                }
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_cluster_info(&self, state: &AppState, name: String) -> EmptyResult {
+        let kubeconfig = state.kubeconfig.clone();
+        let metadata = state.metadata.clone();
+        let tunnels = self.tunnels.clone();
+        let family = state.config.ip_family_for(&name);
+        let event_bus = self.event_bus_tx.clone();
+        let _ = event_bus
+            .send(KtxEvent::PushInfoMessage(format!(
+                "Fetching cluster info for {}",
+                name
+            )))
+            .await;
+        self.tasks
+            .submit(format!("Fetching cluster info for {}", name), async move {
+                let info = async {
+                    let client =
+                        context_client(&tunnels, &kubeconfig, &metadata, &name, family).await?;
+                    cluster::fetch_cluster_info(client).await
+                }
                 .await;
-        });
+                let event = match info {
+                    Ok(info) => KtxEvent::SetClusterInfo((name, info)),
+                    Err(e) => KtxEvent::PushMessage(UiMessage::retry(
+                        MessageLevel::Error,
+                        format!("Unable to fetch cluster info for {}: {}", name, e),
+                        &name,
+                        KtxEvent::FetchClusterInfo(name.clone()),
+                    )),
+                };
+                let _ = event_bus.send(event).await;
+            })
+            .await?;
+        Ok(())
+    }
+
+    // Re-runs the import of every context with a recorded provenance, side by side like import
+    // all, and merges the results over the existing entries under the contexts' current names
+    async fn refresh_imported_credentials(
+        &self,
+        state: &mut AppState,
+        contexts: Vec<(String, Provenance, CloudImportPath)>,
+    ) -> EmptyResult {
+        state.record_context_before_import();
+        let event_bus = self.event_bus_tx.clone();
+        let runner = self.runner.clone();
+        let config = state.config.clone();
+        self.tasks
+            .submit("Refreshing imported credentials", async move {
+                let results = futures::stream::iter(contexts)
+                    .map(|(name, provenance, import_path)| {
+                        let event_bus = event_bus.clone();
+                        let runner = runner.clone();
+                        // Contexts imported through a preset assume its role again
+                        let config = config.for_preset(provenance.preset.as_deref());
+                        async move {
+                            let option = (name.clone(), name.clone(), None);
+                            let mut incoming =
+                                match cloud::import_cluster(runner.as_ref(), &import_path, &config)
+                                    .await
+                                {
+                                    Ok(incoming) => incoming,
+                                    Err(e) => {
+                                        return (option, ImportOutcome::Failed(e.to_string()))
+                                    }
+                                };
+                            // Keep renamed contexts under their name and leave the current one alone
+                            if let [context] = incoming.contexts.as_slice() {
+                                let imported_name = context.name.clone();
+                                let _ = kubeconfig::rename_context(
+                                    &mut incoming,
+                                    &imported_name,
+                                    &name,
+                                );
+                            }
+                            incoming.current_context = None;
+                            let _ = event_bus
+                                .send(KtxEvent::ResolveImportConflict((
+                                    incoming,
+                                    Some(provenance),
+                                    ConflictResolution::Overwrite,
+                                )))
+                                .await;
+                            (option, ImportOutcome::Imported)
+                        }
+                    })
+                    .buffer_unordered(REFRESH_CONCURRENCY)
+                    .collect::<Vec<_>>()
+                    .await;
+                let _ = event_bus.send(KtxEvent::RefreshConfig).await;
+                let _ = event_bus
+                    .send(KtxEvent::ShowImportSummary((
+                        CloudImportPath::from(vec![]),
+                        results,
+                    )))
+                    .await;
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn generate_service_account_token(
+        &self,
+        state: &AppState,
+        name: String,
+        namespace: String,
+        service_account: String,
+    ) -> EmptyResult {
+        let kubeconfig = state.kubeconfig.clone();
+        let metadata = state.metadata.clone();
+        let event_bus = self.event_bus_tx.clone();
+        let tunnels = self.tunnels.clone();
+        let family = state.config.ip_family_for(&name);
+        self.tasks
+            .submit(
+                format!("Creating a token for {}/{}", namespace, service_account),
+                async move {
+                    let token = async {
+                        let client =
+                            context_client(&tunnels, &kubeconfig, &metadata, &name, family).await?;
+                        cluster::service_account_token(client, &namespace, &service_account).await
+                    }
+                    .await;
+                    let event = match token {
+                        Ok(token) => KtxEvent::AddServiceAccountContext((
+                            name,
+                            namespace,
+                            service_account,
+                            token,
+                        )),
+                        Err(e) => KtxEvent::PushErrorMessage(format!(
+                            "Unable to create a token for {}/{}: {}",
+                            namespace, service_account, e
+                        )),
+                    };
+                    let _ = event_bus.send(event).await;
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn check_before_switch(&self, state: &AppState, name: String) -> EmptyResult {
+        let kubeconfig = state.kubeconfig.clone();
+        let metadata = state.metadata.clone();
+        let event_bus = self.event_bus_tx.clone();
+        let tunnels = self.tunnels.clone();
+        let reachable = state.switch_event(name.clone());
+        let family = state.config.ip_family_for(&name);
+        let probe = state.config.health_probe_for(&name);
+        let _ = event_bus
+            .send(KtxEvent::PushInfoMessage(format!("Checking {}...", name)))
+            .await;
+        self.tasks
+            .submit(format!("Checking {}", name), async move {
+                let result = match tunnels
+                    .effective_kubeconfig(&kubeconfig, &metadata, &name)
+                    .await
+                {
+                    Ok(kubeconfig) => tokio::time::timeout(
+                        SWITCH_CHECK_TIMEOUT,
+                        cluster::health_check(&kubeconfig, &name, family, &probe),
+                    )
+                    .await
+                    .unwrap_or_else(|_| Err("Timed out".into())),
+                    Err(e) => Err(e),
+                };
+                let (status, next) = match result {
+                    Ok(version) => (KubeContextStatus::Healthy(version), reachable),
+                    Err(e) => {
+                        let _ = event_bus
+                            .send(KtxEvent::RecordHealthFailure((name.clone(), e.to_string())))
+                            .await;
+                        (
+                            KubeContextStatus::Unhealthy,
+                            KtxEvent::ConfirmUnreachableSwitch((name.clone(), e.to_string())),
+                        )
+                    }
+                };
+                let _ = event_bus
+                    .send(KtxEvent::SetConnectivityStatus((name, status)))
+                    .await;
+                let _ = event_bus.send(next).await;
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn run_benchmark(&self, state: &AppState, names: Vec<String>) -> EmptyResult {
+        let kubeconfig = state.kubeconfig.clone();
+        let metadata = state.metadata.clone();
+        let tunnels = self.tunnels.clone();
+        let config = state.config.clone();
+        let event_bus = self.event_bus_tx.clone();
+        self.tasks
+            .submit("Benchmarking API servers", async move {
+                let (kubeconfig, metadata, tunnels) = (&kubeconfig, &metadata, &tunnels);
+                let config = &config;
+                futures::stream::iter(names)
+                    .for_each_concurrent(cluster::BENCHMARK_CONCURRENCY, |name| {
+                        let event_bus = event_bus.clone();
+                        async move {
+                            let family = config.ip_family_for(&name);
+                            let result = match tunnels
+                                .effective_kubeconfig(kubeconfig, metadata, &name)
+                                .await
+                            {
+                                Ok(kubeconfig) => {
+                                    cluster::benchmark(&kubeconfig, &name, family, BENCHMARK_ROUNDS)
+                                        .await
+                                }
+                                Err(e) => Err(e.to_string()),
+                            };
+                            let _ = event_bus.send(KtxEvent::SetLatency((name, result))).await;
+                        }
+                    })
+                    .await;
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn detect_cloud_providers(&self) -> EmptyResult {
+        let runner = self.runner.clone();
+        let event_bus = self.event_bus_tx.clone();
+        self.tasks
+            .submit("Detecting cloud providers", async move {
+                let event = match cloud::list_clouds(runner.as_ref()).await {
+                    Ok(providers) => KtxEvent::SetCloudProviders(providers),
+                    Err(e) => KtxEvent::PushErrorMessage(e.to_string()),
+                };
+                let _ = event_bus.send(event).await;
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_namespaces(&self, state: &AppState, name: String) -> EmptyResult {
+        let kubeconfig = state.kubeconfig.clone();
+        let metadata = state.metadata.clone();
+        let tunnels = self.tunnels.clone();
+        let family = state.config.ip_family_for(&name);
+        let event_bus = self.event_bus_tx.clone();
+        self.tasks
+            .submit(format!("Listing namespaces of {}", name), async move {
+                let namespaces = async {
+                    let client =
+                        context_client(&tunnels, &kubeconfig, &metadata, &name, family).await?;
+                    cluster::list_namespaces(client).await
+                }
+                .await;
+                let event = match namespaces {
+                    Ok(namespaces) => KtxEvent::SetNamespaces((name, namespaces)),
+                    Err(e) => KtxEvent::PushErrorMessage(e.to_string()),
+                };
+                let _ = event_bus.send(event).await;
+            })
+            .await?;
         Ok(())
     }
 
@@ -224,40 +937,182 @@ where
     }
 
     async fn handle_terminal_event(&self, event: Event, state: &mut AppState) -> EmptyResult {
+        // Raw mode delivers Ctrl-C as a key press instead of SIGINT
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) = event
+        {
+            self.interrupt().await;
+            return Ok(());
+        }
+        #[cfg(unix)]
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('z'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) = event
+        {
+            return self.suspend().await;
+        }
         // "Inversed" event handling order because filter is technically in focus and should
         // handle events before any other view
         if state.is_filter_on {
             let view_stack = self.view_stack.lock().await;
             let current_view = view_stack.last().unwrap();
-            if let Event::Key(key_event) = event {
-                self.handle_filter_on_navigation(key_event.code, &current_view)
-                    .await?;
+            match event {
+                Event::Key(key_event) => {
+                    self.handle_filter_on_navigation(key_event.code, current_view)
+                        .await?;
+                }
+                // Pasted text goes into the filter verbatim instead of being replayed as keys
+                Event::Paste(text) => {
+                    let mut current_filter = current_view.get_filter().await;
+                    current_filter.push_str(text.trim());
+                    current_view.update_filter(current_filter).await;
+                }
+                _ => {}
+            }
+        } else if let Some(jump) = state.jump.clone() {
+            self.handle_jump_event(event, jump, state).await?;
+        } else if let Some(KtxEvent::TerminalEvent(Event::Key(KeyEvent {
+            code: KeyCode::Char('x'),
+            ..
+        }))) = self
+            .propagate_event(KtxEvent::TerminalEvent(event), state)
+            .await?
+        {
+            // Unless the view uses x, it acts on the newest toast that has an action
+            let now = chrono::Utc::now();
+            if let Some(index) = state
+                .messages
+                .iter()
+                .rposition(|m| m.expires_at > now && m.action.is_some())
+            {
+                let message = state.messages.remove(index);
+                if let Some(event) = message.action_event() {
+                    let _ = self.event_bus_tx.send(event).await;
+                }
             }
-        } else {
-            self.propagate_event(KtxEvent::TerminalEvent(event), state)
-                .await?;
         };
         Ok(())
     }
 
+    // Keys that don't edit the jump text leave jump mode, and motions still apply
+    async fn handle_jump_event(
+        &self,
+        event: Event,
+        mut jump: String,
+        state: &mut AppState,
+    ) -> EmptyResult {
+        match event {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                ..
+            }) => jump.push(c),
+            Event::Key(KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            }) => {
+                jump.pop();
+            }
+            Event::Paste(text) => jump.push_str(text.trim()),
+            Event::Key(KeyEvent {
+                code: KeyCode::Enter | KeyCode::Esc,
+                ..
+            }) => {
+                state.jump = None;
+                return Ok(());
+            }
+            Event::Key(_) => {
+                state.jump = None;
+                self.propagate_event(KtxEvent::TerminalEvent(event), state)
+                    .await?;
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+        if !jump.is_empty() {
+            let view_stack = self.view_stack.lock().await;
+            view_stack.last().unwrap().jump_to(&jump, state).await;
+        }
+        state.jump = Some(jump);
+        Ok(())
+    }
+
     async fn handle_app_event(&self, event: KtxEvent, state: &mut AppState) -> EmptyResult {
         if let Some(event) = self.propagate_event(event, state).await? {
             match event {
                 KtxEvent::ExitFilterMode => {
                     state.is_filter_on = false;
                 }
+                KtxEvent::EnterJumpMode => {
+                    state.jump = Some(String::new());
+                }
                 KtxEvent::EnterFilterMode => {
                     state.is_filter_on = true;
                 }
+                KtxEvent::TestConnections if state.config.offline => {
+                    self.offline_notice("Connectivity tests are").await;
+                }
                 KtxEvent::TestConnections => {
-                    self.test_connections(state).await?;
+                    self.test_connections(state, false).await?;
+                }
+                KtxEvent::PollConnections if state.config.offline => {}
+                KtxEvent::PollConnections => {
+                    self.test_connections(state, true).await?;
                 }
                 KtxEvent::SetConnectivityStatus((name, status)) => {
-                    state.connectivity_status.insert(name, status);
+                    state.record_health(name, status, None);
+                }
+                KtxEvent::RecordHealthCheck((name, status, latency)) => {
+                    state.record_health(name, status, Some(latency));
+                }
+                KtxEvent::RecordHealthFailure((name, error)) => {
+                    state.health_failures.insert(name, error);
+                }
+                KtxEvent::SetCertExpiry((name, expiry)) => {
+                    state.cert_expiry.insert(name, expiry);
+                }
+                KtxEvent::FetchClusterInfo(_) if state.config.offline => {
+                    self.offline_notice("Fetching cluster info is").await;
+                }
+                KtxEvent::FetchClusterInfo(name) => {
+                    self.fetch_cluster_info(state, name).await?;
+                }
+                KtxEvent::SetClusterInfo((name, info)) => {
+                    state.cluster_info.insert(name, info);
+                }
+                KtxEvent::ShowNamespaces(_) if state.config.offline => {
+                    self.offline_notice("Browsing namespaces is").await;
+                }
+                KtxEvent::ShowNamespaces(name) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(NamespaceListView::new(
+                        self.event_bus_tx.clone(),
+                        name.clone(),
+                    )));
+                    self.fetch_namespaces(state, name).await?;
+                }
+                KtxEvent::SetNamespaces(_) => {
+                    // The namespace popup was closed before the fetch finished
+                }
+                KtxEvent::DeleteContext(name)
+                | KtxEvent::RenameContext(name)
+                | KtxEvent::MoveContext(name)
+                | KtxEvent::EditServerUrl(name)
+                | KtxEvent::ToggleInsecureSkipTlsVerify(name)
+                | KtxEvent::EditCertificateAuthority(name)
+                | KtxEvent::PickEntry((name, _))
+                    if state.kubeconfig_sources.is_read_only(&name) =>
+                {
+                    state.kubeconfig_sources.check_writable(&name)?;
                 }
                 KtxEvent::DeleteContext(name) => {
                     let mut view_stack = self.view_stack.lock().await;
-                    view_stack.push(Box::new(ConfirmationDialogView::new::<B>(
+                    view_stack.push(Arc::new(ConfirmationDialogView::new(
                         self.event_bus_tx.clone(),
                         format!(
                             "Are you sure you want to delete\n\n{}\n\nfrom your kubeconfig file?",
@@ -267,31 +1122,220 @@ where
                     )));
                 }
                 KtxEvent::RefreshConfig => {
-                    let _config_guard = state.config_lock.lock().await;
-                    state.kubeconfig = Kubeconfig::read_from(&state.kubeconfig_path)?;
+                    self.schedule_reload();
+                }
+                KtxEvent::ReloadConfig if state.kubeconfig_sources.unchanged_on_disk() => {
+                    self.refresh_pending.store(false, Ordering::SeqCst);
+                }
+                KtxEvent::ReloadConfig => {
+                    self.refresh_pending.store(false, Ordering::SeqCst);
+                    let (reloaded, sources) = kubeconfig::load(
+                        &state.kubeconfig_path,
+                        state.config.shared_kubeconfig.as_deref(),
+                    )?;
+                    // Views hear about each change right here, queueing them on the bus only
+                    // this loop drains could fill it up and block the loop on itself
+                    for change in state.apply_reload(reloaded, sources) {
+                        let event = match change {
+                            ContextChange::Added(name) => KtxEvent::ContextAdded(name),
+                            ContextChange::Removed(name) => KtxEvent::ContextRemoved(name),
+                            ContextChange::Changed(name) => KtxEvent::ContextChanged(name),
+                        };
+                        match self.propagate_event(event, state).await? {
+                            Some(KtxEvent::ContextAdded(name)) => {
+                                log::debug!("{} was added to the kubeconfig", name);
+                            }
+                            Some(KtxEvent::ContextRemoved(name)) => {
+                                log::debug!("{} was removed from the kubeconfig", name);
+                            }
+                            Some(KtxEvent::ContextChanged(name)) => {
+                                log::debug!("{} changed in the kubeconfig", name);
+                            }
+                            _ => {}
+                        }
+                    }
+                    self.restore_current_context(state).await?;
+                }
+                KtxEvent::ImportStarted => {
+                    state.record_context_before_import();
                 }
                 KtxEvent::PushErrorMessage(error) => {
-                    state.last_message = Some(UiMessage::Error(error));
-                    state.last_message_timestamp = Some(chrono::Utc::now());
+                    log::error!("{}", error);
+                    state.push_message(UiMessage::new(MessageLevel::Error, error));
+                }
+                KtxEvent::PushInfoMessage(message) => {
+                    state.push_message(UiMessage::new(MessageLevel::Info, message));
                 }
-                KtxEvent::PushInfoMessage(error) => {
-                    state.last_message = Some(UiMessage::Info(error));
-                    state.last_message_timestamp = Some(chrono::Utc::now());
+                KtxEvent::PushSuccessMessage(message) => {
+                    state.push_message(UiMessage::new(MessageLevel::Success, message));
+                }
+                KtxEvent::PushMessage(message) => {
+                    if message.level == MessageLevel::Error {
+                        log::error!("{}", message.text);
+                    }
+                    state.push_message(message);
                 }
-                KtxEvent::PushSuccessMessage(error) => {
-                    state.last_message = Some(UiMessage::Success(error));
-                    state.last_message_timestamp = Some(chrono::Utc::now());
+                KtxEvent::ShowImportView(_) | KtxEvent::ShowImportPreset(_)
+                    if state.config.offline =>
+                {
+                    self.offline_notice("Importing is").await;
                 }
                 KtxEvent::ShowImportView(path) => {
                     let mut view_stack = self.view_stack.lock().await;
-                    let import_view = ImportView::new::<B>(self.event_bus_tx.clone(), path);
-                    import_view.load_options().await?;
-                    view_stack.push(Box::new(import_view));
+                    let import_view = ImportView::new(
+                        self.event_bus_tx.clone(),
+                        self.runner.clone(),
+                        self.tasks.clone(),
+                        path,
+                        None,
+                    );
+                    import_view.load_options(state).await?;
+                    self.restore_view(&import_view, state).await;
+                    view_stack.push(Arc::new(import_view));
+                }
+                KtxEvent::ShowImportPreset(name) => {
+                    let path = state
+                        .config
+                        .import_preset(&name)
+                        .and_then(CloudImportPath::from_preset)
+                        .ok_or_else(|| {
+                            format!(
+                                "Import preset {} needs a provider (aws, gcp or azure), an \
+                                 account and for AWS a region",
+                                name
+                            )
+                        })?;
+                    let mut view_stack = self.view_stack.lock().await;
+                    let import_view = ImportView::new(
+                        self.event_bus_tx.clone(),
+                        self.runner.clone(),
+                        self.tasks.clone(),
+                        path,
+                        Some(name),
+                    );
+                    import_view.load_options(state).await?;
+                    self.restore_view(&import_view, state).await;
+                    view_stack.push(Arc::new(import_view));
+                }
+                KtxEvent::DetectCloudProviders => {
+                    self.detect_cloud_providers().await?;
+                }
+                KtxEvent::SetCloudProviders(providers) => {
+                    state.cloud_providers = Some(providers);
+                }
+                KtxEvent::TaskQueued((id, label)) => {
+                    state.queued_tasks.insert(id, label);
+                }
+                KtxEvent::TaskStarted(id) => {
+                    if let Some(label) = state.queued_tasks.remove(&id) {
+                        state.running_tasks.insert(id, label);
+                    }
+                }
+                KtxEvent::TaskFinished((id, outcome)) => {
+                    let label = state.queued_tasks.remove(&id);
+                    let label = state.running_tasks.remove(&id).or(label);
+                    if let (Some(label), TaskOutcome::Cancelled) = (label, outcome) {
+                        log::info!("Cancelled {}", label);
+                    }
+                    if !state.has_background_tasks() {
+                        state.context_before_import = None;
+                    }
+                }
+                KtxEvent::CancelTasks => {
+                    let count = self.tasks.cancel_all();
+                    if count == 0 {
+                        return Err("No background tasks to cancel".into());
+                    }
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushInfoMessage(format!(
+                            "Cancelled {} background task(s)",
+                            count
+                        )))
+                        .await;
+                }
+                // Also arrives when the import view was closed before its options were listed
+                KtxEvent::ImportOptionsLoaded((import_path, preset, Ok(options))) => {
+                    state
+                        .listing_cache
+                        .insert((preset, import_path.key()), (chrono::Utc::now(), options));
+                }
+                KtxEvent::ImportOptionsLoaded(_) => {}
+                KtxEvent::RefreshImportedCredentials if state.config.offline => {
+                    self.offline_notice("Importing is").await;
+                }
+                KtxEvent::RefreshImportedCredentials => {
+                    let count = state.refreshable_contexts().len();
+                    if count == 0 {
+                        return Err("No context has a recorded cloud import to refresh".into());
+                    }
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ConfirmationDialogView::new(
+                        self.event_bus_tx.clone(),
+                        format!(
+                            "Run the imports of {} context(s) again to refresh their credentials?\n\nTheir clusters and users are overwritten with what the cloud CLIs return.",
+                            count
+                        ),
+                        KtxEvent::RefreshImportedCredentialsConfirm,
+                    )));
+                }
+                KtxEvent::RefreshImportedCredentialsConfirm => {
+                    let contexts = state.refreshable_contexts();
+                    self.refresh_imported_credentials(state, contexts).await?;
+                }
+                KtxEvent::MergeImportedConfig((incoming, provenance, batch)) => {
+                    let conflicts = kubeconfig::find_conflicts(&state.kubeconfig, &incoming);
+                    let mut previewed = incoming.clone();
+                    if state.retargeted_context(&incoming).is_some()
+                        && state.config.import_current_context != ImportCurrentContext::Switch
+                    {
+                        previewed.current_context = None;
+                    }
+                    let preview = kubeconfig::merge_preview(&state.kubeconfig, &previewed);
+                    // Batches would stack a dialog per cluster, their summary lists what changed
+                    let wants_preview = state.config.preview_merges && !batch;
+                    if conflicts.is_empty() && wants_preview && !preview.is_empty() {
+                        let mut view_stack = self.view_stack.lock().await;
+                        view_stack.push(Arc::new(ConfirmationDialogView::new(
+                            self.event_bus_tx.clone(),
+                            format!(
+                                "Merge the following into the kubeconfig?\n\n{}",
+                                format_merge_preview(&preview)
+                            ),
+                            KtxEvent::ResolveImportConflict((
+                                incoming,
+                                provenance,
+                                ConflictResolution::Overwrite,
+                            )),
+                        )));
+                    } else if conflicts.is_empty() {
+                        self.merge_import(
+                            state,
+                            incoming,
+                            provenance,
+                            ConflictResolution::Overwrite,
+                        )
+                        .await?;
+                    } else {
+                        let mut view_stack = self.view_stack.lock().await;
+                        view_stack.push(Arc::new(ImportConflictDialogView::new(
+                            self.event_bus_tx.clone(),
+                            format_merge_preview(&preview),
+                            incoming,
+                            provenance,
+                        )));
+                    }
+                }
+                KtxEvent::ResolveImportConflict((incoming, provenance, resolution)) => {
+                    self.merge_import(state, incoming, provenance, resolution)
+                        .await?;
                 }
                 KtxEvent::PopView | KtxEvent::DialogReject | KtxEvent::DialogConfirm => {
                     let mut view_stack = self.view_stack.lock().await;
                     if view_stack.len() > 1 {
-                        view_stack.pop();
+                        if let Some(view) = view_stack.pop() {
+                            self.remember_view(view.as_ref(), state).await;
+                        }
                     } else {
                         let _ = self.event_bus_tx.send(KtxEvent::Exit).await;
                     }
@@ -299,55 +1343,924 @@ where
                 KtxEvent::DeleteContextConfirm(name) => {
                     state.kubeconfig.contexts.retain(|c| c.name != name);
                     self.write_kubeconfig(state).await?;
+                    self.audit(state, "delete", &name, None).await;
+                    if state.metadata.contexts.remove(&name).is_some() {
+                        self.write_metadata(state).await?;
+                    }
+                }
+                KtxEvent::DeleteExpiredContexts => {
+                    let expired: Vec<String> = state
+                        .kubeconfig
+                        .contexts
+                        .iter()
+                        .map(|c| c.name.clone())
+                        .filter(|name| state.metadata.is_expired(name))
+                        .collect();
+                    self.confirm_bulk_delete(state, expired, "expired").await;
+                }
+                KtxEvent::DeleteEphemeralContexts => {
+                    let ephemeral = state.ephemeral_contexts();
+                    let names: Vec<String> = state
+                        .kubeconfig
+                        .contexts
+                        .iter()
+                        .map(|c| c.name.clone())
+                        .filter(|name| ephemeral.contains(name))
+                        .collect();
+                    self.confirm_bulk_delete(state, names, "ephemeral").await;
                 }
-                KtxEvent::SetContext(name) => {
-                    state.kubeconfig.current_context = Some(name);
+                KtxEvent::DeleteContextsConfirm((names, reason)) => {
+                    state
+                        .kubeconfig
+                        .contexts
+                        .retain(|c| !names.contains(&c.name));
                     self.write_kubeconfig(state).await?;
+                    for name in &names {
+                        self.audit(state, "delete", name, Some(reason.clone()))
+                            .await;
+                        state.metadata.contexts.remove(name);
+                    }
+                    self.write_metadata(state).await?;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushSuccessMessage(format!(
+                            "Deleted {} {} context(s)",
+                            names.len(),
+                            reason
+                        )))
+                        .await;
                 }
-                _ => {}
-            };
-        };
-        Ok(())
-    }
-
-    pub async fn start_renderer(&self, mut rx: mpsc::Receiver<RendererMessage>) -> () {
-        enable_raw_mode().expect("Failed to enable raw mode");
-        self.terminal
-            .lock()
-            .await
+                KtxEvent::OpenConsole(name) => {
+                    let url = state
+                        .metadata
+                        .provenance(&name)
+                        .or_else(|| Provenance::from_context_name(&name))
+                        .and_then(|p| p.console_url())
+                        .ok_or(format!("No cloud console known for {}", name))?;
+                    open_in_browser(&url)?;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushInfoMessage(format!("Opened {}", url)))
+                        .await;
+                }
+                KtxEvent::ViewContext(name) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ContextDetailView::new(
+                        self.event_bus_tx.clone(),
+                        name,
+                    )));
+                }
+                KtxEvent::FlattenKubeconfig => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ConfirmationDialogView::new(
+                        self.event_bus_tx.clone(),
+                        format!(
+                            "Inline all certificate and key files referenced by\n\n{}\n\nas base64 data?",
+                            state.kubeconfig_path
+                        ),
+                        KtxEvent::FlattenKubeconfigConfirm,
+                    )));
+                }
+                KtxEvent::FlattenKubeconfigConfirm => {
+                    let (flattened, inlined) = kubeconfig::flatten(&state.kubeconfig)?;
+                    if inlined > 0 {
+                        state.kubeconfig = flattened.into();
+                        self.write_kubeconfig(state).await?;
+                    }
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushSuccessMessage(format!(
+                            "Inlined {} file(s)",
+                            inlined
+                        )))
+                        .await;
+                }
+                KtxEvent::NormalizeKubeconfig => {
+                    let (_, preview) = state.normalized_kubeconfig();
+                    if preview.is_empty() {
+                        return Err("No user relies on a deprecated auth-provider".into());
+                    }
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ConfirmationDialogView::new(
+                        self.event_bus_tx.clone(),
+                        format!(
+                            "kubectl 1.26+ rejects the gcp and azure auth-providers. Rewrite them to exec plugins?\n\n{}",
+                            format_merge_preview(&preview)
+                        ),
+                        KtxEvent::NormalizeKubeconfigConfirm,
+                    )));
+                }
+                KtxEvent::NormalizeKubeconfigConfirm => {
+                    let (normalized, upgraded) = state.normalized_kubeconfig();
+                    if !upgraded.is_empty() {
+                        state.kubeconfig = normalized.into();
+                        self.write_kubeconfig(state).await?;
+                    }
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushSuccessMessage(format!(
+                            "Upgraded {} user(s)",
+                            upgraded.len()
+                        )))
+                        .await;
+                }
+                KtxEvent::MinifyContext(name) => {
+                    let file_name: String = name
+                        .chars()
+                        .map(|c| {
+                            if c.is_alphanumeric() || c == '-' || c == '.' {
+                                c
+                            } else {
+                                '_'
+                            }
+                        })
+                        .collect();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Minify".to_string(),
+                        format!("Write a self-contained kubeconfig for\n{}\nto", name),
+                        format!("~/{}.yaml", file_name),
+                        Box::new(move |path| KtxEvent::WriteMinifiedContext((name.clone(), path))),
+                    )));
+                }
+                KtxEvent::WriteMinifiedContext((name, path)) => {
+                    let path = shellexpand::tilde(path.trim()).into_owned();
+                    if path.is_empty() {
+                        return Err("No output file given".into());
+                    }
+                    let minified = kubeconfig::minify(&state.kubeconfig, &[name.as_str()])?;
+                    kubeconfig::save_private(&minified, Path::new(&path)).await?;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushSuccessMessage(format!("Wrote {}", path)))
+                        .await;
+                }
+                KtxEvent::ShowImportSummary((import_path, results)) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ImportSummaryView::new(
+                        self.event_bus_tx.clone(),
+                        import_path,
+                        results,
+                    )));
+                }
+                KtxEvent::RetryImports(_) if state.config.offline => {
+                    self.offline_notice("Importing is").await;
+                }
+                // Refresh summaries list contexts by name
+                KtxEvent::RetryImports((import_path, options)) if import_path.is_empty() => {
+                    let names: Vec<String> = options.into_iter().map(|option| option.0).collect();
+                    let contexts: Vec<_> = state
+                        .refreshable_contexts()
+                        .into_iter()
+                        .filter(|(name, _, _)| names.contains(name))
+                        .collect();
+                    self.refresh_imported_credentials(state, contexts).await?;
+                }
+                // The import view it came from was closed in the meantime
+                KtxEvent::RetryImports((import_path, _)) => {
+                    return Err(format!(
+                        "Open the import wizard for {} to retry",
+                        import_path.display_names().join(" / ")
+                    )
+                    .into());
+                }
+                KtxEvent::ShowBenchmark if state.config.offline => {
+                    self.offline_notice("Benchmarks are").await;
+                }
+                KtxEvent::ShowBenchmark => {
+                    let names: Vec<String> = state
+                        .kubeconfig
+                        .contexts
+                        .iter()
+                        .map(|c| c.name.clone())
+                        .collect();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(BenchmarkView::new(
+                        self.event_bus_tx.clone(),
+                        names.clone(),
+                    )));
+                    self.run_benchmark(state, names).await?;
+                }
+                KtxEvent::ShowHealthDashboard if state.config.offline => {
+                    self.offline_notice("Health checks are").await;
+                }
+                KtxEvent::ShowHealthDashboard => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(HealthDashboardView::new(
+                        self.event_bus_tx.clone(),
+                        state.kubeconfig.contexts.len(),
+                        dashboard::poll_interval(&state.config),
+                    )));
+                    self.test_connections(state, true).await?;
+                }
+                KtxEvent::SetLatency(_) => {
+                    // The benchmark view was closed before the measurement finished
+                }
+                KtxEvent::ShowRecentContexts => {
+                    let mut recent = state.recent_contexts();
+                    recent.truncate(RECENT_CONTEXTS_LENGTH);
+                    if recent.is_empty() {
+                        return Err("No other context has been used yet".into());
+                    }
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(RecentContextsView::new(
+                        self.event_bus_tx.clone(),
+                        recent,
+                    )));
+                }
+                KtxEvent::ShowHistory => {
+                    let mut snapshots = vec![];
+                    for snapshot in history::list(&history::dir(&state.metadata_path)).await? {
+                        let current = if Path::new(&snapshot.original).exists() {
+                            Kubeconfig::read_from(&snapshot.original).map_err(|e| e.to_string())
+                        } else {
+                            Ok(Kubeconfig::default())
+                        };
+                        let diff = history::read(&snapshot)
+                            .map_err(|e| e.to_string())
+                            .and_then(|old| Ok(history::diff(&old, &current?)));
+                        snapshots.push((snapshot, diff));
+                    }
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(HistoryView::new(
+                        self.event_bus_tx.clone(),
+                        snapshots,
+                    )));
+                }
+                KtxEvent::RestoreSnapshot(snapshot) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ConfirmationDialogView::new(
+                        self.event_bus_tx.clone(),
+                        format!(
+                            "Restore {}\n\nas it was at {}?",
+                            snapshot.original,
+                            snapshot
+                                .taken_at
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M:%S")
+                        ),
+                        KtxEvent::RestoreSnapshotConfirm(snapshot),
+                    )));
+                }
+                KtxEvent::RestoreSnapshotConfirm(snapshot) => {
+                    history::restore(&history::dir(&state.metadata_path), &snapshot).await?;
+                    let (restored, sources) = kubeconfig::load(
+                        &state.kubeconfig_path,
+                        state.config.shared_kubeconfig.as_deref(),
+                    )?;
+                    (state.kubeconfig, state.kubeconfig_sources) = (restored.into(), sources);
+                    self.audit(
+                        state,
+                        "restore",
+                        &snapshot.original,
+                        Some(format!("snapshot from {}", snapshot.taken_at.to_rfc3339())),
+                    )
+                    .await;
+                    // The history view is out of date now
+                    let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushSuccessMessage(format!(
+                            "Restored {}",
+                            snapshot.original
+                        )))
+                        .await;
+                }
+                KtxEvent::ShowAuditLog => {
+                    let path = state
+                        .config
+                        .audit_log
+                        .clone()
+                        .ok_or("The audit log is disabled, set audit_log in the ktx config")?;
+                    let entries = audit::load(&path).await?;
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(AuditLogView::new(
+                        self.event_bus_tx.clone(),
+                        entries,
+                    )));
+                }
+                KtxEvent::RenameContext(name) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Rename".to_string(),
+                        format!("New name for\n{}", name),
+                        name.clone(),
+                        Box::new(move |new_name| {
+                            KtxEvent::SetContextName((name.clone(), new_name))
+                        }),
+                    )));
+                }
+                KtxEvent::CloneContext(name) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Clone".to_string(),
+                        format!(
+                            "Name for a copy of\n{}\noptionally followed by another namespace and user",
+                            name
+                        ),
+                        format!("{}-copy", name),
+                        Box::new(move |answer| {
+                            KtxEvent::CloneContextAs((name.clone(), answer))
+                        }),
+                    )));
+                }
+                KtxEvent::CloneContextAs((name, answer)) => {
+                    let mut words = answer.split_whitespace();
+                    let new_name = words.next().unwrap_or_default();
+                    let (namespace, user) = (words.next(), words.next());
+                    if words.next().is_some() {
+                        return Err("Enter a name, a namespace and a user at most".into());
+                    }
+                    kubeconfig::clone_context(
+                        &mut state.kubeconfig,
+                        &name,
+                        new_name,
+                        namespace,
+                        user,
+                    )?;
+                    self.write_kubeconfig(state).await?;
+                    self.audit(state, "clone", new_name, Some(format!("from {}", name)))
+                        .await;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushMessage(UiMessage::about(
+                            MessageLevel::Success,
+                            format!("Cloned {} as {}", name, new_name),
+                            new_name,
+                        )))
+                        .await;
+                }
+                KtxEvent::PickEntry((name, kind)) => {
+                    let names = kubeconfig::entry_names(&state.kubeconfig, kind);
+                    if names.is_empty() {
+                        return Err(format!("There is no {} to pick", kind.label()).into());
+                    }
+                    let current = kubeconfig::find_context(&state.kubeconfig, &name)
+                        .and_then(|c| c.context.as_ref())
+                        .map(|c| match kind {
+                            EntryKind::Cluster => c.cluster.clone(),
+                            EntryKind::User => c.user.clone(),
+                        })
+                        .unwrap_or_default();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(EntryPickerView::new(
+                        self.event_bus_tx.clone(),
+                        name,
+                        kind,
+                        names,
+                        current,
+                    )));
+                }
+                KtxEvent::AssignEntry((name, kind, entry)) => {
+                    state.kubeconfig_sources.check_writable(&name)?;
+                    kubeconfig::assign_entry(&mut state.kubeconfig, &name, kind, &entry)?;
+                    self.write_kubeconfig(state).await?;
+                    self.audit(
+                        state,
+                        "assign",
+                        &name,
+                        Some(format!("{} {}", kind.label(), entry)),
+                    )
+                    .await;
+                    // The old status belongs to the old cluster or credentials
+                    state.connectivity_status.remove(&name);
+                    state.cluster_info.remove(&name);
+                }
+                KtxEvent::MoveContext(_) if !state.kubeconfig_sources.is_multi_file() => {
+                    return Err(
+                        "Moving contexts needs several kubeconfig files, list them in KUBECONFIG"
+                            .into(),
+                    );
+                }
+                KtxEvent::MoveContext(name) => {
+                    let paths = &state.kubeconfig_sources.paths;
+                    let current = state.kubeconfig_sources.context_file(&name);
+                    let next = paths
+                        .iter()
+                        .cycle()
+                        .skip_while(|p| *p != current)
+                        .nth(1)
+                        .cloned()
+                        .unwrap_or_default();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Move".to_string(),
+                        format!(
+                            "Move {}\nfrom {} to one of\n{}",
+                            name,
+                            current,
+                            paths.join("\n")
+                        ),
+                        next,
+                        Box::new(move |path| KtxEvent::MoveContextToFile((name.clone(), path))),
+                    )));
+                }
+                KtxEvent::MoveContextToFile((name, path)) => {
+                    let path = path.trim();
+                    let from = state.kubeconfig_sources.context_file(&name).to_string();
+                    if from != path {
+                        state
+                            .kubeconfig_sources
+                            .move_context(&state.kubeconfig, &name, path)?;
+                        self.write_kubeconfig(state).await?;
+                        self.audit(
+                            state,
+                            "move",
+                            &name,
+                            Some(format!("from {} to {}", from, path)),
+                        )
+                        .await;
+                        let _ = self
+                            .event_bus_tx
+                            .send(KtxEvent::PushSuccessMessage(format!(
+                                "Moved {} to {}",
+                                name, path
+                            )))
+                            .await;
+                    }
+                }
+                KtxEvent::SetContextName((name, new_name)) => {
+                    let new_name = new_name.trim();
+                    if new_name != name {
+                        kubeconfig::rename_context(&mut state.kubeconfig, &name, new_name)?;
+                        state.kubeconfig_sources.rename_context(&name, new_name);
+                        self.write_kubeconfig(state).await?;
+                        self.audit(state, "rename", new_name, Some(format!("from {}", name)))
+                            .await;
+                        state.metadata.rename(&name, new_name);
+                        self.write_metadata(state).await?;
+                        if let Some(status) = state.connectivity_status.remove(&name) {
+                            state
+                                .connectivity_status
+                                .insert(new_name.to_string(), status);
+                        }
+                        if let Some(info) = state.cluster_info.remove(&name) {
+                            state.cluster_info.insert(new_name.to_string(), info);
+                        }
+                        if let Some(history) = state.health_history.remove(&name) {
+                            state.health_history.insert(new_name.to_string(), history);
+                        }
+                        if let Some(error) = state.health_failures.remove(&name) {
+                            state.health_failures.insert(new_name.to_string(), error);
+                        }
+                    }
+                }
+                KtxEvent::AddContext(mut draft) => {
+                    // A rejected answer is asked for again
+                    if let Err(e) = draft.check_last_answer(&state.kubeconfig) {
+                        draft.answers.pop();
+                        let _ = self
+                            .event_bus_tx
+                            .send(KtxEvent::PushErrorMessage(e.to_string()))
+                            .await;
+                    }
+                    if draft.next_prompt().is_some() {
+                        // The answered dialog is still open until its DialogConfirm is handled
+                        let _ = self
+                            .event_bus_tx
+                            .send(KtxEvent::PromptContextDraft(draft))
+                            .await;
+                    } else {
+                        let name = kubeconfig::add_context(&mut state.kubeconfig, &draft)?;
+                        self.write_kubeconfig(state).await?;
+                        self.audit(state, "add", &name, None).await;
+                        let _ = self
+                            .event_bus_tx
+                            .send(KtxEvent::PushSuccessMessage(format!("Added {}", name)))
+                            .await;
+                    }
+                }
+                KtxEvent::PromptContextDraft(draft) => {
+                    let prompt = draft.next_prompt().unwrap_or_default();
+                    let initial = match draft.answers.len() {
+                        1 => "https://",
+                        3 => "token",
+                        _ => "",
+                    };
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "New context".to_string(),
+                        prompt.to_string(),
+                        initial.to_string(),
+                        Box::new(move |answer| {
+                            let mut draft = draft.clone();
+                            draft
+                                .answers
+                                .push(shellexpand::tilde(answer.trim()).into_owned());
+                            KtxEvent::AddContext(draft)
+                        }),
+                    )));
+                }
+                KtxEvent::CreateServiceAccountToken(_) if state.config.offline => {
+                    self.offline_notice("Creating tokens is").await;
+                }
+                KtxEvent::CreateServiceAccountToken(name) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Service account".to_string(),
+                        format!(
+                            "Service account as namespace/name to create a token for in\n{}\n(created when missing)",
+                            name
+                        ),
+                        "default/".to_string(),
+                        Box::new(move |service_account| {
+                            KtxEvent::GenerateServiceAccountToken((name.clone(), service_account))
+                        }),
+                    )));
+                }
+                KtxEvent::GenerateServiceAccountToken((name, service_account)) => {
+                    let (namespace, service_account) = service_account
+                        .trim()
+                        .split_once('/')
+                        .filter(|(namespace, sa)| !namespace.is_empty() && !sa.is_empty())
+                        .ok_or("Enter the service account as namespace/name")?;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushInfoMessage(format!(
+                            "Requesting a token for {}/{}...",
+                            namespace, service_account
+                        )))
+                        .await;
+                    self.generate_service_account_token(
+                        state,
+                        name,
+                        namespace.to_string(),
+                        service_account.to_string(),
+                    )
+                    .await?;
+                }
+                KtxEvent::AddServiceAccountContext((name, namespace, service_account, token)) => {
+                    let new_name = kubeconfig::add_service_account_context(
+                        &mut state.kubeconfig,
+                        &name,
+                        &namespace,
+                        &service_account,
+                        token,
+                    )?;
+                    self.write_kubeconfig(state).await?;
+                    self.audit(
+                        state,
+                        "add",
+                        &new_name,
+                        Some(format!("service account {}/{}", namespace, service_account)),
+                    )
+                    .await;
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushSuccessMessage(format!(
+                            "Added {}, export it with ktx export",
+                            new_name
+                        )))
+                        .await;
+                }
+                KtxEvent::ShowEntries => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(EntriesView::new(
+                        self.event_bus_tx.clone(),
+                        EntryKind::Cluster,
+                    )));
+                }
+                KtxEvent::RenameEntry((kind, name)) => {
+                    state.kubeconfig_sources.check_entry_writable(
+                        &state.kubeconfig,
+                        kind,
+                        &name,
+                    )?;
+                    let references = kubeconfig::entry_references(&state.kubeconfig, kind, &name);
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Rename".to_string(),
+                        format!(
+                            "New name for the {}\n{}\n({} contexts are updated)",
+                            kind.label(),
+                            name,
+                            references.len()
+                        ),
+                        name.clone(),
+                        Box::new(move |new_name| {
+                            KtxEvent::SetEntryName((kind, name.clone(), new_name))
+                        }),
+                    )));
+                }
+                KtxEvent::SetEntryName((kind, name, new_name)) => {
+                    let new_name = new_name.trim();
+                    if new_name != name {
+                        kubeconfig::rename_entry(&mut state.kubeconfig, kind, &name, new_name)?;
+                        state.kubeconfig_sources.rename_entry(kind, &name, new_name);
+                        self.write_kubeconfig(state).await?;
+                        self.audit(
+                            state,
+                            "rename",
+                            new_name,
+                            Some(format!("{} from {}", kind.label(), name)),
+                        )
+                        .await;
+                    }
+                }
+                KtxEvent::DeleteEntry((kind, name)) => {
+                    state.kubeconfig_sources.check_entry_writable(
+                        &state.kubeconfig,
+                        kind,
+                        &name,
+                    )?;
+                    let references = kubeconfig::entry_references(&state.kubeconfig, kind, &name);
+                    if !references.is_empty() {
+                        return Err(format!(
+                            "The {} {} is still used by {}, delete or repoint those contexts first",
+                            kind.label(),
+                            name,
+                            references.join(", ")
+                        )
+                        .into());
+                    }
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(ConfirmationDialogView::new(
+                        self.event_bus_tx.clone(),
+                        format!(
+                            "Are you sure you want to delete the {}\n\n{}\n\nfrom your kubeconfig file?",
+                            kind.label(),
+                            name
+                        ),
+                        KtxEvent::DeleteEntryConfirm((kind, name)),
+                    )));
+                }
+                KtxEvent::DeleteEntryConfirm((kind, name)) => {
+                    kubeconfig::delete_entry(&mut state.kubeconfig, kind, &name)?;
+                    self.write_kubeconfig(state).await?;
+                    self.audit(state, "delete", &name, Some(kind.label().to_string()))
+                        .await;
+                }
+                KtxEvent::EditServerUrl(name) => {
+                    let current = kubeconfig::context_cluster(&state.kubeconfig, &name)
+                        .and_then(|c| c.cluster.as_ref())
+                        .and_then(|c| c.server.clone())
+                        .unwrap_or_default();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Server URL".to_string(),
+                        format!("API server URL for the cluster of\n{}", name),
+                        current,
+                        Box::new(move |url| KtxEvent::SetServerUrl((name.clone(), url))),
+                    )));
+                }
+                KtxEvent::SetServerUrl((name, url)) => {
+                    let url = url.trim().to_string();
+                    kubeconfig::check_server_url(&url)?;
+                    if let Some(cluster) =
+                        kubeconfig::context_cluster_mut(&mut state.kubeconfig, &name)
+                    {
+                        cluster.server = Some(url);
+                        self.write_kubeconfig(state).await?;
+                        state.connectivity_status.remove(&name);
+                    }
+                }
+                KtxEvent::ToggleInsecureSkipTlsVerify(name) => {
+                    if let Some(cluster) =
+                        kubeconfig::context_cluster_mut(&mut state.kubeconfig, &name)
+                    {
+                        let insecure = !cluster.insecure_skip_tls_verify.unwrap_or(false);
+                        // kubectl refuses to combine a CA with the insecure flag
+                        if insecure {
+                            cluster.certificate_authority = None;
+                            cluster.certificate_authority_data = None;
+                        }
+                        cluster.insecure_skip_tls_verify = if insecure { Some(true) } else { None };
+                        self.write_kubeconfig(state).await?;
+                    }
+                }
+                KtxEvent::TogglePin(name) => {
+                    let entry = state.metadata.context_mut(&name);
+                    entry.pinned = !entry.pinned;
+                    self.write_metadata(state).await?;
+                }
+                KtxEvent::EditNotes(name) => {
+                    let current = state
+                        .metadata
+                        .contexts
+                        .get(&name)
+                        .and_then(|m| m.notes.clone())
+                        .unwrap_or_default();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Notes".to_string(),
+                        format!("Notes for\n{}", name),
+                        current,
+                        Box::new(move |notes| KtxEvent::SetNotes((name.clone(), notes))),
+                    )));
+                }
+                KtxEvent::SetNotes((name, notes)) => {
+                    let notes = notes.trim();
+                    state.metadata.context_mut(&name).notes = if notes.is_empty() {
+                        None
+                    } else {
+                        Some(notes.to_string())
+                    };
+                    self.write_metadata(state).await?;
+                }
+                KtxEvent::EditTtl(name) => {
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "TTL".to_string(),
+                        format!(
+                            "Time to live from now for\n{}\ne.g. 12h or 7d, empty for none",
+                            name
+                        ),
+                        "".to_string(),
+                        Box::new(move |ttl| KtxEvent::SetTtl((name.clone(), ttl))),
+                    )));
+                }
+                KtxEvent::SetTtl((name, ttl)) => {
+                    let expires_at = if ttl.trim().is_empty() {
+                        None
+                    } else {
+                        Some(chrono::Utc::now() + metadata::parse_ttl(&ttl)?)
+                    };
+                    state.metadata.context_mut(&name).expires_at = expires_at;
+                    self.write_metadata(state).await?;
+                }
+                KtxEvent::EditCertificateAuthority(name) => {
+                    let current = kubeconfig::context_cluster(&state.kubeconfig, &name)
+                        .and_then(|c| c.cluster.as_ref())
+                        .and_then(|c| c.certificate_authority.clone())
+                        .unwrap_or_default();
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(InputDialogView::new(
+                        self.event_bus_tx.clone(),
+                        "Certificate authority".to_string(),
+                        format!("Path to the CA file for the cluster of\n{}", name),
+                        current,
+                        Box::new(move |path| {
+                            KtxEvent::SetCertificateAuthority((name.clone(), path))
+                        }),
+                    )));
+                }
+                KtxEvent::SetCertificateAuthority((name, path)) => {
+                    if let Some(cluster) =
+                        kubeconfig::context_cluster_mut(&mut state.kubeconfig, &name)
+                    {
+                        let path = shellexpand::tilde(path.trim()).into_owned();
+                        if path.is_empty() {
+                            cluster.certificate_authority = None;
+                        } else {
+                            if !Path::new(&path).exists() {
+                                return Err(format!("{} does not exist", path).into());
+                            }
+                            cluster.certificate_authority = Some(path);
+                            cluster.certificate_authority_data = None;
+                            cluster.insecure_skip_tls_verify = None;
+                        }
+                        self.write_kubeconfig(state).await?;
+                    }
+                }
+                KtxEvent::SwitchContext(name)
+                    if state.config.check_before_switch && !state.config.offline =>
+                {
+                    self.check_before_switch(state, name).await?;
+                }
+                KtxEvent::SwitchContext(name)
+                    if matches!(
+                        state.connectivity_status.get(&name),
+                        Some(KubeContextStatus::Unhealthy)
+                    ) =>
+                {
+                    let error = state
+                        .health_failures
+                        .get(&name)
+                        .cloned()
+                        .unwrap_or_else(|| "The connectivity test failed".to_string());
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::ConfirmUnreachableSwitch((name, error)))
+                        .await;
+                }
+                KtxEvent::SwitchContext(name) => {
+                    let _ = self.event_bus_tx.send(state.switch_event(name)).await;
+                }
+                KtxEvent::ConfirmUnreachableSwitch((name, error)) => {
+                    let can_reauth = !state.config.offline
+                        && state
+                            .refreshable_contexts()
+                            .iter()
+                            .any(|(refreshable, _, _)| *refreshable == name);
+                    let mut view_stack = self.view_stack.lock().await;
+                    view_stack.push(Arc::new(UnhealthySwitchDialogView::new(
+                        self.event_bus_tx.clone(),
+                        name.clone(),
+                        error,
+                        // Listing its namespaces wouldn't work either
+                        KtxEvent::SetContext((name.clone(), state.remembered_namespace(&name))),
+                        can_reauth,
+                    )));
+                }
+                KtxEvent::RetestSwitch(_) if state.config.offline => {
+                    self.offline_notice("Connectivity tests are").await;
+                }
+                KtxEvent::RetestSwitch(name) => {
+                    self.check_before_switch(state, name).await?;
+                }
+                KtxEvent::ReauthContext(_) if state.config.offline => {
+                    self.offline_notice("Importing is").await;
+                }
+                KtxEvent::ReauthContext(name) => {
+                    let contexts: Vec<_> = state
+                        .refreshable_contexts()
+                        .into_iter()
+                        .filter(|(refreshable, _, _)| *refreshable == name)
+                        .collect();
+                    if contexts.is_empty() {
+                        return Err(
+                            format!("{} has no recorded cloud import to re-run", name).into()
+                        );
+                    }
+                    self.refresh_imported_credentials(state, contexts).await?;
+                }
+                KtxEvent::SetContext((name, namespace)) => {
+                    log::info!("Switching to {}", name);
+                    if namespace.is_some() {
+                        state.kubeconfig_sources.check_writable(&name)?;
+                    }
+                    kubeconfig::switch_context(&mut state.kubeconfig, &name, namespace.as_deref())?;
+                    self.write_kubeconfig(state).await?;
+                    if state.context_before_import.is_some() {
+                        state.context_before_import = Some(name.clone());
+                    }
+                    self.audit(
+                        state,
+                        "switch",
+                        &name,
+                        namespace.as_ref().map(|ns| format!("namespace {}", ns)),
+                    )
+                    .await;
+                    let metadata = state.metadata.context_mut(&name);
+                    metadata.last_used = Some(chrono::Utc::now());
+                    if namespace.is_some() {
+                        metadata.namespace = namespace;
+                    }
+                    self.write_metadata(state).await?;
+                }
+                _ => {}
+            };
+        };
+        Ok(())
+    }
+
+    pub async fn start_renderer(&self, mut rx: mpsc::Receiver<RendererMessage>) -> () {
+        enable_raw_mode().expect("Failed to enable raw mode");
+        self.terminal
+            .lock()
+            .await
             .clear()
             .expect("Failed to clear terminal");
+        // Redraw whenever the event loop reports a change, and on a slow tick so time based
+        // things like message expiry show up even when nothing happens.
+        let mut ticker = tokio::time::interval(RENDER_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let (state_rx, mut view_rx) = (self.state_tx.subscribe(), self.view_tx.subscribe());
+        let mut drawn: Option<ViewSnapshot<B>> = None;
+        let mut published_offset = None;
         loop {
-            match rx.recv().await {
-                Some(RendererMessage::Render) => {
-                    // Drain all pending render messages.
-                    while let Ok(RendererMessage::Render) = rx.try_recv() {
-                        // just drain the channel, do nothing with the messages
-                    }
-                    let mut state = self.state.lock().await;
-                    let view_stack = self.view_stack.lock().await;
-                    let current_view = view_stack.last().unwrap();
-                    let view_filter = current_view.get_filter().await;
-                    let state_mutex = current_view.get_state_mutex();
-                    let mut view_state = state_mutex.lock().await;
-                    let mut terminal = self.terminal.lock().await;
-                    terminal
-                        .draw(move |f| {
-                            self.draw(
-                                f,
-                                f.size(),
-                                &mut state,
-                                current_view,
-                                &mut view_state,
-                                view_filter,
-                            )
-                        })
-                        .expect("Unable to draw terminal");
-                }
-                Some(RendererMessage::Stop) | None => {
-                    break;
+            tokio::select! {
+                message = rx.recv() => match message {
+                    Some(RendererMessage::Render) => {}
+                    _ => break,
+                },
+                _ = ticker.tick() => {}
+            }
+            let state = state_rx.borrow().clone();
+            if view_rx.has_changed().unwrap_or(false) {
+                let mut next = view_rx.borrow_and_update().clone();
+                let offset = next.as_mut().and_then(ViewSnapshot::offset);
+                if let (Some(next), Some(drawn)) = (next.as_mut(), drawn.as_mut()) {
+                    // Unless the event loop scrolled it, e.g. to a remembered position, the
+                    // view stays where it was last drawn
+                    if Arc::ptr_eq(&next.view, &drawn.view) && offset == published_offset {
+                        next.scroll_like(drawn);
+                    }
                 }
+                (drawn, published_offset) = (next, offset);
             }
+            let Some(ViewSnapshot {
+                view,
+                view_state,
+                filter,
+            }) = drawn.as_mut()
+            else {
+                continue;
+            };
+            let mut terminal = self.terminal.lock().await;
+            terminal
+                .draw(|f| self.draw(f, f.size(), &state, view, view_state, filter.clone()))
+                .expect("Unable to draw terminal");
         }
     }
 
@@ -355,16 +2268,37 @@ where
         &self,
         f: &mut Frame<B>,
         area: Rect,
-        state: &mut AppState,
+        state: &AppState,
         current_view: &DynAppView<B>,
         view_filter: String,
+        compact: bool,
     ) {
-        if state.is_filter_on {
+        if state.is_filter_on && compact {
+            let filter_input = Paragraph::new(format!("/{}", view_filter))
+                .style(Style::default().fg(Color::Yellow));
+            f.render_widget(filter_input, area);
+        } else if state.is_filter_on {
             let filter_input = Paragraph::new(view_filter)
                 .style(Style::default().fg(Color::Yellow))
                 .block(Block::default().borders(Borders::ALL).title("Filter"))
                 .wrap(Wrap { trim: true });
             f.render_widget(filter_input, area);
+        } else if let Some(jump) = state.jump.as_ref().filter(|_| compact) {
+            let jump_input =
+                Paragraph::new(format!("'{}", jump)).style(Style::default().fg(Color::Cyan));
+            f.render_widget(jump_input, area);
+        } else if let Some(jump) = &state.jump {
+            let jump_input = Paragraph::new(jump.clone())
+                .style(Style::default().fg(Color::Cyan))
+                .block(Block::default().borders(Borders::ALL).title("Jump"))
+                .wrap(Wrap { trim: true });
+            f.render_widget(jump_input, area);
+        } else if compact {
+            // A single line of hints, whatever doesn't fit is cut off
+            let top_bar_content = current_view
+                .draw_top_bar(state)
+                .style(Style::default().fg(Color::Yellow));
+            f.render_widget(top_bar_content, area);
         } else {
             let top_bar_content = current_view
                 .draw_top_bar(state)
@@ -380,46 +2314,61 @@ where
         &self,
         f: &mut Frame<B>,
         _area: Rect,
-        state: &mut AppState,
+        state: &AppState,
         current_view: &DynAppView<B>,
         view_state: &mut ViewState,
         view_filter: String,
     ) {
         let size = f.size();
+        if size.width < MIN_TERMINAL_WIDTH || size.height < MIN_TERMINAL_HEIGHT {
+            let message = Paragraph::new(format!(
+                "Terminal too small ({}x{})\nPlease enlarge it to at least {}x{}",
+                size.width, size.height, MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT
+            ))
+            .style(Style::default().fg(Color::Yellow))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+            let top = size.height.saturating_sub(2) / 2;
+            f.render_widget(
+                message,
+                Rect::new(size.x, size.y + top, size.width, size.height - top),
+            );
+            return;
+        }
+        let compact = size.width < COMPACT_TERMINAL_WIDTH || size.height < COMPACT_TERMINAL_HEIGHT;
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .margin(1)
+            .margin(if compact { 0 } else { 1 })
             .constraints(
                 [
-                    Constraint::Length(3),
+                    Constraint::Length(if compact { 1 } else { 3 }),
+                    Constraint::Length(1),
                     Constraint::Min(0),
-                    Constraint::Length(2),
+                    Constraint::Length(if compact { 1 } else { 2 }),
                 ]
                 .as_ref(),
             )
             .split(size);
-        self.draw_top_bar(f, layout[0], state, current_view, view_filter);
-        current_view.draw(f, layout[1], state, view_state);
-        self.draw_error_bar(f, layout[2], state);
+        self.draw_top_bar(f, layout[0], state, current_view, view_filter, compact);
+        f.render_widget(Paragraph::new(render_banner(state)), layout[1]);
+        current_view.draw(f, layout[2], state, view_state);
+        toast::render_toasts(f, layout[2], state);
+        self.draw_status_bar(f, layout[3], state);
     }
 
-    pub fn draw_error_bar(&self, f: &mut Frame<B>, area: Rect, state: &mut AppState) {
-        if let (Some(msg), Some(ts)) = (&state.last_message, &state.last_message_timestamp) {
-            if *ts + chrono::Duration::seconds(6) > chrono::Utc::now() {
-                let error_bar = match msg {
-                    UiMessage::Error(msg) => {
-                        Paragraph::new(msg.as_str()).style(Style::default().fg(Color::Red))
-                    }
-                    UiMessage::Info(msg) => {
-                        Paragraph::new(msg.as_str()).style(Style::default().fg(Color::DarkGray))
-                    }
-                    UiMessage::Success(msg) => {
-                        Paragraph::new(msg.as_str()).style(Style::default().fg(Color::Green))
-                    }
-                }
-                .wrap(Wrap { trim: true });
-                f.render_widget(error_bar, area);
+    pub fn draw_status_bar(&self, f: &mut Frame<B>, area: Rect, state: &AppState) {
+        if let Some(label) = state.running_tasks.values().next() {
+            let mut summary = format!("{}...", label);
+            if state.running_tasks.len() > 1 {
+                summary.push_str(&format!(" (+{} more)", state.running_tasks.len() - 1));
+            }
+            if !state.queued_tasks.is_empty() {
+                summary.push_str(&format!(", {} queued", state.queued_tasks.len()));
             }
+            f.render_widget(
+                Paragraph::new(summary).style(Style::default().fg(Color::DarkGray)),
+                area,
+            );
         }
     }
 
@@ -429,6 +2378,15 @@ where
             KtxEvent::TerminalEvent(evt) => self.handle_terminal_event(evt, &mut state).await,
             _ => self.handle_app_event(event, &mut state).await,
         };
+        // Most events only move a selection, those leave the published snapshot alone
+        self.state_tx.send_if_modified(|published| {
+            let modified = **published != *state;
+            if modified {
+                *published = Arc::new(state.clone());
+            }
+            modified
+        });
+        self.publish_view().await;
         if let Err(e) = result {
             let _ = self
                 .event_bus_tx
@@ -437,22 +2395,522 @@ where
         }
     }
 
+    // The renderer never locks the views, it gets a copy of the one on top instead
+    async fn publish_view(&self) {
+        let view_stack = self.view_stack.lock().await;
+        if let Some(view) = view_stack.last() {
+            let view_state = view.get_state_mutex().lock().await.clone();
+            self.view_tx.send_replace(Some(ViewSnapshot {
+                view: view.clone(),
+                view_state,
+                filter: view.get_filter().await,
+            }));
+        }
+    }
+
+    // Stops background work right away and exits once the events queued so far are handled
+    pub async fn interrupt(&self) {
+        self.tasks.cancel_all();
+        let _ = self.event_bus_tx.send(KtxEvent::Exit).await;
+    }
+
+    // Ctrl-Z is a key press in raw mode too, so the terminal is handed back and the process stops
+    // itself. Execution continues here once the shell resumes it.
+    #[cfg(unix)]
+    async fn suspend(&self) -> EmptyResult {
+        {
+            let mut terminal = self.terminal.lock().await;
+            disable_raw_mode()?;
+            execute!(io::stdout(), DisableBracketedPaste, LeaveAlternateScreen)?;
+            terminal.show_cursor()?;
+        }
+        // SAFETY: raising a signal has no memory safety requirements
+        unsafe {
+            libc::raise(libc::SIGTSTP);
+        }
+        self.resume().await
+    }
+
+    // Also runs on SIGCONT, after a stop that didn't go through Ctrl-Z the terminal may have been
+    // reset by the shell
+    pub async fn resume(&self) -> EmptyResult {
+        let mut terminal = self.terminal.lock().await;
+        execute!(io::stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
+        enable_raw_mode()?;
+        // Forces a full redraw
+        terminal.clear()?;
+        Ok(())
+    }
+
+    pub fn is_in_shell(&self) -> bool {
+        self.in_shell.load(Ordering::SeqCst)
+    }
+
     pub async fn shutdown(&self) {
+        self.tasks.cancel_all();
+        // Waits for a kubeconfig write in progress
+        let mut state = self.state.lock().await;
+        if state.config.remembers_views() {
+            let view_stack = self.view_stack.lock().await;
+            for view in view_stack.iter() {
+                self.remember_view(view.as_ref(), &mut state).await;
+            }
+            state.session.stack = if state.config.restore_session {
+                view_stack
+                    .iter()
+                    .filter_map(|view| view.open_view())
+                    .collect()
+            } else {
+                vec![]
+            };
+            drop(view_stack);
+            let path = SessionState::path(&state.metadata_path);
+            if let Err(e) = state.session.save(&path).await {
+                log::warn!("Unable to save session: {}", e);
+            }
+        }
+        drop(state);
+        self.tunnels.close_all().await;
         self.terminal
             .lock()
             .await
             .clear()
             .expect("Failed to clear terminal");
+        execute!(io::stdout(), DisableBracketedPaste, LeaveAlternateScreen)
+            .expect("Failed to leave alternate screen");
         disable_raw_mode().expect("Failed to disable raw mode");
-        self.terminal.lock().await.show_cursor().expect("Failed to show cursor");
+        self.terminal
+            .lock()
+            .await
+            .show_cursor()
+            .expect("Failed to show cursor");
+    }
+
+    pub async fn open_shell(&self, name: String) {
+        self.in_shell.store(true, Ordering::SeqCst);
+        let result = self.run_shell(&name).await;
+        self.in_shell.store(false, Ordering::SeqCst);
+        let event = match result {
+            Ok(()) => KtxEvent::PushInfoMessage(format!("Left shell for {}", name)),
+            Err(e) => KtxEvent::PushErrorMessage(e.to_string()),
+        };
+        let _ = self.event_bus_tx.send(event).await;
+    }
+
+    async fn run_shell(&self, name: &str) -> EmptyResult {
+        let state = self.state.lock().await;
+        let kubeconfig = self
+            .tunnels
+            .effective_kubeconfig(&state.kubeconfig, &state.metadata, name)
+            .await?;
+        let kubeconfig = kubeconfig::extract_context(&kubeconfig, name)
+            .ok_or(format!("Context {} not found", name))?;
+        let shell_command = state.config.shell_command.clone();
+        let shell_prompt = state.config.shell_prompt.clone();
+        drop(state);
+
+        // A directory only the user can enter, removed with its content when dropped
+        let dir = tempfile::Builder::new().prefix("ktx-").tempdir()?;
+        let path = dir.path().join("kubeconfig.yaml");
+        kubeconfig::save_private(&kubeconfig, &path).await?;
+
+        let (program, args) = match &shell_command {
+            Some(command) => {
+                let mut parts = command.split_whitespace().map(String::from);
+                let program = parts.next().ok_or("shell_command is empty")?;
+                (program, parts.collect())
+            }
+            None => (
+                std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string()),
+                vec![],
+            ),
+        };
+
+        // Holding the terminal keeps the renderer from drawing over the child process
+        let mut terminal = self.terminal.lock().await;
+        disable_raw_mode()?;
+        execute!(io::stdout(), DisableBracketedPaste, LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+        let status = tokio::process::Command::new(&program)
+            .args(&args)
+            .env("KUBECONFIG", &path)
+            .env("KTX_CONTEXT", name)
+            .envs(prompt_env(&program, shell_prompt.as_deref(), name))
+            .status()
+            .await;
+        drop(dir);
+        execute!(io::stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
+        enable_raw_mode()?;
+        terminal.clear()?;
+        status.map_err(|e| format!("Unable to start {}: {}", program, e))?;
+        Ok(())
     }
 
     async fn write_kubeconfig(&self, state: &mut AppState) -> EmptyResult {
-        let _config_guard = state.config_lock.lock().await;
-        let serialized_kubeconfig = serde_yaml::to_string(&state.kubeconfig)?;
-        let path = Path::new(state.kubeconfig_path.as_str());
-        let mut file = fs::File::create(&path).await?;
-        file.write_all(serialized_kubeconfig.as_bytes()).await?;
+        kubeconfig::save_sources(
+            &state.kubeconfig,
+            &state.kubeconfig_sources,
+            &history::dir(&state.metadata_path),
+        )
+        .await
+    }
+
+    // Shared files are left alone, like for single deletions
+    async fn confirm_bulk_delete(&self, state: &AppState, mut names: Vec<String>, reason: &str) {
+        names.retain(|name| !state.kubeconfig_sources.is_read_only(name));
+        if names.is_empty() {
+            let _ = self
+                .event_bus_tx
+                .send(KtxEvent::PushInfoMessage(format!(
+                    "No {} contexts to delete",
+                    reason
+                )))
+                .await;
+            return;
+        }
+        let mut view_stack = self.view_stack.lock().await;
+        view_stack.push(Arc::new(ConfirmationDialogView::new(
+            self.event_bus_tx.clone(),
+            format!(
+                "Delete {} {} context(s)\n\n{}\n\nfrom your kubeconfig file?",
+                names.len(),
+                reason,
+                names.join("\n")
+            ),
+            KtxEvent::DeleteContextsConfirm((names, reason.to_string())),
+        )));
+    }
+
+    async fn remember_view(&self, view: &(dyn AppView<B> + Send + Sync), state: &mut AppState) {
+        if !state.config.remembers_views() {
+            return;
+        }
+        if let (Some(key), Some(memory)) = (view.memory_key(), view.get_memory(state).await) {
+            state.session.views.insert(key, memory);
+        }
+    }
+
+    async fn restore_view(&self, view: &(dyn AppView<B> + Send + Sync), state: &AppState) {
+        if !state.config.remembers_views() {
+            return;
+        }
+        if let Some(memory) = view
+            .memory_key()
+            .and_then(|key| state.session.views.get(&key))
+        {
+            view.restore_memory(memory.clone(), state).await;
+        }
+    }
+
+    fn schedule_reload(&self) {
+        if self.refresh_pending.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let event_bus = self.event_bus_tx.clone();
+        // Only waits, and shouldn't be held up behind queued imports either
+        tokio::spawn(async move {
+            tokio::time::sleep(REFRESH_DEBOUNCE).await;
+            let _ = event_bus.send(KtxEvent::ReloadConfig).await;
+        });
+    }
+
+    async fn write_metadata(&self, state: &mut AppState) -> EmptyResult {
+        state.metadata.save(&state.metadata_path).await
+    }
+
+    // A failed audit write is reported but never blocks the change it describes
+    async fn audit(&self, state: &AppState, action: &str, context: &str, detail: Option<String>) {
+        let Some(path) = &state.config.audit_log else {
+            return;
+        };
+        if let Err(e) = audit::append(path, action, context, detail).await {
+            let _ = self
+                .event_bus_tx
+                .send(KtxEvent::PushErrorMessage(format!(
+                    "Unable to write the audit log: {}",
+                    e
+                )))
+                .await;
+        }
+    }
+
+    async fn audit_import(
+        &self,
+        state: &AppState,
+        contexts: &[String],
+        provenance: &Option<Provenance>,
+    ) {
+        let detail = provenance.as_ref().map(|p| {
+            [
+                Some(p.provider.as_str()),
+                Some(p.account.as_str()),
+                p.location.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+        });
+        for name in contexts {
+            self.audit(state, "import", name, detail.clone()).await;
+        }
+    }
+
+    async fn restore_current_context(&self, state: &mut AppState) -> EmptyResult {
+        if !state.config.keep_current_context {
+            return Ok(());
+        }
+        let Some(before) = state.context_before_import.clone() else {
+            return Ok(());
+        };
+        if state.kubeconfig.current_context.as_ref() == Some(&before)
+            || kubeconfig::find_context(&state.kubeconfig, &before).is_none()
+        {
+            return Ok(());
+        }
+        log::info!(
+            "Restoring current-context {} after an import changed it to {}",
+            before,
+            state.kubeconfig.current_context.clone().unwrap_or_default()
+        );
+        state.kubeconfig.current_context = Some(before);
+        self.write_kubeconfig(state).await
+    }
+
+    // Keys offline mode disables aren't errors, they just say why nothing happens
+    async fn offline_notice(&self, what: &str) {
+        let _ = self
+            .event_bus_tx
+            .send(KtxEvent::PushInfoMessage(format!(
+                "{} disabled in offline mode",
+                what
+            )))
+            .await;
+    }
+
+    async fn merge_import(
+        &self,
+        state: &mut AppState,
+        mut incoming: Kubeconfig,
+        provenance: Option<Provenance>,
+        resolution: ConflictResolution,
+    ) -> EmptyResult {
+        let retargeted = state.retargeted_context(&incoming);
+        if retargeted.is_some()
+            && state.config.import_current_context != ImportCurrentContext::Switch
+        {
+            incoming.current_context = None;
+        }
+        if let Some(name) = retargeted
+            .as_ref()
+            .filter(|_| state.context_before_import.is_some())
+            .filter(|_| state.config.import_current_context == ImportCurrentContext::Switch)
+        {
+            state.context_before_import = Some(name.clone());
+        }
+        let merged = kubeconfig::merge(&mut state.kubeconfig, incoming, resolution);
+        self.write_kubeconfig(state).await?;
+        self.finish_import(state, merged, provenance).await?;
+        if let (Some(name), ImportCurrentContext::Prompt) =
+            (retargeted, state.config.import_current_context)
+        {
+            let mut view_stack = self.view_stack.lock().await;
+            view_stack.push(Arc::new(ConfirmationDialogView::new(
+                self.event_bus_tx.clone(),
+                format!(
+                    "The import points current-context at {}.\n\nSwitch to it? Otherwise {} stays current.",
+                    name,
+                    state.kubeconfig.current_context.clone().unwrap_or_default()
+                ),
+                KtxEvent::SwitchContext(name),
+            )));
+        }
         Ok(())
     }
+
+    async fn finish_import(
+        &self,
+        state: &mut AppState,
+        contexts: Vec<String>,
+        provenance: Option<Provenance>,
+    ) -> EmptyResult {
+        // Only once written, a preview or conflict dialog may have been cancelled instead
+        if !contexts.is_empty() {
+            let _ = self
+                .event_bus_tx
+                .send(KtxEvent::PushSuccessMessage(format!(
+                    "Successfully imported {}",
+                    contexts.join(", ")
+                )))
+                .await;
+        }
+        self.audit_import(state, &contexts, &provenance).await;
+        self.record_provenance(state, contexts.clone(), provenance)
+            .await?;
+        self.wait_for_ready(state, &contexts).await
+    }
+
+    // Freshly provisioned clusters can take a while to serve requests, so keep polling them
+    // instead of leaving them Unhealthy until the next manual test
+    async fn wait_for_ready(&self, state: &AppState, contexts: &[String]) -> EmptyResult {
+        if state.config.import_wait_timeout == 0 || state.config.offline {
+            return Ok(());
+        }
+        let deadline = Duration::from_secs(state.config.import_wait_timeout);
+        for name in contexts {
+            let name = name.clone();
+            let kubeconfig = state.kubeconfig.clone();
+            let event_bus = self.event_bus_tx.clone();
+            let family = state.config.ip_family_for(&name);
+            let probe = state.config.health_probe_for(&name);
+            self.tasks
+                .submit(format!("Waiting for {}", name), async move {
+                    let started = std::time::Instant::now();
+                    loop {
+                        let elapsed = started.elapsed();
+                        let _ = event_bus
+                            .send(KtxEvent::SetConnectivityStatus((
+                                name.clone(),
+                                KubeContextStatus::Waiting(elapsed.as_secs()),
+                            )))
+                            .await;
+                        let probe = tokio::time::timeout(
+                            SWITCH_CHECK_TIMEOUT,
+                            cluster::health_check(&kubeconfig, &name, family, &probe),
+                        )
+                        .await;
+                        if let Ok(Ok(version)) = probe {
+                            let _ = event_bus
+                                .send(KtxEvent::SetConnectivityStatus((
+                                    name.clone(),
+                                    KubeContextStatus::Healthy(version),
+                                )))
+                                .await;
+                            let _ = event_bus
+                                .send(KtxEvent::PushMessage(UiMessage::about(
+                                    MessageLevel::Success,
+                                    format!("{} is ready", name),
+                                    &name,
+                                )))
+                                .await;
+                            return;
+                        }
+                        if started.elapsed() >= deadline {
+                            let _ = event_bus
+                                .send(KtxEvent::SetConnectivityStatus((
+                                    name.clone(),
+                                    KubeContextStatus::Unhealthy,
+                                )))
+                                .await;
+                            let _ = event_bus
+                                .send(KtxEvent::PushMessage(UiMessage::about(
+                                    MessageLevel::Info,
+                                    format!(
+                                        "{} didn't become ready within {}s",
+                                        name,
+                                        deadline.as_secs()
+                                    ),
+                                    &name,
+                                )))
+                                .await;
+                            return;
+                        }
+                        tokio::time::sleep(IMPORT_WAIT_INTERVAL).await;
+                    }
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn record_provenance(
+        &self,
+        state: &mut AppState,
+        contexts: Vec<String>,
+        provenance: Option<Provenance>,
+    ) -> EmptyResult {
+        if let Some(provenance) = provenance {
+            for name in contexts {
+                state.metadata.record_import(
+                    &name,
+                    provenance.clone(),
+                    &state.config.copy_import_tags,
+                );
+            }
+            self.write_metadata(state).await?;
+        }
+        Ok(())
+    }
+}
+
+// Re-applies the prompt fragment after rc files replaced PS1. It reads KTX_PROMPT at runtime so a
+// shell started from a nested ktx shows the innermost context only.
+const BASH_PROMPT_HOOK: &str =
+    r#"case "$PS1" in "$KTX_PROMPT"*) ;; *) PS1="$KTX_PROMPT$PS1" ;; esac"#;
+
+fn prompt_env(program: &str, template: Option<&str>, context: &str) -> Vec<(String, String)> {
+    let Some(template) = template else {
+        return vec![];
+    };
+    let fragment = template.replace("{context}", context);
+    let outer = std::env::var("KTX_PROMPT").unwrap_or_default();
+    let ps1 = std::env::var("PS1").unwrap_or_else(|_| "$ ".to_string());
+    let ps1 = ps1.strip_prefix(outer.as_str()).unwrap_or(&ps1);
+    let mut env = vec![
+        ("PS1".to_string(), format!("{}{}", fragment, ps1)),
+        ("KTX_PROMPT".to_string(), fragment),
+    ];
+    if Path::new(program).file_name().and_then(|n| n.to_str()) == Some("bash") {
+        let hook = match std::env::var("PROMPT_COMMAND") {
+            Ok(existing) if existing.contains(BASH_PROMPT_HOOK) => existing,
+            Ok(existing) if !existing.is_empty() => format!("{}; {}", existing, BASH_PROMPT_HOOK),
+            _ => BASH_PROMPT_HOOK.to_string(),
+        };
+        env.push(("PROMPT_COMMAND".to_string(), hook));
+    }
+    env
+}
+
+// Keeps dialogs readable when importing a kubeconfig with many entries
+const MERGE_PREVIEW_LINES: usize = 15;
+
+// Clients of a context go through its tunnel and address family, the way health checks do
+async fn context_client(
+    tunnels: &TunnelPool,
+    kubeconfig: &Kubeconfig,
+    metadata: &KtxMetadata,
+    name: &str,
+    family: IpFamily,
+) -> Result<kube::Client, Box<dyn Error + Send + Sync>> {
+    let kubeconfig = tunnels
+        .effective_kubeconfig(kubeconfig, metadata, name)
+        .await?;
+    cluster::client_for_context(&kubeconfig, name, family).await
+}
+
+fn format_merge_preview(preview: &[String]) -> String {
+    let mut lines: Vec<String> = preview.iter().take(MERGE_PREVIEW_LINES).cloned().collect();
+    if preview.len() > MERGE_PREVIEW_LINES {
+        lines.push(format!(
+            "… and {} more",
+            preview.len() - MERGE_PREVIEW_LINES
+        ));
+    }
+    lines.join("\n")
+}
+
+fn open_in_browser(url: &str) -> EmptyResult {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener)
+        .arg(url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Unable to run {}: {}", opener, e))?;
+    Ok(())
 }
@@ -0,0 +1,81 @@
+// Applying reloaded kubeconfigs to the app state.
+
+use kube::config::Kubeconfig;
+
+use super::AppState;
+use crate::kubeconfig::{ContextChange, Sources};
+use crate::ui::KubeContextStatus;
+
+fn fleet(count: usize, server: &str) -> Kubeconfig {
+    let mut yaml = String::from("clusters:\n");
+    for i in 0..count {
+        yaml += &format!("- name: c{i}\n  cluster:\n    server: {server}/{i}\n");
+    }
+    yaml += "contexts:\n";
+    for i in 0..count {
+        yaml += &format!("- name: c{i}\n  context:\n    cluster: c{i}\n    user: u\n");
+    }
+    serde_yaml::from_str(&yaml).unwrap()
+}
+
+#[test]
+fn reloading_many_changed_contexts_forgets_their_status() {
+    let mut state = AppState {
+        kubeconfig: fleet(2000, "https://old.example.com").into(),
+        ..Default::default()
+    };
+    for context in &state.kubeconfig.contexts {
+        state.connectivity_status.insert(
+            context.name.clone(),
+            KubeContextStatus::Healthy("v1.27".to_string()),
+        );
+        state
+            .health_failures
+            .insert(context.name.clone(), "timed out".to_string());
+    }
+
+    let changes = state.apply_reload(fleet(2000, "https://new.example.com"), Sources::default());
+
+    assert_eq!(changes.len(), 2000);
+    assert!(changes
+        .iter()
+        .all(|change| matches!(change, ContextChange::Changed(_))));
+    assert!(state.connectivity_status.is_empty());
+    assert!(state.health_failures.is_empty());
+    assert_eq!(state.kubeconfig.contexts.len(), 2000);
+}
+
+#[test]
+fn reloading_drops_the_history_of_removed_contexts_only() {
+    let mut state = AppState {
+        kubeconfig: fleet(3, "https://old.example.com").into(),
+        ..Default::default()
+    };
+    for context in &state.kubeconfig.contexts {
+        state.health_history.insert(context.name.clone(), vec![]);
+    }
+    let mut reloaded = fleet(3, "https://old.example.com");
+    reloaded.contexts.retain(|c| c.name != "c1");
+
+    let changes = state.apply_reload(reloaded, Sources::default());
+
+    assert_eq!(changes, vec![ContextChange::Removed("c1".to_string())]);
+    let mut kept: Vec<_> = state.health_history.keys().cloned().collect();
+    kept.sort();
+    assert_eq!(kept, vec!["c0", "c2"]);
+}
+
+#[test]
+fn snapshots_share_the_kubeconfig_until_it_is_written_to() {
+    let mut state = AppState {
+        kubeconfig: fleet(3, "https://old.example.com").into(),
+        ..Default::default()
+    };
+    let snapshot = state.clone();
+    assert!(state == snapshot);
+
+    state.kubeconfig.contexts.retain(|c| c.name != "c1");
+
+    assert!(state != snapshot);
+    assert_eq!(snapshot.kubeconfig.contexts.len(), 3);
+}
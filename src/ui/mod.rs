@@ -1,6 +1,7 @@
+mod app;
+mod tasks;
 mod types;
 mod views;
-mod app;
 
 pub use app::{AppView, KtxApp};
-pub use types::{KtxEvent, KubeContextStatus, RendererMessage};
+pub use types::{CloudImportPath, HealthSample, KtxEvent, KubeContextStatus, RendererMessage};
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+
+use futures::future::{AbortHandle, Abortable, BoxFuture};
+use futures::FutureExt;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::ui::KtxEvent;
+
+#[cfg(test)]
+mod tests;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+pub type TaskId = u64;
+
+pub const TASK_WORKERS: usize = 8;
+pub const TASK_QUEUE_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskOutcome {
+    Done,
+    Cancelled,
+}
+
+struct Job {
+    id: TaskId,
+    work: Abortable<BoxFuture<'static, ()>>,
+}
+
+// Runs the subprocess and network work of the UI on a fixed number of workers. Tasks report being
+// queued, started and finished on a channel of their own: submitting happens on the event loop,
+// which would wait on itself if the bus it drains were full. Submitting more than the queue holds
+// fails instead of piling up work nobody waits for anymore.
+//
+// Timers that only send an event now and then are spawned on their own, they would hold a worker
+// for as long as they run.
+pub struct TaskPool {
+    queue_tx: mpsc::Sender<Job>,
+    task_events_tx: mpsc::UnboundedSender<KtxEvent>,
+    next_id: AtomicU64,
+    handles: Arc<StdMutex<HashMap<TaskId, AbortHandle>>>,
+}
+
+impl TaskPool {
+    pub fn new(
+        workers: usize,
+        capacity: usize,
+        task_events_tx: mpsc::UnboundedSender<KtxEvent>,
+    ) -> Self {
+        let (queue_tx, queue_rx) = mpsc::channel::<Job>(capacity);
+        let queue_rx = Arc::new(Mutex::new(queue_rx));
+        let handles: Arc<StdMutex<HashMap<TaskId, AbortHandle>>> = Arc::default();
+        for _ in 0..workers {
+            let queue_rx = queue_rx.clone();
+            let task_events_tx = task_events_tx.clone();
+            let handles = handles.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some(job) = queue_rx.lock().await.recv().await else {
+                        break;
+                    };
+                    // Cancelled tasks still go through the queue but never start
+                    if !job.work.is_aborted() {
+                        let _ = task_events_tx.send(KtxEvent::TaskStarted(job.id));
+                    }
+                    let outcome = match job.work.await {
+                        Ok(()) => TaskOutcome::Done,
+                        Err(_) => TaskOutcome::Cancelled,
+                    };
+                    handles.lock().unwrap().remove(&job.id);
+                    let _ = task_events_tx.send(KtxEvent::TaskFinished((job.id, outcome)));
+                }
+            });
+        }
+        Self {
+            queue_tx,
+            task_events_tx,
+            next_id: AtomicU64::new(1),
+            handles,
+        }
+    }
+
+    pub async fn submit<F>(&self, label: impl Into<String>, work: F) -> Result<TaskId, BoxError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let permit = self
+            .queue_tx
+            .try_reserve()
+            .map_err(|_| "Too many background tasks are queued, try again once some finish")?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (handle, registration) = AbortHandle::new_pair();
+        self.handles.lock().unwrap().insert(id, handle);
+        let _ = self
+            .task_events_tx
+            .send(KtxEvent::TaskQueued((id, label.into())));
+        permit.send(Job {
+            id,
+            work: Abortable::new(work.boxed(), registration),
+        });
+        Ok(id)
+    }
+
+    // Queued tasks are dropped before they start, running ones at their next await point, which
+    // also kills the CLI processes they wait on
+    pub fn cancel_all(&self) -> usize {
+        let handles = self.handles.lock().unwrap();
+        for handle in handles.values() {
+            handle.abort();
+        }
+        handles.len()
+    }
+}
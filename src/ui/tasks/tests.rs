@@ -0,0 +1,31 @@
+// Task lifecycle reporting, which must never wait on whoever drains it.
+
+use tokio::sync::mpsc;
+
+use super::{TaskOutcome, TaskPool};
+use crate::ui::KtxEvent;
+
+#[tokio::test]
+async fn submitting_never_waits_for_lifecycle_events_to_be_read() {
+    let (task_events_tx, mut task_events_rx) = mpsc::unbounded_channel();
+    let pool = TaskPool::new(1, 64, task_events_tx);
+    let mut ids = vec![];
+    // Nobody reads the events until everything is submitted, like the event loop submitting
+    for _ in 0..64 {
+        ids.push(pool.submit("noop", async {}).await.unwrap());
+    }
+
+    let mut finished = vec![];
+    while finished.len() < ids.len() {
+        match task_events_rx.recv().await.unwrap() {
+            KtxEvent::TaskFinished((id, outcome)) => {
+                assert_eq!(outcome, TaskOutcome::Done);
+                finished.push(id);
+            }
+            KtxEvent::TaskQueued(_) | KtxEvent::TaskStarted(_) => {}
+            _ => panic!("unexpected event"),
+        }
+    }
+    finished.sort();
+    assert_eq!(finished, ids);
+}
@@ -1,15 +1,144 @@
 use std::error::Error;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
+use crate::cloud::{ClusterDetails, ImportOption};
+use crate::cluster::{ClusterInfo, LatencyResult};
+use crate::config::ImportPreset;
+use crate::history::Snapshot;
+use crate::kubeconfig::{ConflictResolution, ContextDraft, EntryKind};
+use crate::metadata::Provenance;
+use crate::ui::tasks::{TaskId, TaskOutcome};
+use crate::ui::views::audit::AuditLogViewState;
+use crate::ui::views::bench::BenchmarkViewState;
 use crate::ui::views::confirmation::ConfirmationDialogViewState;
+use crate::ui::views::conflict::ImportConflictDialogViewState;
+use crate::ui::views::dashboard::HealthDashboardViewState;
+use crate::ui::views::detail::ContextDetailViewState;
+use crate::ui::views::entries::EntriesViewState;
+use crate::ui::views::history::HistoryViewState;
 use crate::ui::views::import::ImportViewState;
+use crate::ui::views::input::InputDialogViewState;
 use crate::ui::views::list::ContextListViewState;
+use crate::ui::views::namespaces::NamespaceListViewState;
+use crate::ui::views::picker::EntryPickerViewState;
+use crate::ui::views::recent::RecentContextsViewState;
+use crate::ui::views::summary::ImportSummaryViewState;
+use crate::ui::views::unhealthy::UnhealthySwitchDialogViewState;
 use crossterm::event::Event;
+use kube::config::Kubeconfig;
+use tui::widgets::ListState;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum KubeContextStatus {
     Unknown,
     Healthy(String),
     Unhealthy,
+    // Seconds spent waiting for a freshly imported cluster to respond
+    Waiting(u64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthSample {
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub status: KubeContextStatus,
+    // Time the check took, when it was a regular connectivity test
+    pub latency: Option<std::time::Duration>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportOutcome {
+    Imported,
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageLevel {
+    Error,
+    Info,
+    Success,
+}
+
+impl MessageLevel {
+    // Errors stay up longer since they usually need reading
+    fn lifetime(self) -> chrono::Duration {
+        match self {
+            MessageLevel::Error => chrono::Duration::seconds(10),
+            MessageLevel::Info | MessageLevel::Success => chrono::Duration::seconds(6),
+        }
+    }
+}
+
+// What x does with a toast
+#[derive(Debug, Clone)]
+pub enum MessageAction {
+    ViewContext(String),
+    Retry(Box<KtxEvent>),
+}
+
+#[derive(Debug, Clone)]
+pub struct UiMessage {
+    pub level: MessageLevel,
+    pub text: String,
+    // Context or cluster the message is about
+    pub context: Option<String>,
+    pub action: Option<MessageAction>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Events can't be compared, the action goes along with the text anyway
+impl PartialEq for UiMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.level == other.level
+            && self.text == other.text
+            && self.context == other.context
+            && self.expires_at == other.expires_at
+    }
+}
+
+impl UiMessage {
+    pub fn new(level: MessageLevel, text: String) -> Self {
+        Self {
+            level,
+            text,
+            context: None,
+            action: None,
+            expires_at: chrono::Utc::now() + level.lifetime(),
+        }
+    }
+
+    // Messages about a context open its details unless they can be retried
+    pub fn about(level: MessageLevel, text: String, context: &str) -> Self {
+        Self {
+            context: Some(context.to_string()),
+            action: Some(MessageAction::ViewContext(context.to_string())),
+            ..Self::new(level, text)
+        }
+    }
+
+    pub fn retry(level: MessageLevel, text: String, context: &str, retry: KtxEvent) -> Self {
+        Self {
+            action: Some(MessageAction::Retry(Box::new(retry))),
+            ..Self::about(level, text, context)
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        match self.action {
+            Some(MessageAction::ViewContext(_)) => Some("x details"),
+            Some(MessageAction::Retry(_)) => Some("x retry"),
+            None => None,
+        }
+    }
+
+    pub fn action_event(&self) -> Option<KtxEvent> {
+        match &self.action {
+            Some(MessageAction::ViewContext(name)) => Some(KtxEvent::ViewContext(name.clone())),
+            Some(MessageAction::Retry(event)) => Some(*event.clone()),
+            None => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -19,15 +148,59 @@ pub enum RendererMessage {
 }
 
 // primary id, display name, optional secondary id
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CloudImportPath(Vec<(String, String, Option<String>)>);
 
+// What a level of the import wizard lists
+pub type ImportListing = Vec<(ImportOption, ClusterDetails)>;
+
+// Shared between the live app state and the snapshots published from it, writes copy it first
+// while a snapshot still holds on to it. Equal only to itself, which tells that it was written
+// to without comparing contents.
+#[derive(Debug, Default)]
+pub struct Shared<T>(Arc<T>);
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<T> for Shared<T> {
+    fn from(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone> DerefMut for Shared<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<T> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 pub type EmptyResult = Result<(), Box<dyn Error + Send + Sync>>;
 
 impl CloudImportPath {
     pub fn is_full(&self) -> bool {
         if self.is_empty() {
             false
+        } else if self.is_directory() {
+            // Directory path: directory -> file
+            self.0.len() == 2
         } else if self.is_gcp() {
             // GCP path: platform -> project -> cluster
             self.0.len() == 3
@@ -45,6 +218,8 @@ impl CloudImportPath {
     pub fn is_listing_clusters(&self) -> bool {
         if self.is_empty() {
             false
+        } else if self.is_directory() {
+            self.0.len() == 1
         } else if self.is_gcp() {
             self.0.len() == 2
         } else if self.is_aws() {
@@ -74,6 +249,18 @@ impl CloudImportPath {
         self.0[0].0 == "azure"
     }
 
+    // A folder of kubeconfig files rather than a cloud
+    pub fn is_directory(&self) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        self.0[0].0 == "dir"
+    }
+
+    pub fn get_directory(&self) -> String {
+        self.0[0].2.clone().unwrap()
+    }
+
     pub fn is_gcp(&self) -> bool {
         if self.is_empty() {
             return false;
@@ -81,6 +268,7 @@ impl CloudImportPath {
         self.0[0].0 == "gcp"
     }
 
+    #[allow(dead_code)]
     pub fn has_gcp_project(&self) -> bool {
         self.is_gcp() && self.0.len() > 1
     }
@@ -93,6 +281,7 @@ impl CloudImportPath {
         self.0[2].2.clone().unwrap()
     }
 
+    #[allow(dead_code)]
     pub fn has_aws_profile(&self) -> bool {
         self.is_aws() && self.0.len() > 1
     }
@@ -101,6 +290,7 @@ impl CloudImportPath {
         self.0[1].0.clone()
     }
 
+    #[allow(dead_code)]
     pub fn has_azure_subscription(&self) -> bool {
         self.is_azure() && self.0.len() > 1
     }
@@ -113,6 +303,7 @@ impl CloudImportPath {
         self.0[2].2.clone().unwrap()
     }
 
+    #[allow(dead_code)]
     pub fn has_aws_region(&self) -> bool {
         self.is_aws() && self.0.len() > 2
     }
@@ -131,13 +322,172 @@ impl CloudImportPath {
         Self(new_path)
     }
 
+    // The CLI listing the next level, named when it fails
+    pub fn cli_name(&self) -> &'static str {
+        if self.is_gcp() {
+            "gcloud"
+        } else if self.is_aws() {
+            "aws"
+        } else if self.is_azure() {
+            "az"
+        } else {
+            "ktx"
+        }
+    }
+
+    // What the next level lists, e.g. for "No regions found in prod"
+    pub fn listed_items(&self) -> &'static str {
+        if self.is_directory() {
+            "kubeconfig files"
+        } else if self.is_listing_clusters() {
+            "clusters"
+        } else if self.is_gcp() {
+            "projects"
+        } else if self.is_aws() && self.0.len() == 1 {
+            "profiles"
+        } else if self.is_aws() {
+            "regions"
+        } else if self.is_azure() {
+            "subscriptions"
+        } else {
+            "options"
+        }
+    }
+
+    pub fn display_names(&self) -> Vec<String> {
+        self.0.iter().map(|o| o.1.clone()).collect()
+    }
+
+    // Stable across runs, unlike display names which may carry details that change
+    pub fn key(&self) -> String {
+        self.0
+            .iter()
+            .map(|o| o.0.as_str())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    pub fn to_vec(&self) -> Vec<(String, String, Option<String>)> {
+        self.0.clone()
+    }
+
     pub fn get_platform(&self) -> String {
         self.0[0].0.clone()
     }
+
+    // Matches the context naming scheme used by `aws eks update-kubeconfig`,
+    // `gcloud container clusters get-credentials` and `az aks get-credentials`.
+    fn matches_context_name(&self, name: &str) -> bool {
+        if !self.is_full() {
+            false
+        } else if self.is_gcp() {
+            name == format!(
+                "gke_{}_{}_{}",
+                self.get_gcp_project(),
+                self.get_gke_zone(),
+                self.get_cluster_id()
+            )
+        } else if self.is_aws() {
+            name.starts_with(&format!("arn:aws:eks:{}:", self.get_aws_region()))
+                && name.ends_with(&format!(":cluster/{}", self.get_cluster_id()))
+        } else if self.is_azure() {
+            name == self.get_cluster_id()
+        } else {
+            false
+        }
+    }
+
+    // Files carry no cloud account to trace back to
+    pub fn provenance(&self) -> Option<Provenance> {
+        if self.is_directory() {
+            return None;
+        }
+        Some(Provenance {
+            provider: self.get_platform(),
+            account: self.0[1].0.clone(),
+            location: if self.is_aws() {
+                Some(self.get_aws_region())
+            } else if self.is_gcp() {
+                Some(self.get_gke_zone())
+            } else {
+                None
+            },
+            resource_group: if self.is_azure() {
+                Some(self.get_azure_resource_group())
+            } else {
+                None
+            },
+            cluster: self.get_cluster_id(),
+            imported_at: Some(chrono::Utc::now()),
+            tags: Default::default(),
+            preset: None,
+        })
+    }
+
+    pub fn is_imported(&self, kubeconfig: &Kubeconfig) -> bool {
+        if self.is_directory() && self.is_full() {
+            // File options list the file's contexts one per line
+            let contexts = self.0[1].2.clone().unwrap_or_default();
+            return contexts
+                .lines()
+                .all(|name| kubeconfig.contexts.iter().any(|c| c.name == name));
+        }
+        kubeconfig
+            .contexts
+            .iter()
+            .any(|c| self.matches_context_name(&c.name))
+    }
+}
+
+impl CloudImportPath {
+    // Rebuilds the drilldown a context was imported through, so the import can be run again
+    pub fn from_provenance(provenance: &Provenance) -> Option<Self> {
+        let option =
+            |id: &str, secondary: Option<String>| (id.to_string(), id.to_string(), secondary);
+        let account = option(&provenance.account, None);
+        let path = match provenance.provider.as_str() {
+            "aws" => vec![
+                option("aws", None),
+                account,
+                option(provenance.location.as_deref()?, None),
+                option(&provenance.cluster, None),
+            ],
+            "gcp" => vec![
+                option("gcp", None),
+                account,
+                option(&provenance.cluster, Some(provenance.location.clone()?)),
+            ],
+            "azure" => vec![
+                option("azure", None),
+                account,
+                option(
+                    &provenance.cluster,
+                    Some(provenance.resource_group.clone()?),
+                ),
+            ],
+            _ => return None,
+        };
+        Some(Self(path))
+    }
+
+    // Presets start at their account's cluster listing
+    pub fn from_preset(preset: &ImportPreset) -> Option<Self> {
+        let option = |id: &str| (id.to_string(), id.to_string(), None);
+        let path = match preset.provider.as_str() {
+            "aws" => vec![
+                option("aws"),
+                option(&preset.account),
+                option(preset.region.as_deref()?),
+            ],
+            "gcp" | "azure" => vec![option(&preset.provider), option(&preset.account)],
+            _ => return None,
+        };
+        Some(Self(path))
+    }
 }
 
 impl From<Vec<(String, String, Option<String>)>> for CloudImportPath {
@@ -148,37 +498,169 @@ impl From<Vec<(String, String, Option<String>)>> for CloudImportPath {
 
 #[derive(Debug, Clone)]
 pub enum KtxEvent {
-    // ViewContext(String),
-    SetContext(String),
+    ViewContext(String),
+    SwitchContext(String),
+    ConfirmUnreachableSwitch((String, String)),
+    RetestSwitch(String),
+    ReauthContext(String),
+    SetContext((String, Option<String>)),
     DeleteContext(String),
     DeleteContextConfirm(String),
     ListSelect(usize),
     DialogConfirm,
     DialogReject,
-    ListOneUp,
-    ListOneDown,
+    ListUp(usize),
+    ListDown(usize),
     ListPageUp,
     ListPageDown,
     ListTop,
     ListBottom,
     PushErrorMessage(String),
     PushSuccessMessage(String),
+    // A message tied to a context, with an action for x
+    PushMessage(UiMessage),
     PushInfoMessage(String),
+    // Coalesced into one ReloadConfig, imports send a lot of these in a row
     RefreshConfig,
+    ReloadConfig,
+    // What a reload found changed in the kubeconfig files
+    ContextAdded(String),
+    ContextRemoved(String),
+    ContextChanged(String),
     SetConnectivityStatus((String, KubeContextStatus)),
+    FetchClusterInfo(String),
+    SetClusterInfo((String, ClusterInfo)),
+    ShowNamespaces(String),
+    OpenShell(String),
+    OpenConsole(String),
+    EditNotes(String),
+    SetNotes((String, String)),
+    EditTtl(String),
+    SetTtl((String, String)),
+    DeleteExpiredContexts,
+    DeleteEphemeralContexts,
+    // Names and why they go, e.g. "expired"
+    DeleteContextsConfirm((Vec<String>, String)),
+    SetNamespaces((String, Vec<(String, String)>)),
     ShowImportView(CloudImportPath),
+    ShowImportPreset(String),
+    ImportStarted,
+    DetectCloudProviders,
+    SetCloudProviders(Vec<ImportOption>),
+    // With the preset the listing ran under
+    ImportOptionsLoaded(
+        (
+            CloudImportPath,
+            Option<String>,
+            Result<ImportListing, String>,
+        ),
+    ),
+    RefreshImportedCredentials,
+    RefreshImportedCredentialsConfirm,
+    ShowImportSummary((CloudImportPath, Vec<(ImportOption, ImportOutcome)>)),
+    RetryImports((CloudImportPath, Vec<ImportOption>)),
+    // Set when the import is part of a batch, which is merged without a preview each
+    MergeImportedConfig((Kubeconfig, Option<Provenance>, bool)),
+    ResolveImportConflict((Kubeconfig, Option<Provenance>, ConflictResolution)),
+    ShowAuditLog,
+    ShowHistory,
+    ShowRecentContexts,
+    RestoreSnapshot(Snapshot),
+    RestoreSnapshotConfirm(Snapshot),
+    FlattenKubeconfig,
+    FlattenKubeconfigConfirm,
+    NormalizeKubeconfig,
+    NormalizeKubeconfigConfirm,
+    MinifyContext(String),
+    WriteMinifiedContext((String, String)),
+    RenameContext(String),
+    SetContextName((String, String)),
+    CloneContext(String),
+    CloneContextAs((String, String)),
+    MoveContext(String),
+    TogglePin(String),
+    MoveContextToFile((String, String)),
+    ShowEntries,
+    CreateServiceAccountToken(String),
+    GenerateServiceAccountToken((String, String)),
+    AddServiceAccountContext((String, String, String, String)),
+    AddContext(ContextDraft),
+    PromptContextDraft(ContextDraft),
+    PickEntry((String, EntryKind)),
+    AssignEntry((String, EntryKind, String)),
+    RenameEntry((EntryKind, String)),
+    SetEntryName((EntryKind, String, String)),
+    DeleteEntry((EntryKind, String)),
+    DeleteEntryConfirm((EntryKind, String)),
+    EditServerUrl(String),
+    SetServerUrl((String, String)),
+    ToggleInsecureSkipTlsVerify(String),
+    EditCertificateAuthority(String),
+    SetCertificateAuthority((String, String)),
     EnterFilterMode,
     ExitFilterMode,
+    EnterJumpMode,
     TestConnections,
+    PollConnections,
+    RecordHealthCheck((String, KubeContextStatus, std::time::Duration)),
+    // Error of a failed check, shown when switching to the context
+    RecordHealthFailure((String, String)),
+    SetCertExpiry((String, chrono::DateTime<chrono::Utc>)),
+    ShowHealthDashboard,
+    ShowBenchmark,
+    SetLatency((String, LatencyResult)),
+    TaskQueued((TaskId, String)),
+    TaskStarted(TaskId),
+    TaskFinished((TaskId, TaskOutcome)),
+    CancelTasks,
     PopView,
     Exit,
     TerminalEvent(Event),
 }
 
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone)]
 pub enum ViewState {
     ContextListView(ContextListViewState),
     ConfirmationDialogView(ConfirmationDialogViewState),
     ImportView(ImportViewState),
+    ImportConflictDialogView(ImportConflictDialogViewState),
+    ContextDetailView(ContextDetailViewState),
+    InputDialogView(InputDialogViewState),
+    NamespaceListView(NamespaceListViewState),
+    AuditLogView(AuditLogViewState),
+    ImportSummaryView(ImportSummaryViewState),
+    BenchmarkView(BenchmarkViewState),
+    HealthDashboardView(HealthDashboardViewState),
+    HistoryView(HistoryViewState),
+    RecentContextsView(RecentContextsViewState),
+    EntriesView(EntriesViewState),
+    EntryPickerView(EntryPickerViewState),
+    UnhealthySwitchDialogView(UnhealthySwitchDialogViewState),
+}
+
+impl ViewState {
+    // Lists are scrolled while drawing, the renderer keeps the offsets of its own copy
+    pub fn list_state(&mut self) -> Option<&mut ListState> {
+        match self {
+            ViewState::ContextListView(state) => Some(&mut state.list_state),
+            ViewState::ImportView(state) => Some(&mut state.list_state),
+            ViewState::NamespaceListView(state) => Some(&mut state.list_state),
+            ViewState::AuditLogView(state) => Some(&mut state.list_state),
+            ViewState::ImportSummaryView(state) => Some(&mut state.list_state),
+            ViewState::BenchmarkView(state) => Some(&mut state.list_state),
+            ViewState::HealthDashboardView(state) => Some(&mut state.list_state),
+            ViewState::HistoryView(state) => Some(&mut state.list_state),
+            ViewState::RecentContextsView(state) => Some(&mut state.list_state),
+            ViewState::EntriesView(state) => Some(&mut state.list_state),
+            ViewState::EntryPickerView(state) => Some(&mut state.list_state),
+            ViewState::ConfirmationDialogView(_)
+            | ViewState::ImportConflictDialogView(_)
+            | ViewState::ContextDetailView(_)
+            | ViewState::InputDialogView(_)
+            | ViewState::UnhealthySwitchDialogView(_) => None,
+        }
+    }
 }
 
 macro_rules! impl_view_state {
@@ -202,4 +684,17 @@ impl_view_state!(
     ConfirmationDialogViewState => ViewState::ConfirmationDialogView,
     ContextListViewState => ViewState::ContextListView,
     ImportViewState => ViewState::ImportView,
+    ImportConflictDialogViewState => ViewState::ImportConflictDialogView,
+    ContextDetailViewState => ViewState::ContextDetailView,
+    InputDialogViewState => ViewState::InputDialogView,
+    NamespaceListViewState => ViewState::NamespaceListView,
+    AuditLogViewState => ViewState::AuditLogView,
+    ImportSummaryViewState => ViewState::ImportSummaryView,
+    BenchmarkViewState => ViewState::BenchmarkView,
+    HealthDashboardViewState => ViewState::HealthDashboardView,
+    HistoryViewState => ViewState::HistoryView,
+    RecentContextsViewState => ViewState::RecentContextsView,
+    EntriesViewState => ViewState::EntriesView,
+    EntryPickerViewState => ViewState::EntryPickerView,
+    UnhealthySwitchDialogViewState => ViewState::UnhealthySwitchDialogView,
 );
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::audit::AuditEntry;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, key_style, pad_to_width, styled_list,
+};
+
+#[derive(Clone)]
+pub struct AuditLogViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+    pub entries: Vec<AuditEntry>,
+    pub filter: String,
+}
+
+impl AuditLogViewState {
+    fn get_filtered_entries(&self) -> Vec<&AuditEntry> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry.context.to_lowercase().contains(&filter)
+                    || entry.action.to_lowercase().contains(&filter)
+                    || entry.user.to_lowercase().contains(&filter)
+            })
+            .collect()
+    }
+}
+
+pub struct AuditLogView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl AuditLogView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, mut entries: Vec<AuditEntry>) -> Self {
+        // Most recent first
+        entries.reverse();
+        let mut list_state = ListState::default();
+        if !entries.is_empty() {
+            list_state.select(Some(0));
+        }
+        let state = AuditLogViewState {
+            list_state,
+            remembered_g: false,
+            pending_count: 0,
+            entries,
+            filter: "".to_string(),
+        };
+        Self {
+            event_bus_tx,
+            state: Arc::new(Mutex::new(ViewState::AuditLogView(state))),
+        }
+    }
+}
+
+fn render_entry(entry: &AuditEntry) -> ListItem<'static> {
+    let action_color = match entry.action.as_str() {
+        "delete" => Color::Red,
+        "import" => Color::Green,
+        "rename" => Color::Yellow,
+        _ => Color::Cyan,
+    };
+    let mut spans = vec![
+        Span::styled(
+            entry
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S  ")
+                .to_string(),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled(
+            pad_to_width(&entry.action, 8),
+            Style::default().fg(action_color),
+        ),
+        Span::raw(pad_to_width(&entry.user, 14)),
+        Span::raw(entry.context.clone()),
+    ];
+    if let Some(detail) = &entry.detail {
+        spans.push(Span::styled(
+            format!("  {}", detail),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+#[async_trait]
+impl<B> AppView<B> for AuditLogView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    async fn update_filter(&self, filter: String) {
+        let mut state = self.state.lock().await;
+        let state = AuditLogViewState::from_view_state(&mut state);
+        state.filter = filter;
+    }
+
+    async fn get_filter(&self) -> String {
+        let mut state = self.state.lock().await;
+        let state = AuditLogViewState::from_view_state(&mut state);
+        state.filter.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("/"),
+            action_style(" - filter, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = AuditLogViewState::from_view_state(view_state);
+        let items: Vec<ListItem> = view_state
+            .get_filtered_entries()
+            .into_iter()
+            .map(render_entry)
+            .collect();
+        let total = items.len();
+        let list = styled_list("Audit Log", items);
+        f.render_stateful_widget(list, area, &mut view_state.list_state);
+        draw_list_position(f, area, &view_state.list_state, total);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = AuditLogViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            _ => {
+                let entries_len = view_state.get_filtered_entries().len();
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    entries_len,
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::kubeconfig;
+use crate::ui::{app::AppState, types::KubeContextStatus};
+
+use super::utils::format_age;
+
+// One line on where kubectl would go right now, drawn under the top bar of every view and dialog
+pub fn render_banner(state: &AppState) -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let Some(name) = state.kubeconfig.current_context.clone() else {
+        return Line::from(Span::styled("No current context", dim));
+    };
+    let namespace = kubeconfig::find_context(&state.kubeconfig, &name)
+        .and_then(|c| c.context.as_ref())
+        .and_then(|c| c.namespace.clone())
+        .unwrap_or_else(|| "default".to_string());
+    let (health, color) = match state.connectivity_status.get(&name) {
+        Some(KubeContextStatus::Healthy(v)) => (format!("Healthy ({})", v), Color::Green),
+        Some(KubeContextStatus::Unhealthy) => ("Unhealthy".to_string(), Color::Red),
+        Some(KubeContextStatus::Waiting(secs)) => (format!("Waiting ({}s)", secs), Color::Yellow),
+        _ => ("Unknown".to_string(), Color::DarkGray),
+    };
+    let checked = state
+        .health_history
+        .get(&name)
+        .and_then(|h| h.last())
+        .map(|sample| format!(" · checked {} ago", format_age(sample.checked_at)))
+        .unwrap_or_default();
+    Line::from(vec![
+        Span::styled("● ", Style::default().fg(color)),
+        Span::styled(
+            name,
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(format!("  ns {}  ", namespace), dim),
+        Span::styled(health, Style::default().fg(color)),
+        Span::styled(checked, dim),
+    ])
+}
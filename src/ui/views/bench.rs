@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::cluster::{self, LatencyResult, LatencySort};
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, key_style, pad_to_width, styled_list, truncate_to_width,
+};
+
+const NAME_WIDTH: usize = 50;
+pub const BENCHMARK_ROUNDS: u32 = 5;
+
+#[derive(Clone)]
+pub struct BenchmarkViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+    pub results: Vec<(String, Option<LatencyResult>)>,
+    pub sort: LatencySort,
+}
+
+pub struct BenchmarkView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl BenchmarkView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, names: Vec<String>) -> Self {
+        let mut list_state = ListState::default();
+        if !names.is_empty() {
+            list_state.select(Some(0));
+        }
+        let state = BenchmarkViewState {
+            list_state,
+            remembered_g: false,
+            pending_count: 0,
+            results: names.into_iter().map(|name| (name, None)).collect(),
+            sort: LatencySort::Avg,
+        };
+        Self {
+            event_bus_tx,
+            state: Arc::new(Mutex::new(ViewState::BenchmarkView(state))),
+        }
+    }
+}
+
+fn render_result(result: &(String, Option<LatencyResult>)) -> ListItem<'static> {
+    let (name, result) = result;
+    let name = Span::raw(pad_to_width(
+        &truncate_to_width(name, NAME_WIDTH - 1),
+        NAME_WIDTH,
+    ));
+    let stats = match result {
+        Some(Ok(stats)) => Span::styled(
+            format!(
+                "{:>9}  {:>9}  {:>9}",
+                cluster::format_millis(stats.min),
+                cluster::format_millis(stats.avg),
+                cluster::format_millis(stats.max)
+            ),
+            Style::default().fg(Color::Green),
+        ),
+        Some(Err(e)) => Span::styled(e.clone(), Style::default().fg(Color::Red)),
+        None => Span::styled("measuring...", Style::default().fg(Color::DarkGray)),
+    };
+    ListItem::new(Line::from(vec![name, stats]))
+}
+
+#[async_trait]
+impl<B> AppView<B> for BenchmarkView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("s"),
+            action_style(" - sort, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = BenchmarkViewState::from_view_state(view_state);
+        let items: Vec<ListItem> = view_state.results.iter().map(render_result).collect();
+        let total = items.len();
+        let list = styled_list(
+            format!(
+                "API latency min / avg / max over {} round-trips, sorted by {}",
+                BENCHMARK_ROUNDS,
+                view_state.sort.label()
+            )
+            .as_str(),
+            items,
+        );
+        f.render_stateful_widget(list, area, &mut view_state.list_state);
+        draw_list_position(f, area, &view_state.list_state, total);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = BenchmarkViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('s'),
+                            ..
+                        }) => {
+                            view_state.sort = view_state.sort.next();
+                            view_state.sort.sort(&mut view_state.results);
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            KtxEvent::SetLatency((name, result)) => {
+                if let Some(entry) = view_state.results.iter_mut().find(|(n, _)| *n == name) {
+                    entry.1 = Some(result);
+                }
+                view_state.sort.sort(&mut view_state.results);
+                Ok(None)
+            }
+            _ => {
+                let results_len = view_state.results.len();
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    results_len,
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
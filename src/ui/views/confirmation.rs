@@ -12,9 +12,13 @@ use tui::{
     Frame,
 };
 
-use crate::ui::{app::{AppState, HandleEventResult}, types::ViewState, AppView, KtxEvent};
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::ViewState,
+    AppView, KtxEvent,
+};
 
-use super::utils::{action_style, key_style, styled_button};
+use super::utils::{action_style, centered_rect, key_style, styled_button};
 
 #[derive(Clone, Copy, Debug)]
 pub enum ConfirmationDialogSelection {
@@ -30,12 +34,13 @@ pub struct ConfirmationDialogView {
     state: Arc<Mutex<ViewState>>,
 }
 
+#[derive(Clone)]
 pub struct ConfirmationDialogViewState {
     pub selection: ConfirmationDialogSelection,
 }
 
 impl ConfirmationDialogView {
-    pub fn new<B: Backend>(
+    pub fn new(
         event_bus_tx: mpsc::Sender<KtxEvent>,
         content: String,
         on_confirm_event: KtxEvent,
@@ -96,13 +101,12 @@ where
 
     fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
         let state = ConfirmationDialogViewState::from_view_state(view_state);
-        let dialog_width = (area.width as f32 * 0.4) as u16;
-        let dialog_height = (area.height as f32 * 0.4) as u16;
-
-        let dialog_left = (area.width - dialog_width) / 2;
-        let dialog_top = (area.height - dialog_height) / 2;
-
-        let dialog = Rect::new(dialog_left, dialog_top, dialog_width, dialog_height);
+        // Wide enough for both buttons
+        let dialog = centered_rect(
+            area,
+            ((area.width as f32 * 0.4) as u16).max(56),
+            ((area.height as f32 * 0.4) as u16).max(10),
+        );
 
         // Create a layout inside the dialog
         let layout = Layout::default()
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use kube::config::Kubeconfig;
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::kubeconfig::ConflictResolution;
+use crate::metadata::Provenance;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::ViewState,
+    AppView, KtxEvent,
+};
+
+use super::utils::{action_style, centered_rect, key_style, styled_button};
+
+const RESOLUTIONS: [ConflictResolution; 3] = [
+    ConflictResolution::Overwrite,
+    ConflictResolution::Skip,
+    ConflictResolution::RenameWithSuffix,
+];
+
+pub struct ImportConflictDialogView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    content: String,
+    incoming: Kubeconfig,
+    provenance: Option<Provenance>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+#[derive(Clone)]
+pub struct ImportConflictDialogViewState {
+    pub selection: Option<usize>,
+}
+
+impl ImportConflictDialogView {
+    pub fn new(
+        event_bus_tx: mpsc::Sender<KtxEvent>,
+        preview: String,
+        incoming: Kubeconfig,
+        provenance: Option<Provenance>,
+    ) -> Self {
+        let content = format!(
+            "The import would make the following changes:\n\n{}\n\nOverwrite the changed (~) entries, skip them or import them under a new name?",
+            preview
+        );
+        Self {
+            event_bus_tx,
+            content,
+            incoming,
+            provenance,
+            state: Arc::new(Mutex::new(ViewState::ImportConflictDialogView(
+                ImportConflictDialogViewState { selection: None },
+            ))),
+        }
+    }
+
+    async fn resolve(&self, resolution: ConflictResolution) {
+        let _ = self
+            .event_bus_tx
+            .send(KtxEvent::ResolveImportConflict((
+                self.incoming.clone(),
+                self.provenance.clone(),
+                resolution,
+            )))
+            .await;
+        let _ = self.event_bus_tx.send(KtxEvent::DialogConfirm).await;
+    }
+}
+
+#[async_trait]
+impl<B> AppView<B> for ImportConflictDialogView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("o"),
+            action_style(" - overwrite, "),
+            key_style("s"),
+            action_style(" - skip, "),
+            key_style("r"),
+            action_style(" - rename, "),
+            key_style("Esc"),
+            action_style(" - cancel import"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let state = ImportConflictDialogViewState::from_view_state(view_state);
+        let dialog = centered_rect(
+            area,
+            ((area.width as f32 * 0.5) as u16).max(56),
+            ((area.height as f32 * 0.5) as u16).max(10),
+        );
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(dialog);
+
+        let spacer = || Span::styled("          ", Style::default());
+        let buttons = Paragraph::new(Line::from(vec![
+            styled_button("Overwrite", state.selection == Some(0)),
+            spacer(),
+            styled_button("Skip", state.selection == Some(1)),
+            spacer(),
+            styled_button("Rename", state.selection == Some(2)),
+        ]))
+        .block(Block::default().borders(Borders::ALL))
+        .alignment(tui::layout::Alignment::Center);
+
+        let content = Paragraph::new(self.content.as_str())
+            .block(
+                Block::default()
+                    .title("Import conflict")
+                    .borders(Borders::ALL)
+                    .padding(Padding::new(1, 1, 1, 1)),
+            )
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(Clear, dialog);
+        f.render_widget(content, layout[0]);
+        f.render_widget(buttons, layout[1]);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, _state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = ImportConflictDialogViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => match evt {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('o'),
+                    ..
+                }) => {
+                    self.resolve(ConflictResolution::Overwrite).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    ..
+                }) => {
+                    self.resolve(ConflictResolution::Skip).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) => {
+                    self.resolve(ConflictResolution::RenameWithSuffix).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                }) => {
+                    let _ = self.event_bus_tx.send(KtxEvent::DialogReject).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Left | KeyCode::Char('h'),
+                    ..
+                }) => {
+                    view_state.selection = match view_state.selection {
+                        Some(i) if i > 0 => Some(i - 1),
+                        _ => Some(RESOLUTIONS.len() - 1),
+                    };
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right | KeyCode::Char('l'),
+                    ..
+                }) => {
+                    view_state.selection = match view_state.selection {
+                        Some(i) if i + 1 < RESOLUTIONS.len() => Some(i + 1),
+                        _ => Some(0),
+                    };
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                }) if view_state.selection.is_some() => {
+                    self.resolve(RESOLUTIONS[view_state.selection.unwrap()])
+                        .await;
+                }
+                _ => {
+                    return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                }
+            },
+            _ => {
+                return Ok(Some(event));
+            }
+        };
+        Ok(None)
+    }
+}
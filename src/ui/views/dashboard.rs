@@ -0,0 +1,264 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::cluster;
+use crate::config::KtxConfig;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, KubeContextStatus, ViewState},
+    AppView, HealthSample,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, key_style, pad_to_width, styled_list, truncate_to_width,
+};
+
+const NAME_WIDTH: usize = 40;
+const SPARKLINE_WIDTH: usize = 30;
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Used unless health_poll_interval is configured
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+pub fn poll_interval(config: &KtxConfig) -> Duration {
+    match config.health_poll_interval {
+        0 => DEFAULT_POLL_INTERVAL,
+        secs => Duration::from_secs(secs),
+    }
+}
+
+#[derive(Clone)]
+pub struct HealthDashboardViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+}
+
+pub struct HealthDashboardView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl HealthDashboardView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, total: usize, interval: Duration) -> Self {
+        let mut list_state = ListState::default();
+        if total > 0 {
+            list_state.select(Some(0));
+        }
+        let state = Arc::new(Mutex::new(ViewState::HealthDashboardView(
+            HealthDashboardViewState {
+                list_state,
+                remembered_g: false,
+                pending_count: 0,
+            },
+        )));
+        spawn_poller(event_bus_tx.clone(), Arc::downgrade(&state), interval);
+        Self {
+            event_bus_tx,
+            state,
+        }
+    }
+}
+
+// Keeps polling for as long as the view is alive, popping it drops the state and ends the loop.
+// Spawned outside the task pool, where it would hold a worker while the dashboard is open.
+fn spawn_poller(
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    state: Weak<Mutex<ViewState>>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            if state.upgrade().is_none() {
+                break;
+            }
+            if event_bus_tx.send(KtxEvent::PollConnections).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn sparkline(history: &[HealthSample]) -> Vec<Span<'static>> {
+    let window = &history[history.len().saturating_sub(SPARKLINE_WIDTH)..];
+    let slowest = window
+        .iter()
+        .filter_map(|sample| sample.latency)
+        .max()
+        .unwrap_or_default();
+    let mut spans: Vec<Span> = window
+        .iter()
+        .map(|sample| match (&sample.status, sample.latency) {
+            (KubeContextStatus::Healthy(_), Some(latency)) if !slowest.is_zero() => {
+                let level = (latency.as_secs_f64() / slowest.as_secs_f64()
+                    * (SPARKLINE_BARS.len() - 1) as f64)
+                    .round() as usize;
+                Span::styled(
+                    SPARKLINE_BARS[level].to_string(),
+                    Style::default().fg(Color::Green),
+                )
+            }
+            (KubeContextStatus::Healthy(_), _) => {
+                Span::styled("▁", Style::default().fg(Color::Green))
+            }
+            (KubeContextStatus::Unhealthy, _) => Span::styled("×", Style::default().fg(Color::Red)),
+            _ => Span::styled("·", Style::default().fg(Color::DarkGray)),
+        })
+        .collect();
+    if window.len() < SPARKLINE_WIDTH {
+        spans.push(Span::raw(" ".repeat(SPARKLINE_WIDTH - window.len())));
+    }
+    spans
+}
+
+fn render_row(name: &str, history: &[HealthSample]) -> ListItem<'static> {
+    let mut spans = vec![Span::raw(pad_to_width(
+        &truncate_to_width(name, NAME_WIDTH - 1),
+        NAME_WIDTH,
+    ))];
+    spans.extend(sparkline(history));
+    let checks = history
+        .iter()
+        .filter(|sample| !matches!(sample.status, KubeContextStatus::Unknown))
+        .count();
+    if checks == 0 {
+        spans.push(Span::styled(
+            "  waiting for the first check",
+            Style::default().fg(Color::DarkGray),
+        ));
+        return ListItem::new(Line::from(spans));
+    }
+    let healthy = history
+        .iter()
+        .filter(|sample| matches!(sample.status, KubeContextStatus::Healthy(_)))
+        .count();
+    spans.push(Span::raw(format!("  {:>3}% up", healthy * 100 / checks)));
+    match history.last() {
+        Some(HealthSample {
+            status: KubeContextStatus::Healthy(_),
+            latency: Some(latency),
+            ..
+        }) => spans.push(Span::raw(format!(
+            "  {:>9}",
+            cluster::format_millis(*latency)
+        ))),
+        Some(HealthSample {
+            status: KubeContextStatus::Unhealthy,
+            ..
+        }) => spans.push(Span::styled(
+            "  unreachable",
+            Style::default().fg(Color::Red),
+        )),
+        _ => {}
+    }
+    ListItem::new(Line::from(spans))
+}
+
+#[async_trait]
+impl<B> AppView<B> for HealthDashboardView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("t"),
+            action_style(" - check now, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, state: &AppState, view_state: &mut ViewState) {
+        let view_state = HealthDashboardViewState::from_view_state(view_state);
+        let items: Vec<ListItem> = state
+            .kubeconfig
+            .contexts
+            .iter()
+            .map(|context| {
+                let history = state
+                    .health_history
+                    .get(&context.name)
+                    .map(|history| history.as_slice())
+                    .unwrap_or_default();
+                render_row(&context.name, history)
+            })
+            .collect();
+        let total = items.len();
+        let list = styled_list(
+            format!(
+                "Health, checked every {}s, bars show latency relative to the slowest check",
+                poll_interval(&state.config).as_secs()
+            )
+            .as_str(),
+            items,
+        );
+        f.render_stateful_widget(list, area, &mut view_state.list_state);
+        draw_list_position(f, area, &view_state.list_state, total);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = HealthDashboardViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('t'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PollConnections).await;
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            _ => {
+                let total = state.kubeconfig.contexts.len();
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    total,
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
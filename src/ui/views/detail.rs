@@ -0,0 +1,396 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::cluster::{self, FailureKind};
+use crate::kubeconfig::{self, EntryKind};
+use crate::metadata;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KubeContextStatus, ViewState},
+    AppView, HealthSample, KtxEvent,
+};
+
+use super::utils::{action_style, format_age, format_duration, key_style};
+
+const DETAIL_HISTORY_LENGTH: usize = 10;
+
+pub struct ContextDetailView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    context_name: String,
+    state: Arc<Mutex<ViewState>>,
+}
+
+#[derive(Clone)]
+pub struct ContextDetailViewState {
+    pub scroll: u16,
+}
+
+fn detail_line(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!("{:<26}", label),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(value),
+    ])
+}
+
+impl ContextDetailView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, context_name: String) -> Self {
+        Self {
+            event_bus_tx,
+            context_name,
+            state: Arc::new(Mutex::new(ViewState::ContextDetailView(
+                ContextDetailViewState { scroll: 0 },
+            ))),
+        }
+    }
+
+    async fn send_event(&self, event: KtxEvent) {
+        let _ = self.event_bus_tx.send(event).await;
+    }
+}
+
+// Shared with the details pane of the context list
+pub fn render_details(state: &AppState, context_name: &str) -> Vec<Line<'static>> {
+    let kubeconfig = &state.kubeconfig;
+    let context = kubeconfig::find_context(kubeconfig, context_name)
+        .and_then(|c| c.context.clone())
+        .unwrap_or_default();
+    let cluster = kubeconfig::context_cluster(kubeconfig, context_name)
+        .and_then(|c| c.cluster.clone())
+        .unwrap_or_default();
+    let certificate_authority = if let Some(path) = &cluster.certificate_authority {
+        path.clone()
+    } else if cluster.certificate_authority_data.is_some() {
+        "embedded".to_string()
+    } else {
+        "none".to_string()
+    };
+    let status = match state.connectivity_status.get(context_name) {
+        Some(KubeContextStatus::Healthy(v)) => format!("Healthy ({})", v),
+        Some(KubeContextStatus::Unhealthy) => match state.health_failures.get(context_name) {
+            Some(error) => format!("Unhealthy ({})", FailureKind::classify(error).label()),
+            None => "Unhealthy".to_string(),
+        },
+        Some(KubeContextStatus::Waiting(secs)) => format!("Waiting to become ready ({}s)", secs),
+        _ => "Unknown".to_string(),
+    };
+    let notes = state
+        .metadata
+        .contexts
+        .get(context_name)
+        .and_then(|m| m.notes.clone())
+        .unwrap_or_else(|| "-".to_string());
+    let expires = match state.metadata.expires_at(context_name) {
+        Some(at) if state.metadata.is_expired(context_name) => {
+            format!("expired {} ago", format_age(at))
+        }
+        Some(at) => format!("in {}", format_duration(at - chrono::Utc::now())),
+        None => "never".to_string(),
+    };
+    let tags = state.metadata.tags(context_name);
+    let tags = if tags.is_empty() {
+        "-".to_string()
+    } else {
+        metadata::format_tags(&tags)
+    };
+    let mut lines = vec![
+        detail_line("Context", context_name.to_string()),
+        detail_line("Cluster", context.cluster),
+        detail_line("User", context.user),
+        detail_line(
+            "Namespace",
+            context.namespace.unwrap_or_else(|| "default".to_string()),
+        ),
+        detail_line("Status", status),
+        health_history_line(state, context_name),
+    ];
+    if let (Some(KubeContextStatus::Unhealthy), Some(failure)) = (
+        state.connectivity_status.get(context_name),
+        state.health_failures.get(context_name),
+    ) {
+        let (error, dns) = cluster::split_dns_details(failure);
+        lines.push(detail_line("Error", error.to_string()));
+        if let Some(dns) = dns {
+            lines.push(detail_line("DNS", dns.to_string()));
+        }
+    }
+    lines.extend([
+        detail_line("Notes", notes),
+        detail_line("Tags", tags),
+        detail_line("Expires", expires),
+        Line::from(""),
+    ]);
+    if let Some(identity) = state.aws_identity(context_name) {
+        lines.push(detail_line(
+            "AWS account",
+            identity.account.unwrap_or_else(|| "-".to_string()),
+        ));
+        lines.push(detail_line(
+            "AWS profile",
+            identity.profile.unwrap_or_else(|| "-".to_string()),
+        ));
+    }
+    if state.kubeconfig_sources.is_multi_file() {
+        let file = state.kubeconfig_sources.context_file(context_name);
+        lines.push(detail_line(
+            "File",
+            if state.kubeconfig_sources.is_read_only(context_name) {
+                format!("{} (shared, read-only)", file)
+            } else {
+                file.to_string()
+            },
+        ));
+    }
+    lines.extend([
+        detail_line("Server", cluster.server.unwrap_or_default()),
+        detail_line("Certificate authority", certificate_authority),
+        detail_line(
+            "Skip TLS verification",
+            if cluster.insecure_skip_tls_verify.unwrap_or(false) {
+                "yes".to_string()
+            } else {
+                "no".to_string()
+            },
+        ),
+        cert_expiry_line(state, context_name),
+        Line::from(""),
+    ]);
+    match state.cluster_info.get(context_name) {
+        Some(info) => {
+            lines.push(detail_line("Platform", info.platform.clone()));
+            lines.push(detail_line("Nodes", info.node_count.to_string()));
+            lines.push(detail_line("Node versions", info.node_versions.join(", ")));
+            lines.push(detail_line("Namespaces", info.namespace_count.to_string()));
+        }
+        None => {
+            lines.push(Line::from(Span::styled(
+                "Press r to fetch cluster info",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+    }
+    lines
+}
+
+fn cert_expiry_line(state: &AppState, context_name: &str) -> Line<'static> {
+    let mut line = detail_line("Server certificate", "-".to_string());
+    if let Some(expiry) = state.cert_expiry.get(context_name) {
+        let color = if cluster::cert_expires_soon(expiry) {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+        line.spans[1] = Span::styled(
+            format!(
+                "{} ({})",
+                cluster::format_cert_expiry(expiry),
+                expiry.format("%Y-%m-%d")
+            ),
+            Style::default().fg(color),
+        );
+    }
+    line
+}
+
+fn health_history_line(state: &AppState, context_name: &str) -> Line<'static> {
+    let history = state
+        .health_history
+        .get(context_name)
+        .map(|h| h.as_slice())
+        .unwrap_or_default();
+    let mut spans = vec![Span::styled(
+        format!("{:<26}", "Health history"),
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    if history.is_empty() {
+        spans.push(Span::raw("-"));
+    }
+    // The dashboard shows the whole session, a glance at the latest checks is enough here
+    for sample in &history[history.len().saturating_sub(DETAIL_HISTORY_LENGTH)..] {
+        spans.push(match &sample.status {
+            KubeContextStatus::Healthy(_) => Span::styled("● ", Style::default().fg(Color::Green)),
+            KubeContextStatus::Unhealthy => Span::styled("● ", Style::default().fg(Color::Red)),
+            KubeContextStatus::Unknown | KubeContextStatus::Waiting(_) => {
+                Span::styled("● ", Style::default().fg(Color::DarkGray))
+            }
+        });
+    }
+    if let Some(HealthSample { checked_at, .. }) = history.last() {
+        spans.push(Span::styled(
+            format!(
+                "last checked {}",
+                checked_at.with_timezone(&chrono::Local).format("%H:%M:%S")
+            ),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
+#[async_trait]
+impl<B> AppView<B> for ContextDetailView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("r"),
+            action_style(" - cluster info, "),
+            key_style("e"),
+            action_style(" - edit server, "),
+            key_style("x"),
+            action_style(" - toggle TLS verification, "),
+            key_style("c"),
+            action_style(" - set CA file, "),
+            key_style("C"),
+            action_style(" - pick cluster, "),
+            key_style("U"),
+            action_style(" - pick user, "),
+            key_style("n"),
+            action_style(" - notes, "),
+            key_style("t"),
+            action_style(" - TTL, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, state: &AppState, view_state: &mut ViewState) {
+        let view_state = ContextDetailViewState::from_view_state(view_state);
+        let details = Paragraph::new(render_details(state, &self.context_name))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(Span::styled(
+                        self.context_name.as_str(),
+                        Style::default().fg(Color::LightBlue),
+                    ))
+                    .padding(Padding::new(1, 1, 1, 1)),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((view_state.scroll, 0));
+        f.render_widget(details, area);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, _state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = ContextDetailViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => match evt {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Down | KeyCode::Char('j'),
+                    ..
+                }) => {
+                    view_state.scroll = view_state.scroll.saturating_add(1);
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Up | KeyCode::Char('k'),
+                    ..
+                }) => {
+                    view_state.scroll = view_state.scroll.saturating_sub(1);
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc | KeyCode::Char('q'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::PopView).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::FetchClusterInfo(self.context_name.clone()))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('e'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::EditServerUrl(self.context_name.clone()))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('x'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::ToggleInsecureSkipTlsVerify(
+                        self.context_name.clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('n'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::EditNotes(self.context_name.clone()))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('t'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::EditTtl(self.context_name.clone()))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::EditCertificateAuthority(
+                        self.context_name.clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('C'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::PickEntry((
+                        self.context_name.clone(),
+                        EntryKind::Cluster,
+                    )))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('U'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::PickEntry((
+                        self.context_name.clone(),
+                        EntryKind::User,
+                    )))
+                    .await;
+                }
+                _ => {
+                    return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                }
+            },
+            // Nothing left to show, the reload is handled by the app too
+            KtxEvent::ContextRemoved(ref name) if name == &self.context_name => {
+                let _ = self.event_bus_tx.try_send(KtxEvent::PopView);
+                return Ok(Some(event));
+            }
+            _ => {
+                return Ok(Some(event));
+            }
+        };
+        Ok(None)
+    }
+}
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use kube::config::{AuthInfo, Kubeconfig};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::kubeconfig::{self, EntryKind};
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, key_style, pad_to_width, styled_list, truncate_to_width,
+};
+
+const NAME_WIDTH: usize = 40;
+const DETAIL_WIDTH: usize = 50;
+
+#[derive(Clone)]
+pub struct EntriesViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+    pub kind: EntryKind,
+}
+
+// Lists the clusters or users of the kubeconfig with the contexts using them
+pub struct EntriesView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl EntriesView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, kind: EntryKind) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+        Self {
+            event_bus_tx,
+            state: Arc::new(Mutex::new(ViewState::EntriesView(EntriesViewState {
+                list_state,
+                remembered_g: false,
+                pending_count: 0,
+                kind,
+            }))),
+        }
+    }
+}
+
+fn auth_method(auth_info: &AuthInfo) -> String {
+    if let Some(exec) = &auth_info.exec {
+        format!("exec {}", exec.command.as_deref().unwrap_or(""))
+    } else if let Some(provider) = &auth_info.auth_provider {
+        format!("auth provider {}", provider.name)
+    } else if auth_info.token.is_some() || auth_info.token_file.is_some() {
+        "token".to_string()
+    } else if auth_info.client_certificate.is_some() || auth_info.client_certificate_data.is_some()
+    {
+        "client certificate".to_string()
+    } else if auth_info.username.is_some() {
+        "basic auth".to_string()
+    } else {
+        "no credentials".to_string()
+    }
+}
+
+fn entry_detail(kubeconfig: &Kubeconfig, kind: EntryKind, name: &str) -> String {
+    match kind {
+        EntryKind::Cluster => kubeconfig
+            .clusters
+            .iter()
+            .find(|c| c.name == name)
+            .and_then(|c| c.cluster.as_ref())
+            .and_then(|c| c.server.clone())
+            .unwrap_or_default(),
+        EntryKind::User => kubeconfig
+            .auth_infos
+            .iter()
+            .find(|u| u.name == name)
+            .and_then(|u| u.auth_info.as_ref())
+            .map(auth_method)
+            .unwrap_or_default(),
+    }
+}
+
+fn render_entry(state: &AppState, kind: EntryKind, name: &str) -> ListItem<'static> {
+    let references = kubeconfig::entry_references(&state.kubeconfig, kind, name);
+    let detail = entry_detail(&state.kubeconfig, kind, name);
+    let usage = if references.is_empty() {
+        Span::styled("unused", Style::default().fg(Color::Yellow))
+    } else {
+        Span::raw(format!("used by {}", references.join(", ")))
+    };
+    ListItem::new(Line::from(vec![
+        Span::raw(pad_to_width(
+            &truncate_to_width(name, NAME_WIDTH - 1),
+            NAME_WIDTH,
+        )),
+        Span::styled(
+            pad_to_width(&truncate_to_width(&detail, DETAIL_WIDTH - 1), DETAIL_WIDTH),
+            Style::default().fg(Color::DarkGray),
+        ),
+        usage,
+    ]))
+}
+
+#[async_trait]
+impl<B> AppView<B> for EntriesView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("Tab"),
+            action_style(" - clusters/users, "),
+            key_style("r"),
+            action_style(" - rename, "),
+            key_style("d"),
+            action_style(" - delete, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, state: &AppState, view_state: &mut ViewState) {
+        let view_state = EntriesViewState::from_view_state(view_state);
+        let kind = view_state.kind;
+        let items: Vec<ListItem> = kubeconfig::entry_names(&state.kubeconfig, kind)
+            .iter()
+            .map(|name| render_entry(state, kind, name))
+            .collect();
+        let total = items.len();
+        let title = match kind {
+            EntryKind::Cluster => "Clusters",
+            EntryKind::User => "Users",
+        };
+        let list = styled_list(title, items);
+        f.render_stateful_widget(list, area, &mut view_state.list_state);
+        draw_list_position(f, area, &view_state.list_state, total);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = EntriesViewState::from_view_state(&mut locked_state);
+        let names = kubeconfig::entry_names(&state.kubeconfig, view_state.kind);
+        let selected = view_state
+            .list_state
+            .selected()
+            .and_then(|i| names.get(i))
+            .cloned();
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Tab | KeyCode::BackTab,
+                            ..
+                        }) => {
+                            view_state.kind = match view_state.kind {
+                                EntryKind::Cluster => EntryKind::User,
+                                EntryKind::User => EntryKind::Cluster,
+                            };
+                            view_state.list_state.select(Some(0));
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('r'),
+                            ..
+                        }) if selected.is_some() => {
+                            let _ = self
+                                .event_bus_tx
+                                .send(KtxEvent::RenameEntry((view_state.kind, selected.unwrap())))
+                                .await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('d'),
+                            ..
+                        }) if selected.is_some() => {
+                            let _ = self
+                                .event_bus_tx
+                                .send(KtxEvent::DeleteEntry((view_state.kind, selected.unwrap())))
+                                .await;
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            _ => {
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    names.len(),
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::history::Snapshot;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, key_style, pad_to_width, styled_list,
+};
+
+// Each snapshot with what restoring it would change, or why that couldn't be worked out
+pub type SnapshotDiff = (Snapshot, Result<Vec<String>, String>);
+
+#[derive(Clone)]
+pub struct HistoryViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+    pub snapshots: Vec<SnapshotDiff>,
+}
+
+pub struct HistoryView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl HistoryView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, snapshots: Vec<SnapshotDiff>) -> Self {
+        let mut list_state = ListState::default();
+        if !snapshots.is_empty() {
+            list_state.select(Some(0));
+        }
+        let state = HistoryViewState {
+            list_state,
+            remembered_g: false,
+            pending_count: 0,
+            snapshots,
+        };
+        Self {
+            event_bus_tx,
+            state: Arc::new(Mutex::new(ViewState::HistoryView(state))),
+        }
+    }
+}
+
+fn render_snapshot(snapshot: &SnapshotDiff) -> ListItem<'static> {
+    let (snapshot, diff) = snapshot;
+    let file = Path::new(&snapshot.original)
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| snapshot.original.clone());
+    let changes = match diff {
+        Ok(changes) if changes.is_empty() => {
+            Span::styled("same as now", Style::default().fg(Color::DarkGray))
+        }
+        Ok(changes) => Span::raw(format!("{} change(s)", changes.len())),
+        Err(_) => Span::styled("unreadable", Style::default().fg(Color::Red)),
+    };
+    ListItem::new(Line::from(vec![
+        Span::raw(format!(
+            "{}  ",
+            snapshot
+                .taken_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        )),
+        Span::raw(pad_to_width(&file, 30)),
+        changes,
+    ]))
+}
+
+fn render_change(change: &str) -> Line<'static> {
+    let color = match change.chars().next() {
+        Some('+') => Color::Green,
+        Some('-') => Color::Red,
+        _ => Color::Yellow,
+    };
+    Line::from(Span::styled(change.to_string(), Style::default().fg(color)))
+}
+
+#[async_trait]
+impl<B> AppView<B> for HistoryView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("u"),
+            action_style(" - roll back, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = HistoryViewState::from_view_state(view_state);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(area);
+        let items: Vec<ListItem> = view_state.snapshots.iter().map(render_snapshot).collect();
+        let total = items.len();
+        let list = styled_list("Kubeconfig snapshots, newest first", items);
+        f.render_stateful_widget(list, layout[0], &mut view_state.list_state);
+        draw_list_position(f, layout[0], &view_state.list_state, total);
+
+        let lines = match view_state
+            .list_state
+            .selected()
+            .and_then(|i| view_state.snapshots.get(i))
+        {
+            Some((_, Ok(changes))) if changes.is_empty() => {
+                vec![Line::from("Identical to the current file")]
+            }
+            Some((_, Ok(changes))) => changes.iter().map(|c| render_change(c)).collect(),
+            Some((_, Err(error))) => vec![Line::from(Span::styled(
+                error.clone(),
+                Style::default().fg(Color::Red),
+            ))],
+            None => vec![Line::from(
+                "No snapshots yet, they are taken whenever ktx changes the kubeconfig",
+            )],
+        };
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Rolling back would"),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, layout[1]);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = HistoryViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('u'),
+                            ..
+                        }) => {
+                            if let Some((snapshot, _)) = view_state
+                                .list_state
+                                .selected()
+                                .and_then(|i| view_state.snapshots.get(i))
+                            {
+                                let _ = self
+                                    .event_bus_tx
+                                    .send(KtxEvent::RestoreSnapshot(snapshot.clone()))
+                                    .await;
+                            }
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            _ => {
+                let total = view_state.snapshots.len();
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    total,
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
@@ -1,42 +1,232 @@
-use std::{error::Error, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use crossterm::event::{Event, KeyCode, KeyEvent};
+use futures::StreamExt;
 use tokio::sync::{mpsc, Mutex};
 use tui::{
     backend::Backend,
     layout::Rect,
-    text::Line,
+    style::{Color, Style},
+    text::{Line, Span},
     widgets::{ListItem, ListState, Paragraph},
     Frame,
 };
 
+use crate::cloud::{self, ClusterDetails, ImportOption, KUBELOGIN_MODES};
+use crate::config::KtxConfig;
+use crate::metadata::{self, Provenance};
+use crate::runner::{CommandRunner, DynCommandRunner};
+use crate::session::{OpenView, ViewMemory};
 use crate::ui::{
     app::{AppState, HandleEventResult},
-    types::{CloudImportPath, EmptyResult, KtxEvent, ViewState},
+    tasks::TaskPool,
+    types::{
+        CloudImportPath, EmptyResult, ImportOutcome, KtxEvent, MessageLevel, UiMessage, ViewState,
+    },
     AppView,
 };
 
 use super::utils::{
-    action_style, handle_list_navigation_event, handle_list_navigation_keyboard_event, key_style,
-    styled_list,
+    action_style, draw_list_position, format_age, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, jump_index, key_style, pad_to_width, styled_list,
+    truncate_to_width,
 };
 
-type ImportOption = (String, String, Option<String>);
+const IMPORT_CONCURRENCY: usize = 8;
+const NAME_WIDTH: usize = 50;
+const REGION_WIDTH: usize = 18;
+const STATUS_WIDTH: usize = 14;
+const VERSION_WIDTH: usize = 20;
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+// Ids of preset options at the root, which can't clash with providers
+const PRESET_PREFIX: &str = "preset:";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportSort {
+    // The order the cloud CLI lists clusters in
+    Listed,
+    Name,
+    Region,
+    Status,
+    Version,
+}
+
+impl ImportSort {
+    fn next(self) -> Self {
+        match self {
+            ImportSort::Listed => ImportSort::Name,
+            ImportSort::Name => ImportSort::Region,
+            ImportSort::Region => ImportSort::Status,
+            ImportSort::Status => ImportSort::Version,
+            ImportSort::Version => ImportSort::Listed,
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        let mut sort = ImportSort::Listed;
+        loop {
+            if sort.label() == label {
+                return Some(sort);
+            }
+            sort = sort.next();
+            if sort == ImportSort::Listed {
+                return None;
+            }
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ImportSort::Listed => "listing order",
+            ImportSort::Name => "name",
+            ImportSort::Region => "region",
+            ImportSort::Status => "status",
+            ImportSort::Version => "version",
+        }
+    }
+}
+
+// Clusters that aren't ready to serve requests stand out, e.g. while they are being created
+fn status_color(status: &str) -> Color {
+    match status.to_lowercase().as_str() {
+        "running" | "active" | "succeeded" => Color::DarkGray,
+        "error" | "failed" | "degraded" => Color::Red,
+        _ => Color::Yellow,
+    }
+}
+
+// Compares versions like "1.27.3-gke.100" by their numeric parts
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
 
+#[derive(Clone)]
 pub struct ImportViewState {
     pub list_state: ListState,
     pub remembered_g: bool,
+    pub pending_count: usize,
     pub options: Vec<ImportOption>,
+    // Keyed by option id
+    pub details: HashMap<String, ClusterDetails>,
+    pub sort: ImportSort,
     pub filter: String,
+    pub imported: HashSet<ImportOption>,
+    pub hide_imported: bool,
+    pub show_unusable: bool,
+    pub skip_existing: bool,
+    // Clusters marked with Space, imported together on Enter
+    pub selected: HashSet<ImportOption>,
+    // Remembered option id to select once the options have loaded
+    pub pending_selection: Option<String>,
+    // Set while the options are listed in the background
+    pub loading: bool,
+    pub load_error: Option<String>,
+    // kubelogin mode AKS imports are converted to, starting with the configured one
+    pub kubelogin_mode: Option<String>,
 }
 
 impl ImportViewState {
     fn get_filtered_options(&self) -> Vec<ImportOption> {
         let mut filtered_options = self.options.clone();
+        filtered_options.retain(|option| {
+            self.matches_filter(option)
+                && !(self.hide_imported && self.imported.contains(option))
+                && (self.show_unusable || !self.is_unusable(option))
+        });
+        let name = |option: &ImportOption| option.1.to_lowercase();
+        let details =
+            |option: &ImportOption| self.details.get(&option.0).cloned().unwrap_or_default();
+        // Clusters without the field go last
+        match self.sort {
+            ImportSort::Listed => {}
+            ImportSort::Name => filtered_options.sort_by_key(name),
+            ImportSort::Region => filtered_options
+                .sort_by_key(|o| (details(o).region.is_none(), details(o).region, name(o))),
+            ImportSort::Status => filtered_options
+                .sort_by_key(|o| (details(o).status.is_none(), details(o).status, name(o))),
+            ImportSort::Version => filtered_options.sort_by_key(|o| {
+                let version = details(o).version;
+                (
+                    version.is_none(),
+                    version.as_deref().map(version_key),
+                    name(o),
+                )
+            }),
+        }
         filtered_options
-            .retain(|(_, name, _)| name.to_lowercase().contains(&self.filter.to_lowercase()));
-        filtered_options
+    }
+
+    // Words of the filter all have to match. key:value looks the value up in the cloud tags, key:
+    // only asks for the tag to be there, anything else is matched against the name and tags.
+    fn matches_filter(&self, option: &ImportOption) -> bool {
+        let tags = self.tags(option);
+        let name = option.1.to_lowercase();
+        let text = metadata::format_tags(&tags).to_lowercase();
+        self.filter
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| match word.split_once(':') {
+                Some((key, value)) if !key.is_empty() => tags
+                    .iter()
+                    .any(|(k, v)| k.to_lowercase() == key && v.to_lowercase().contains(value)),
+                _ => name.contains(word) || text.contains(word),
+            })
+    }
+
+    fn is_unusable(&self, option: &ImportOption) -> bool {
+        self.details
+            .get(&option.0)
+            .map(|details| details.is_unusable())
+            .unwrap_or(false)
+    }
+
+    fn refresh_imported(&mut self, import_path: &CloudImportPath, state: &AppState) {
+        if !import_path.is_listing_clusters() {
+            return;
+        }
+        self.imported = self
+            .options
+            .iter()
+            .filter(|option| {
+                let option_path = import_path.push_clone((*option).clone());
+                // Contexts named by a template only match through their recorded provenance
+                option_path.is_imported(&state.kubeconfig)
+                    || option_path.provenance().is_some_and(|provenance| {
+                        state.kubeconfig.contexts.iter().any(|c| {
+                            state
+                                .metadata
+                                .provenance(&c.name)
+                                .is_some_and(|p| p.same_cluster(&provenance))
+                        })
+                    })
+            })
+            .cloned()
+            .collect();
+    }
+
+    fn select_option(&mut self, id: &str) {
+        if let Some(index) = self
+            .get_filtered_options()
+            .iter()
+            .position(|option| option.0 == id)
+        {
+            self.list_state.select(Some(index));
+        }
+    }
+
+    fn tags(&self, option: &ImportOption) -> BTreeMap<String, String> {
+        self.details
+            .get(&option.0)
+            .map(|details| details.tags.clone())
+            .unwrap_or_default()
     }
 
     fn get_selected_option(&self) -> ImportOption {
@@ -48,421 +238,252 @@ impl ImportViewState {
 
 pub struct ImportView {
     event_bus_tx: mpsc::Sender<KtxEvent>,
+    runner: DynCommandRunner,
+    tasks: Arc<TaskPool>,
     state: Arc<Mutex<ViewState>>,
     import_path: CloudImportPath,
+    // Set when the view was opened through an import preset
+    preset: Option<String>,
 }
 
-async fn exec_to_str(cmd: &str, args: &[&str]) -> Result<String, Box<dyn Error + Send + Sync>> {
-    let output = tokio::process::Command::new(cmd)
-        .args(args)
-        .output()
-        .await?;
-    if output.status.success() == false {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            stderr.to_string(),
-        )));
-    }
-    let output = String::from_utf8_lossy(&output.stdout);
-    Ok(output.to_string())
-}
-
-async fn exec_to_json(
-    cmd: &str,
-    args: &[&str],
-) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
-    let output = exec_to_str(cmd, args).await?;
-    let json: serde_json::Value = serde_json::from_str(&output)?;
-    Ok(json)
-}
-
-async fn import_aws_cluster(import_path: &CloudImportPath) -> EmptyResult {
-    exec_to_str(
-        "aws",
-        &[
-            "--region",
-            import_path.get_aws_region().as_str(),
-            "--profile",
-            import_path.get_aws_profile().as_str(),
-            "eks",
-            "update-kubeconfig",
-            "--name",
-            import_path.get_cluster_id().as_str(),
-        ],
-    )
-    .await?;
-    Ok(())
-}
-
-async fn import_gke_cluster(import_path: &CloudImportPath) -> EmptyResult {
-    exec_to_str(
-        "gcloud",
-        &[
-            "container",
-            "clusters",
-            "get-credentials",
-            import_path.get_cluster_id().as_str(),
-            "--zone",
-            import_path.get_gke_zone().as_str(),
-            "--project",
-            import_path.get_gcp_project().as_str(),
-        ],
-    )
-    .await?;
-    Ok(())
-}
-
-async fn import_aks_cluster(import_path: &CloudImportPath) -> EmptyResult {
-    exec_to_str(
-        "az",
-        &[
-            "aks",
-            "get-credentials",
-            "--resource-group",
-            import_path.get_azure_resource_group().as_str(),
-            "--name",
-            import_path.get_cluster_id().as_str(),
-            "--subscription",
-            import_path.get_azure_subscription().as_str(),
-            "--overwrite-existing",
-        ],
-    )
-    .await?;
-    Ok(())
-}
-
+// Tags are recorded with the provenance, e.g. for telling preview environments apart, and so is
+// the preset so that refreshes use its role again
 async fn import_cluster(
+    runner: &dyn CommandRunner,
     import_path: &CloudImportPath,
+    tags: BTreeMap<String, String>,
+    preset: Option<String>,
+    config: &KtxConfig,
+    batch: bool,
     event_bus_tx: mpsc::Sender<KtxEvent>,
-    config_lock: Arc<Mutex<()>>,
 ) -> EmptyResult {
-    let _config_guard = config_lock.lock().await;
-    if import_path.is_aws() {
-        import_aws_cluster(import_path).await?;
-    } else if import_path.is_gcp() {
-        import_gke_cluster(import_path).await?;
-    } else if import_path.is_azure() {
-        import_aks_cluster(import_path).await?;
-    }
+    let imported = cloud::import_cluster(runner, import_path, config).await?;
+    let provenance = import_path.provenance().map(|provenance| Provenance {
+        tags,
+        preset,
+        ..provenance
+    });
     let _ = event_bus_tx
-        .send(KtxEvent::PushSuccessMessage(format!(
-            "Successfully imported {}",
-            import_path.get_cluster_id()
-        )))
+        .send(KtxEvent::MergeImportedConfig((imported, provenance, batch)))
         .await;
-    // This is to ensure all buffers have been flushed and there're no conflicts between
-    // simultaneous import operations.
-    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     Ok(())
 }
 
 impl ImportView {
-    pub fn new<B: Backend>(
+    pub fn new(
         event_bus_tx: mpsc::Sender<KtxEvent>,
+        runner: DynCommandRunner,
+        tasks: Arc<TaskPool>,
         import_path: CloudImportPath,
+        preset: Option<String>,
     ) -> Self {
         let state = ImportViewState {
             list_state: ListState::default(),
             remembered_g: false,
+            pending_count: 0,
             options: vec![],
+            details: HashMap::new(),
+            sort: ImportSort::Listed,
             filter: "".to_string(),
+            imported: HashSet::new(),
+            hide_imported: false,
+            show_unusable: false,
+            skip_existing: true,
+            selected: HashSet::new(),
+            pending_selection: None,
+            loading: false,
+            load_error: None,
+            kubelogin_mode: None,
         };
         Self {
             event_bus_tx,
+            runner,
+            tasks,
             import_path,
+            preset,
             state: Arc::new(Mutex::new(ViewState::ImportView(state))),
         }
     }
 
-    async fn is_gcp_configured(&self) -> bool {
-        match exec_to_json("gcloud", &["--format", "json", "info"]).await {
-            Err(_) => return false,
-            Ok(info) => {
-                let account = info["config"]["account"].as_str().unwrap_or("");
-                return !account.is_empty();
-            }
-        }
-    }
-
-    async fn is_aws_configured(&self) -> bool {
-        match exec_to_str("aws", &["configure", "list-profiles"]).await {
-            Err(_) => return false,
-            Ok(output) => {
-                let profiles = output.split("\n").collect::<Vec<&str>>();
-                return !profiles.is_empty();
-            }
-        };
-    }
-
-    async fn is_azure_configured(&self) -> bool {
-        match exec_to_json("az", &["account", "show", "--output", "json"]).await {
-            Err(_) => return false,
-            Ok(account) => {
-                let user = account["user"]["name"].as_str().unwrap_or("");
-                return !user.is_empty();
-            }
-        };
+    // What imports from here run with, the preset and the kubelogin mode picked with L
+    fn import_config(&self, view_state: &ImportViewState, config: &KtxConfig) -> KtxConfig {
+        let mut config = config.for_preset(self.preset.as_deref());
+        config.azure.kubelogin_mode = view_state.kubelogin_mode.clone();
+        config
     }
 
-    async fn load_cloud_options(&self, state: &mut ImportViewState) -> EmptyResult {
-        let (gcp_configured, aws_configured, azure_configured) = tokio::join!(
-            self.is_gcp_configured(),
-            self.is_aws_configured(),
-            self.is_azure_configured()
-        );
-        if aws_configured {
-            state
-                .options
-                .push(("aws".to_string(), "AWS".to_string(), None));
-        }
-        if gcp_configured {
-            state
-                .options
-                .push(("gcp".to_string(), "GCP".to_string(), None));
+    // Options are listed in the background and arrive as ImportOptionsLoaded. Provider detection
+    // runs once per session, its result is cached in the app state.
+    pub async fn load_options(&self, app_state: &AppState) -> EmptyResult {
+        let mut state_lock = self.state.lock().await;
+        let state = ImportViewState::from_view_state(&mut state_lock);
+        if self.import_path.is_full() {
+            return Ok(());
         }
-        if azure_configured {
-            state
-                .options
-                .push(("azure".to_string(), "Azure".to_string(), None));
-        };
-        Ok(())
-    }
-
-    async fn load_gcp_projects(&self, state: &mut ImportViewState) -> EmptyResult {
-        let projects = exec_to_json("gcloud", &["--format", "json", "projects", "list"]).await?;
-        for project in projects.as_array().unwrap() {
-            let project_id = project["projectId"].as_str().unwrap_or("");
-            let project_name = project["name"].as_str().unwrap_or("");
-            let lifecycle_state = project["lifecycleState"].as_str().unwrap_or("");
-            if !project_id.is_empty()
-                && !project_id.starts_with("sys-")
-                && !project_name.is_empty()
-                && lifecycle_state == "ACTIVE"
-            {
-                state.options.push((
-                    project_id.to_string(),
-                    format!("{} ({})", project_name.to_string(), project_id.to_string()),
-                    None,
-                ));
+        if self.import_path.is_empty() {
+            match &app_state.cloud_providers {
+                Some(providers) => self.set_providers(state, providers.clone(), &app_state.config),
+                None => self.detect_providers(state).await,
             }
+            return Ok(());
         }
-        Ok(())
-    }
-
-    async fn load_gke_clusters(&self, state: &mut ImportViewState, project: &str) -> EmptyResult {
-        let clusters = exec_to_json(
-            "gcloud",
-            &[
-                "--format",
-                "json",
-                "container",
-                "clusters",
-                "list",
-                "--project",
-                project,
-            ],
-        )
-        .await?;
-        for cluster in clusters.as_array().unwrap() {
-            let cluster_name = cluster["name"].as_str().unwrap_or("");
-            let zone = cluster["zone"].as_str().unwrap_or("");
-            state.options.push((
-                cluster_name.to_string(),
-                cluster_name.to_string(),
-                Some(zone.to_string()),
-            ));
+        state.kubelogin_mode = app_state.config.azure.kubelogin_mode.clone();
+        let ttl = chrono::Duration::seconds(app_state.config.import_cache_ttl as i64);
+        let cached = app_state
+            .listing_cache
+            .get(&(self.preset.clone(), self.import_path.key()))
+            .filter(|(listed_at, _)| chrono::Utc::now() - *listed_at < ttl);
+        if let Some((_, options)) = cached {
+            self.set_options(state, options.clone(), &app_state.config);
+            return Ok(());
         }
-        Ok(())
+        self.list_options(state, app_state).await
     }
 
-    async fn load_aws_profiles(&self, state: &mut ImportViewState) -> EmptyResult {
-        let output = exec_to_str("aws", &["configure", "list-profiles"]).await?;
-        let profiles = output.split("\n").collect::<Vec<&str>>();
-        for profile in profiles {
-            if !profile.is_empty() {
-                state
-                    .options
-                    .push((profile.to_string(), profile.to_string(), None));
-            }
-        }
+    async fn list_options(&self, state: &mut ImportViewState, app_state: &AppState) -> EmptyResult {
+        state.loading = true;
+        state.load_error = None;
+        state.options.clear();
+        state.list_state.select(None);
+        let import_path = self.import_path.clone();
+        let runner = self.runner.clone();
+        let config = app_state.config.for_preset(self.preset.as_deref());
+        let preset = self.preset.clone();
+        let event_bus = self.event_bus_tx.clone();
+        let label = format!("Listing {}", self.import_path.display_names().join(" / "));
+        self.tasks
+            .submit(label, async move {
+                let options =
+                    cloud::list_options_with_details(runner.as_ref(), &import_path, &config)
+                        .await
+                        .map_err(|e| e.to_string());
+                let _ = event_bus
+                    .send(KtxEvent::ImportOptionsLoaded((
+                        import_path,
+                        preset,
+                        options,
+                    )))
+                    .await;
+            })
+            .await?;
         Ok(())
     }
 
-    async fn load_aws_regions(&self, state: &mut ImportViewState, profile: &str) -> EmptyResult {
-        let regions = exec_to_json(
-            "aws",
-            &[
-                "--profile",
-                profile,
-                "--output",
-                "json",
-                "ec2",
-                "describe-regions",
-            ],
-        )
-        .await?;
-        for region in regions["Regions"].as_array().unwrap() {
-            let region_name = region["RegionName"].as_str().unwrap_or("");
-            state
-                .options
-                .push((region_name.to_string(), region_name.to_string(), None));
-        }
-        Ok(())
+    async fn detect_providers(&self, state: &mut ImportViewState) {
+        state.loading = true;
+        state.load_error = None;
+        state.options.clear();
+        state.list_state.select(None);
+        let _ = self.event_bus_tx.send(KtxEvent::DetectCloudProviders).await;
     }
 
-    async fn load_eks_clusters(
+    fn set_providers(
         &self,
         state: &mut ImportViewState,
-        profile: &str,
-        region: &str,
-    ) -> EmptyResult {
-        let clusters = exec_to_json(
-            "aws",
-            &[
-                "--profile",
-                profile,
-                "--output",
-                "json",
-                "eks",
-                "list-clusters",
-                "--region",
-                region,
-            ],
-        )
-        .await?;
-        for cluster in clusters["clusters"].as_array().unwrap() {
-            let cluster_name = cluster.as_str().unwrap_or("");
-            state
-                .options
-                .push((cluster_name.to_string(), cluster_name.to_string(), None));
-        }
-        Ok(())
+        providers: Vec<ImportOption>,
+        config: &KtxConfig,
+    ) {
+        let options = providers
+            .into_iter()
+            .map(|option| (option, ClusterDetails::default()))
+            .collect();
+        self.set_options(state, options, config);
     }
 
-    async fn load_aks_clusters(
+    fn set_options(
         &self,
         state: &mut ImportViewState,
-        subscription: &str,
-    ) -> EmptyResult {
-        let clusters = exec_to_json(
-            "az",
-            &[
-                "aks",
-                "list",
-                "--subscription",
-                subscription,
-                "--output",
-                "json",
-            ],
-        )
-        .await?;
-        for cluster in clusters.as_array().unwrap() {
-            let cluster_name = cluster["name"].as_str().unwrap_or("");
-            let resource_group = cluster["resourceGroup"].as_str().unwrap_or("");
+        options: Vec<(ImportOption, ClusterDetails)>,
+        config: &KtxConfig,
+    ) {
+        let import_dir = config.import_dir.as_str();
+        state.loading = false;
+        state.load_error = None;
+        state.details = options
+            .iter()
+            .map(|(option, details)| (option.0.clone(), details.clone()))
+            .collect();
+        state.options = options.into_iter().map(|(option, _)| option).collect();
+        if self.import_path.is_empty() && Path::new(import_dir).is_dir() {
             state.options.push((
-                cluster_name.to_string(),
-                format!(
-                    "{} (RG: {})",
-                    cluster_name.to_string(),
-                    resource_group.to_string()
-                ),
-                Some(resource_group.to_string()),
+                "dir".to_string(),
+                format!("Kubeconfig directory {}", import_dir),
+                Some(import_dir.to_string()),
             ));
         }
-        Ok(())
-    }
-
-    async fn load_azure_subscriptions(&self, state: &mut ImportViewState) -> EmptyResult {
-        let subscriptions = exec_to_json("az", &["account", "list", "--output", "json"]).await?;
-        for subscription in subscriptions.as_array().unwrap() {
-            let subscription_id = subscription["id"].as_str().unwrap_or("");
-            let subscription_name = subscription["name"].as_str().unwrap_or("");
-            if !subscription_id.is_empty() && !subscription_name.is_empty() {
-                state.options.push((
-                    subscription_id.to_string(),
-                    format!(
-                        "{} ({})",
-                        subscription_name.to_string(),
-                        subscription_id.to_string()
-                    ),
-                    None,
-                ));
-            }
-        }
-        Ok(())
-    }
-
-    async fn drilldown_import_path(&self, state: &mut ImportViewState) -> EmptyResult {
-        match (
-            self.import_path.get_platform().as_str(),
-            self.import_path.len(),
-        ) {
-            ("aws", 1) => {
-                self.load_aws_profiles(state).await?;
-            }
-            ("aws", 2) => {
-                self.load_aws_regions(state, self.import_path.get_aws_profile().as_str())
-                    .await?;
-            }
-            ("aws", 3) => {
-                self.load_eks_clusters(
-                    state,
-                    self.import_path.get_aws_profile().as_str(),
-                    self.import_path.get_aws_region().as_str(),
-                )
-                .await?;
-            }
-            ("gcp", 1) => {
-                self.load_gcp_projects(state).await?;
-            }
-            ("gcp", 2) => {
-                self.load_gke_clusters(state, self.import_path.get_gcp_project().as_str())
-                    .await?;
-            }
-            ("azure", 1) => {
-                self.load_azure_subscriptions(state).await?;
-            }
-            ("azure", 2) => {
-                self.load_aks_clusters(state, self.import_path.get_azure_subscription().as_str())
-                    .await?;
-            }
-            _ => {}
-        };
-        Ok(())
-    }
-
-    pub async fn load_options(&self) -> EmptyResult {
-        let mut state_lock = self.state.lock().await;
-        let state = ImportViewState::from_view_state(&mut state_lock);
-        if self.import_path.is_full() {
-            return Ok(());
-        }
         if self.import_path.is_empty() {
-            self.load_cloud_options(state).await?;
-        } else {
-            self.drilldown_import_path(state).await?;
+            state
+                .options
+                .extend(config.import_presets.iter().map(|preset| {
+                    (
+                        format!("{}{}", PRESET_PREFIX, preset.name),
+                        format!("Preset {}", preset.name),
+                        None,
+                    )
+                }));
         }
         if !state.options.is_empty() {
             state.list_state.select(Some(0));
         };
-        Ok(())
+        if let Some(id) = state.pending_selection.take() {
+            state.select_option(&id);
+        }
     }
 
     async fn handle_enter(
         &self,
         view_state: &mut ImportViewState,
-        config_lock: Arc<Mutex<()>>,
+        config: &KtxConfig,
     ) -> EmptyResult {
+        if self.import_path.is_listing_clusters() && !view_state.selected.is_empty() {
+            return self.import_selected(view_state, config).await;
+        }
         if !view_state.get_filtered_options().is_empty()
             && view_state.list_state.selected().is_some()
         {
             let selected_option = view_state.get_selected_option();
+            if let Some(name) = selected_option.0.strip_prefix(PRESET_PREFIX) {
+                let _ = self
+                    .event_bus_tx
+                    .send(KtxEvent::ShowImportPreset(name.to_string()))
+                    .await;
+                return Ok(());
+            }
             let import_path = self.import_path.push_clone(selected_option.clone());
             if import_path.is_full() {
-                import_cluster(&import_path, self.event_bus_tx.clone(), config_lock.clone())
+                let _ = self.event_bus_tx.send(KtxEvent::ImportStarted).await;
+                let label = format!("Importing {}", selected_option.1);
+                let cluster = selected_option.1.clone();
+                let tags = view_state.tags(&selected_option);
+                let preset = self.preset.clone();
+                let event_bus = self.event_bus_tx.clone();
+                let runner = self.runner.clone();
+                let config = self.import_config(view_state, config);
+                self.tasks
+                    .submit(label, async move {
+                        let event = match import_cluster(
+                            runner.as_ref(),
+                            &import_path,
+                            tags,
+                            preset,
+                            &config,
+                            false,
+                            event_bus.clone(),
+                        )
+                        .await
+                        {
+                            Ok(()) => KtxEvent::RefreshConfig,
+                            Err(e) => KtxEvent::PushMessage(UiMessage {
+                                context: Some(cluster.clone()),
+                                ..UiMessage::new(
+                                    MessageLevel::Error,
+                                    format!("Unable to import {}: {}", cluster, e),
+                                )
+                            }),
+                        };
+                        let _ = event_bus.send(event).await;
+                    })
                     .await?;
-                let _ = self.event_bus_tx.send(KtxEvent::RefreshConfig).await;
             } else {
                 let _ = self
                     .event_bus_tx
@@ -476,26 +497,99 @@ impl ImportView {
     async fn import_all(
         &self,
         view_state: &mut ImportViewState,
-        config_lock: Arc<Mutex<()>>,
+        config: &KtxConfig,
+    ) -> EmptyResult {
+        let mut selected_options = view_state.get_filtered_options();
+        if view_state.skip_existing {
+            selected_options.retain(|option| !view_state.imported.contains(option));
+        }
+        let skipped: Vec<(ImportOption, ImportOutcome)> = view_state
+            .get_filtered_options()
+            .into_iter()
+            .filter(|option| !selected_options.contains(option))
+            .map(|option| (option, ImportOutcome::Skipped))
+            .collect();
+        self.import_options(view_state, selected_options, skipped, config)
+            .await
+    }
+
+    // Marked clusters are imported even when they exist already, picking them is explicit enough
+    async fn import_selected(
+        &self,
+        view_state: &mut ImportViewState,
+        config: &KtxConfig,
+    ) -> EmptyResult {
+        let selected_options: Vec<ImportOption> = view_state
+            .options
+            .iter()
+            .filter(|option| view_state.selected.contains(option))
+            .cloned()
+            .collect();
+        self.import_options(view_state, selected_options, vec![], config)
+            .await?;
+        view_state.selected.clear();
+        Ok(())
+    }
+
+    async fn import_options(
+        &self,
+        view_state: &ImportViewState,
+        selected_options: Vec<ImportOption>,
+        skipped: Vec<(ImportOption, ImportOutcome)>,
+        config: &KtxConfig,
     ) -> EmptyResult {
-        let selected_options = view_state.get_filtered_options();
         let import_path = self.import_path.clone();
         let event_bus = self.event_bus_tx.clone();
-        tokio::spawn(async move {
-            for option in selected_options {
-                let import_path = import_path.push_clone(option.clone());
-                if let Err(e) =
-                    import_cluster(&import_path, event_bus.clone(), config_lock.clone()).await
-                {
-                    let _ = event_bus
-                        .send(KtxEvent::PushErrorMessage(e.to_string()))
-                        .await;
-                } else {
-                    let _ = event_bus.send(KtxEvent::RefreshConfig).await;
-                };
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-        });
+        let runner = self.runner.clone();
+        let config = self.import_config(view_state, config);
+        let preset = self.preset.clone();
+        let _ = self.event_bus_tx.send(KtxEvent::ImportStarted).await;
+        let label = format!("Importing {} cluster(s)", selected_options.len());
+        let selected_options: Vec<_> = selected_options
+            .into_iter()
+            .map(|option| {
+                let tags = view_state.tags(&option);
+                (option, tags)
+            })
+            .collect();
+        self.tasks
+            .submit(label, async move {
+                // Imports only produce in-memory kubeconfigs which are merged one by one on the
+                // event loop, so the CLIs can safely run side by side.
+                let mut results = futures::stream::iter(selected_options)
+                    .map(|(option, tags)| {
+                        let import_path = import_path.push_clone(option.clone());
+                        let event_bus = event_bus.clone();
+                        let (runner, config, preset) = (runner.clone(), &config, preset.clone());
+                        async move {
+                            match import_cluster(
+                                runner.as_ref(),
+                                &import_path,
+                                tags,
+                                preset,
+                                config,
+                                true,
+                                event_bus.clone(),
+                            )
+                            .await
+                            {
+                                Err(e) => (option, ImportOutcome::Failed(e.to_string())),
+                                Ok(()) => {
+                                    let _ = event_bus.send(KtxEvent::RefreshConfig).await;
+                                    (option, ImportOutcome::Imported)
+                                }
+                            }
+                        }
+                    })
+                    .buffer_unordered(IMPORT_CONCURRENCY)
+                    .collect::<Vec<_>>()
+                    .await;
+                results.extend(skipped);
+                let _ = event_bus
+                    .send(KtxEvent::ShowImportSummary((import_path, results)))
+                    .await;
+            })
+            .await?;
         Ok(())
     }
 
@@ -509,6 +603,7 @@ impl ImportView {
             event,
             self.event_bus_tx.clone(),
             &mut view_state.remembered_g,
+            &mut view_state.pending_count,
         )
         .await?
         {
@@ -523,16 +618,119 @@ impl ImportView {
                     ..
                 }) => {
                     if self.import_path.is_listing_clusters() {
-                        self.import_all(view_state, state.config_lock.clone())
-                            .await?;
+                        self.import_all(view_state, &state.config).await?;
+                    }
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(' '),
+                    ..
+                }) if self.import_path.is_listing_clusters() => {
+                    let filtered_options = view_state.get_filtered_options();
+                    if let Some(index) = view_state.list_state.selected() {
+                        if let Some(option) = filtered_options.get(index) {
+                            if !view_state.selected.remove(option) {
+                                view_state.selected.insert(option.clone());
+                            }
+                            view_state
+                                .list_state
+                                .select(Some((index + 1).min(filtered_options.len() - 1)));
+                        }
+                    }
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('\''),
+                    ..
+                }) => {
+                    let _ = self.event_bus_tx.send(KtxEvent::EnterJumpMode).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('H'),
+                    ..
+                }) if self.import_path.is_listing_clusters() => {
+                    view_state.hide_imported = !view_state.hide_imported;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    ..
+                }) if self.import_path.is_listing_clusters() => {
+                    view_state.skip_existing = !view_state.skip_existing;
+                    let message = if view_state.skip_existing {
+                        "Import all will skip already imported clusters"
+                    } else {
+                        "Import all will reimport existing clusters"
+                    };
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushInfoMessage(message.to_string()))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('F'),
+                    ..
+                }) if self.import_path.is_listing_clusters() => {
+                    view_state.show_unusable = !view_state.show_unusable;
+                }
+                // Cycles through the modes, then back to keeping what az returns
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('L'),
+                    ..
+                }) if self.import_path.is_azure() && self.import_path.is_listing_clusters() => {
+                    let next = match &view_state.kubelogin_mode {
+                        None => Some(0),
+                        Some(mode) => KUBELOGIN_MODES
+                            .iter()
+                            .position(|m| m == mode)
+                            .map(|i| i + 1)
+                            .filter(|i| *i < KUBELOGIN_MODES.len()),
+                    };
+                    view_state.kubelogin_mode = next.map(|i| KUBELOGIN_MODES[i].to_string());
+                    let message = match &view_state.kubelogin_mode {
+                        Some(mode) => {
+                            format!("Imported clusters will use kubelogin's {} login", mode)
+                        }
+                        None => "Imported clusters keep the login az configures".to_string(),
+                    };
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushInfoMessage(message))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('o'),
+                    ..
+                }) if self.import_path.is_listing_clusters() => {
+                    view_state.sort = view_state.sort.next();
+                    view_state.list_state.select(Some(0));
+                    let _ = self
+                        .event_bus_tx
+                        .send(KtxEvent::PushInfoMessage(format!(
+                            "Sorted by {}",
+                            view_state.sort.label()
+                        )))
+                        .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('R'),
+                    ..
+                }) if self.import_path.is_empty() && !view_state.loading => {
+                    self.detect_providers(view_state).await;
+                }
+                // Lists again, also after a failed or empty listing or to bypass the cache
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) if !view_state.loading => {
+                    if self.import_path.is_empty() {
+                        self.detect_providers(view_state).await;
+                    } else {
+                        self.list_options(view_state, state).await?;
                     }
                 }
                 Event::Key(KeyEvent {
                     code: KeyCode::Enter,
                     ..
                 }) => {
-                    self.handle_enter(view_state, state.config_lock.clone())
-                        .await?;
+                    self.handle_enter(view_state, &state.config).await?;
                 }
                 _ => {
                     view_state.remembered_g = false;
@@ -546,19 +744,48 @@ impl ImportView {
     async fn handle_app_event(
         &self,
         event: KtxEvent,
-        _state: &AppState,
+        state: &AppState,
         view_state: &mut ImportViewState,
     ) -> HandleEventResult {
-        let options_len = view_state.get_filtered_options().len();
-        let list_state = &mut view_state.list_state;
-        if let Some(event) = handle_list_navigation_event(event, list_state, options_len).await? {
-            match event {
-                // Handle non-navigation events here
-                _ => Ok(Some(event)),
+        match event {
+            KtxEvent::SetCloudProviders(providers) => {
+                if self.import_path.is_empty() {
+                    self.set_providers(view_state, providers.clone(), &state.config);
+                }
+                // Passed on so the app caches them
+                return Ok(Some(KtxEvent::SetCloudProviders(providers)));
             }
-        } else {
-            Ok(None)
+            KtxEvent::ImportOptionsLoaded((import_path, preset, options))
+                if import_path == self.import_path && preset == self.preset =>
+            {
+                match options {
+                    Ok(options) => {
+                        self.set_options(view_state, options.clone(), &state.config);
+                        // Passed on so the app caches them
+                        return Ok(Some(KtxEvent::ImportOptionsLoaded((
+                            import_path,
+                            preset,
+                            Ok(options),
+                        ))));
+                    }
+                    Err(e) => {
+                        view_state.loading = false;
+                        view_state.load_error = Some(e.clone());
+                        return Ok(Some(KtxEvent::PushErrorMessage(e)));
+                    }
+                }
+            }
+            // Sent by the summary of an import started here, which sits right on top
+            KtxEvent::RetryImports((import_path, options)) if import_path == self.import_path => {
+                self.import_options(view_state, options, vec![], &state.config)
+                    .await?;
+                return Ok(None);
+            }
+            _ => {}
         }
+        let options_len = view_state.get_filtered_options().len();
+        let list_state = &mut view_state.list_state;
+        handle_list_navigation_event(event, list_state, options_len, &state.config).await
     }
 }
 
@@ -573,7 +800,7 @@ where
 
     async fn update_filter(&self, filter: String) {
         let mut state = self.state.lock().await;
-        let mut state = ImportViewState::from_view_state(&mut state);
+        let state = ImportViewState::from_view_state(&mut state);
         state.filter = filter;
     }
 
@@ -583,40 +810,254 @@ where
         state.filter.clone()
     }
 
+    async fn jump_to(&self, text: &str, _state: &AppState) {
+        let mut view_state = self.state.lock().await;
+        let view_state = ImportViewState::from_view_state(&mut view_state);
+        let names: Vec<String> = view_state
+            .get_filtered_options()
+            .into_iter()
+            .map(|option| option.1)
+            .collect();
+        if let Some(index) = jump_index(&names, text) {
+            view_state.list_state.select(Some(index));
+        }
+    }
+
+    fn memory_key(&self) -> Option<String> {
+        Some(format!("import/{}", self.import_path.key()))
+    }
+
+    async fn get_memory(&self, _state: &AppState) -> Option<ViewMemory> {
+        let mut view_state = self.state.lock().await;
+        let view_state = ImportViewState::from_view_state(&mut view_state);
+        let selected = view_state
+            .list_state
+            .selected()
+            .and_then(|i| view_state.get_filtered_options().get(i).cloned())
+            .map(|option| option.0)
+            .or_else(|| view_state.pending_selection.clone());
+        Some(ViewMemory {
+            filter: view_state.filter.clone(),
+            selected,
+            offset: view_state.list_state.offset(),
+            sort: Some(view_state.sort.label().to_string()),
+        })
+    }
+
+    fn open_view(&self) -> Option<OpenView> {
+        Some(OpenView {
+            path: self.import_path.to_vec(),
+            preset: self.preset.clone(),
+        })
+    }
+
+    async fn restore_memory(&self, memory: ViewMemory, _state: &AppState) {
+        let mut view_state = self.state.lock().await;
+        let view_state = ImportViewState::from_view_state(&mut view_state);
+        view_state.filter = memory.filter;
+        if let Some(sort) = memory.sort.as_deref().and_then(ImportSort::from_label) {
+            view_state.sort = sort;
+        }
+        *view_state.list_state.offset_mut() = memory.offset;
+        match memory.selected {
+            // Provider lists may already be there from the cached detection
+            Some(id) if !view_state.loading => view_state.select_option(&id),
+            selected => view_state.pending_selection = selected,
+        }
+    }
+
     fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
         if self.import_path.is_listing_clusters() {
-            Paragraph::new(Line::from(vec![
+            let mut spans = vec![
                 key_style("jk"),
                 action_style(" - up/down, "),
+                key_style("'"),
+                action_style(" - jump, "),
                 key_style("Enter"),
                 action_style(" - import, "),
+                key_style("Space"),
+                action_style(" - select, "),
                 key_style("a"),
                 action_style(" - import all, "),
-            ]))
+                key_style("s"),
+                action_style(" - toggle skip existing, "),
+                key_style("H"),
+                action_style(" - hide/show imported, "),
+                key_style("o"),
+                action_style(" - sort, "),
+                key_style("F"),
+                action_style(" - show/hide deleting and failed, "),
+                key_style("r"),
+                action_style(" - list again"),
+            ];
+            if self.import_path.is_azure() {
+                spans.push(action_style(", "));
+                spans.push(key_style("L"));
+                spans.push(action_style(" - kubelogin mode"));
+            }
+            Paragraph::new(Line::from(spans))
         } else {
-            Paragraph::new(Line::from(vec![
+            let mut spans = vec![
                 key_style("jk"),
                 action_style(" - up/down, "),
+                key_style("'"),
+                action_style(" - jump, "),
                 key_style("Enter"),
                 action_style(" - list, "),
-            ]))
+                key_style("r"),
+                action_style(" - list again"),
+            ];
+            if self.import_path.is_empty() {
+                spans.push(action_style(", "));
+                spans.push(key_style("R"));
+                spans.push(action_style(" - detect providers again"));
+            }
+            Paragraph::new(Line::from(spans))
         }
     }
 
-    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+    fn draw(&self, f: &mut Frame<B>, area: Rect, state: &AppState, view_state: &mut ViewState) {
         let view_state = ImportViewState::from_view_state(view_state);
+        view_state.refresh_imported(&self.import_path, state);
+        let dim = Style::default().fg(Color::DarkGray);
+        let placeholder = if view_state.loading {
+            let frame =
+                SPINNER[(chrono::Utc::now().timestamp_millis() / 500) as usize % SPINNER.len()];
+            let text = if self.import_path.is_empty() {
+                "Detecting cloud providers..."
+            } else {
+                "Loading..."
+            };
+            vec![Span::styled(format!("{} {}", frame, text), dim)]
+        } else if let Some(e) = &view_state.load_error {
+            let width = (area.width as usize).saturating_sub(4);
+            let message = format!(
+                "{} returned an error, press r to retry",
+                self.import_path.cli_name()
+            );
+            vec![
+                Span::styled(message, Style::default().fg(Color::Red)),
+                Span::styled(
+                    truncate_to_width(e.lines().next().unwrap_or_default(), width),
+                    dim,
+                ),
+            ]
+        } else if view_state.options.is_empty() {
+            let message = if self.import_path.is_empty() {
+                "No cloud providers detected, press r to detect them again".to_string()
+            } else {
+                format!(
+                    "No {} found in {}, press r to list them again",
+                    self.import_path.listed_items(),
+                    self.import_path.display_names().join(" / ")
+                )
+            };
+            vec![Span::styled(message, dim)]
+        } else if view_state.get_filtered_options().is_empty() {
+            vec![Span::styled(
+                format!(
+                    "All {} {} are hidden by the filter or toggles",
+                    view_state.options.len(),
+                    self.import_path.listed_items()
+                ),
+                dim,
+            )]
+        } else {
+            vec![]
+        };
+        if !placeholder.is_empty() {
+            let items = placeholder.into_iter().map(ListItem::new).collect();
+            let list = styled_list("Import Kubernetes Context(s)", items);
+            f.render_stateful_widget(list, area, &mut view_state.list_state);
+            return;
+        }
+        let has_details = view_state
+            .details
+            .values()
+            .any(|d| *d != ClusterDetails::default());
         let items: Vec<ListItem> = view_state
             .get_filtered_options()
             .iter()
-            .map(|opt| ListItem::new(opt.1.clone()))
+            .map(|opt| {
+                if !self.import_path.is_listing_clusters() {
+                    return ListItem::new(opt.1.clone());
+                }
+                let marker = if view_state.selected.contains(opt) {
+                    Span::styled("● ", Style::default().fg(Color::LightBlue))
+                } else if view_state.imported.contains(opt) {
+                    Span::styled("✓ ", Style::default().fg(Color::Green))
+                } else {
+                    Span::raw("  ")
+                };
+                if !has_details {
+                    return ListItem::new(Line::from(vec![marker, Span::raw(opt.1.clone())]));
+                }
+                let details = view_state.details.get(&opt.0).cloned().unwrap_or_default();
+                let column = |value: Option<String>, width: usize| {
+                    let value = value.unwrap_or_else(|| "-".to_string());
+                    pad_to_width(&truncate_to_width(&value, width - 1), width)
+                };
+                let status_style = details
+                    .status
+                    .as_deref()
+                    .map(|status| Style::default().fg(status_color(status)))
+                    .unwrap_or(dim);
+                ListItem::new(Line::from(vec![
+                    marker,
+                    Span::raw(pad_to_width(
+                        &truncate_to_width(&opt.1, NAME_WIDTH - 1),
+                        NAME_WIDTH,
+                    )),
+                    Span::styled(column(details.region, REGION_WIDTH), dim),
+                    Span::styled(column(details.status, STATUS_WIDTH), status_style),
+                    Span::styled(column(details.version, VERSION_WIDTH), dim),
+                    Span::styled(metadata::format_tags(&details.tags), dim),
+                ]))
+            })
             .collect();
-        let list = styled_list("Import Kubernetes Context(s)", items);
+        let total = items.len();
+        let mut title = "Import Kubernetes Context(s)".to_string();
+        if let Some(preset) = &self.preset {
+            title.push_str(&format!(" · preset {}", preset));
+        }
+        if let Some(mode) = view_state
+            .kubelogin_mode
+            .as_ref()
+            .filter(|_| self.import_path.is_azure())
+        {
+            title.push_str(&format!(" · kubelogin {}", mode));
+        }
+        if !view_state.selected.is_empty() {
+            title.push_str(&format!(" · {} selected", view_state.selected.len()));
+        }
+        // The options shown are the ones cached last, fresh or not
+        let listed_at = state
+            .listing_cache
+            .get(&(self.preset.clone(), self.import_path.key()))
+            .map(|(listed_at, _)| *listed_at);
+        if let Some(listed_at) = listed_at {
+            title.push_str(&format!(" · listed {} ago", format_age(listed_at)));
+        }
+        if view_state.sort != ImportSort::Listed {
+            title.push_str(&format!(" · by {}", view_state.sort.label()));
+        }
+        let unusable = view_state
+            .options
+            .iter()
+            .filter(|option| view_state.is_unusable(option))
+            .count();
+        if unusable > 0 && !view_state.show_unusable {
+            title.push_str(&format!(" · {} deleting or failed hidden", unusable));
+        }
+        let list = styled_list(&title, items);
         f.render_stateful_widget(list, area, &mut view_state.list_state);
+        draw_list_position(f, area, &view_state.list_state, total);
     }
 
     async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
         let mut locked_state = self.state.lock().await;
         let view_state = ImportViewState::from_view_state(&mut locked_state);
+        view_state.refresh_imported(&self.import_path, state);
         match event {
             KtxEvent::TerminalEvent(evt) => self.handle_keyboard(evt, state, view_state).await,
             _ => self.handle_app_event(event, state, view_state).await,
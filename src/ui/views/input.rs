@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::ViewState,
+    AppView, KtxEvent,
+};
+
+use super::utils::{action_style, centered_rect, key_style};
+
+pub type OnSubmit = Box<dyn Fn(String) -> KtxEvent + Send + Sync>;
+
+pub struct InputDialogView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    title: String,
+    prompt: String,
+    on_submit: OnSubmit,
+    state: Arc<Mutex<ViewState>>,
+}
+
+#[derive(Clone)]
+pub struct InputDialogViewState {
+    pub value: String,
+}
+
+impl InputDialogView {
+    pub fn new(
+        event_bus_tx: mpsc::Sender<KtxEvent>,
+        title: String,
+        prompt: String,
+        initial_value: String,
+        on_submit: OnSubmit,
+    ) -> Self {
+        Self {
+            event_bus_tx,
+            title,
+            prompt,
+            on_submit,
+            state: Arc::new(Mutex::new(ViewState::InputDialogView(
+                InputDialogViewState {
+                    value: initial_value,
+                },
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl<B> AppView<B> for InputDialogView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("Enter"),
+            action_style(" - submit, "),
+            key_style("Esc"),
+            action_style(" - cancel"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let state = InputDialogViewState::from_view_state(view_state);
+        let dialog = centered_rect(area, ((area.width as f32 * 0.6) as u16).max(40), 10);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(dialog);
+
+        let prompt = Paragraph::new(self.prompt.as_str())
+            .block(
+                Block::default()
+                    .title(self.title.as_str())
+                    .borders(Borders::ALL)
+                    .padding(Padding::new(1, 1, 0, 0)),
+            )
+            .wrap(Wrap { trim: false });
+        let input = Paragraph::new(state.value.as_str())
+            .style(Style::default().fg(Color::Yellow))
+            .block(Block::default().borders(Borders::ALL));
+
+        f.render_widget(Clear, dialog);
+        f.render_widget(prompt, layout[0]);
+        f.render_widget(input, layout[1]);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, _state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = InputDialogViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => match evt {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                }) => {
+                    let _ = self
+                        .event_bus_tx
+                        .send((self.on_submit)(view_state.value.clone()))
+                        .await;
+                    let _ = self.event_bus_tx.send(KtxEvent::DialogConfirm).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                }) => {
+                    let _ = self.event_bus_tx.send(KtxEvent::DialogReject).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Backspace,
+                    ..
+                }) => {
+                    view_state.value.pop();
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(c),
+                    ..
+                }) => {
+                    view_state.value.push(c);
+                }
+                Event::Paste(text) => {
+                    view_state.value.push_str(text.trim());
+                }
+                _ => {
+                    return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                }
+            },
+            _ => {
+                return Ok(Some(event));
+            }
+        };
+        Ok(None)
+    }
+}
@@ -1,21 +1,26 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use kube::config::NamedContext;
 use tokio::sync::{mpsc, Mutex};
 use tui::{
     backend::Backend,
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, ListItem, ListState, Padding, Paragraph, Wrap},
     Frame,
 };
 
+use crate::cluster::{self, FailureKind};
+use crate::config::{IconMode, QuickSwitch};
+use crate::session::ViewMemory;
+use crate::ui::views::detail::render_details;
 use crate::ui::views::utils::{
-    action_style, handle_list_navigation_event, handle_list_navigation_keyboard_event, key_style,
-    styled_list,
+    action_style, draw_list_position, format_age, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, jump_index, key_style, styled_list, truncate_to_width,
 };
 use crate::ui::{
     app::HandleEventResult,
@@ -26,10 +31,47 @@ use crate::ui::{
     types::CloudImportPath,
 };
 
+#[derive(Clone)]
 pub struct ContextListViewState {
     pub list_state: ListState,
     pub remembered_g: bool,
+    pub pending_count: usize,
     pub filter: String,
+    // Filter that was dismissed with Esc, n/N keep jumping between its matches
+    pub last_filter: String,
+    pub split_pane: bool,
+    // The selection follows this context when refreshes, imports or the filter change the list
+    pub selected_name: Option<String>,
+}
+
+impl ContextListViewState {
+    fn follow_selection(&mut self, contexts: &[(NamedContext, KubeContextStatus)]) {
+        let index = self
+            .selected_name
+            .as_ref()
+            .and_then(|name| contexts.iter().position(|(c, _)| &c.name == name));
+        match index {
+            Some(index) => self.list_state.select(Some(index)),
+            // Gone or filtered out, whatever ends up highlighted is followed from now on
+            None => {
+                let clamped = self
+                    .list_state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(contexts.len().saturating_sub(1));
+                self.list_state.select(Some(clamped));
+                self.track_selection(contexts);
+            }
+        }
+    }
+
+    fn track_selection(&mut self, contexts: &[(NamedContext, KubeContextStatus)]) {
+        self.selected_name = self
+            .list_state
+            .selected()
+            .and_then(|i| contexts.get(i))
+            .map(|(c, _)| c.name.clone());
+    }
 }
 
 pub struct ContextListView {
@@ -38,13 +80,20 @@ pub struct ContextListView {
 }
 
 const STATUS_PADDING: usize = 10;
+// Narrower panes get no details pane and only the status next to names
+const SPLIT_PANE_MIN_WIDTH: u16 = 80;
+const ROW_DETAILS_MIN_WIDTH: u16 = 60;
 
 impl ContextListView {
-    pub fn new<B: Backend>(event_bus_tx: mpsc::Sender<KtxEvent>) -> Self {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, split_pane: bool) -> Self {
         let mut state = ContextListViewState {
             list_state: ListState::default(),
             remembered_g: false,
+            pending_count: 0,
             filter: "".to_string(),
+            last_filter: "".to_string(),
+            split_pane,
+            selected_name: None,
         };
         state.list_state.select(Some(0));
         Self {
@@ -65,10 +114,29 @@ impl ContextListView {
     ) -> HandleEventResult {
         let list_state = &view_state.list_state;
         let filtered_contexts = state.get_filtered_contexts(view_state.filter.as_str());
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char(c @ '1'..='9'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) = event
+        {
+            if state.config.quick_switch != QuickSwitch::Off {
+                let index = c.to_digit(10).unwrap() as usize - 1;
+                match state.quick_switch_targets().get(index) {
+                    Some(name) => self.send_event(KtxEvent::SwitchContext(name.clone())).await,
+                    None => {
+                        self.send_event(KtxEvent::PushInfoMessage(format!("Nothing on {} yet", c)))
+                            .await
+                    }
+                }
+                return Ok(None);
+            }
+        }
         if let Some(event) = handle_list_navigation_keyboard_event(
             event,
             self.event_bus_tx.clone(),
             &mut view_state.remembered_g,
+            &mut view_state.pending_count,
         )
         .await?
         {
@@ -81,7 +149,14 @@ impl ContextListView {
                         .0
                         .name
                         .clone();
-                    self.send_event(KtxEvent::SetContext(name)).await;
+                    self.send_event(KtxEvent::SwitchContext(name)).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                }) if !view_state.filter.is_empty() => {
+                    // Drop the filter, the selection stays on the same context in the full list
+                    view_state.last_filter = std::mem::take(&mut view_state.filter);
+                    view_state.follow_selection(&state.get_filtered_contexts(""));
                 }
                 Event::Key(KeyEvent {
                     code: KeyCode::Esc | KeyCode::Char('q'),
@@ -89,6 +164,42 @@ impl ContextListView {
                 }) => {
                     self.send_event(KtxEvent::PopView).await;
                 }
+                Event::Key(KeyEvent {
+                    code: code @ (KeyCode::Char('n') | KeyCode::Char('N')),
+                    ..
+                }) if view_state.filter.is_empty() && !view_state.last_filter.is_empty() => {
+                    let pattern = view_state.last_filter.to_lowercase();
+                    let matches: Vec<usize> = state
+                        .get_filtered_contexts("")
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, c)| c.0.name.to_lowercase().contains(&pattern))
+                        .map(|(i, _)| i)
+                        .collect();
+                    let current = list_state.selected().unwrap_or(0);
+                    let next = if code == KeyCode::Char('n') {
+                        matches
+                            .iter()
+                            .find(|i| **i > current)
+                            .or_else(|| matches.first())
+                    } else {
+                        matches
+                            .iter()
+                            .rev()
+                            .find(|i| **i < current)
+                            .or_else(|| matches.last())
+                    };
+                    match next {
+                        Some(i) => view_state.list_state.select(Some(*i)),
+                        None => {
+                            self.send_event(KtxEvent::PushInfoMessage(format!(
+                                "No contexts match {}",
+                                view_state.last_filter
+                            )))
+                            .await
+                        }
+                    }
+                }
                 Event::Key(KeyEvent {
                     code: KeyCode::Char('d'),
                     ..
@@ -102,12 +213,204 @@ impl ContextListView {
                         ))
                         .await;
                 }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('v'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::ViewContext(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('b'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::ShowNamespaces(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::OpenShell(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('o'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::OpenConsole(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::CloneContext(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::RenameContext(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('u'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::ShowHistory).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Tab, ..
+                }) => {
+                    self.send_event(KtxEvent::ShowRecentContexts).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('P'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::TogglePin(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('M'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::MoveContext(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('e'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::EditServerUrl(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
                 Event::Key(KeyEvent {
                     code: KeyCode::Char('t'),
                     ..
                 }) => {
                     self.send_event(KtxEvent::TestConnections).await;
                 }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('T'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::ShowBenchmark).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('H'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::ShowHealthDashboard).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('p'),
+                    ..
+                }) => {
+                    view_state.split_pane = !view_state.split_pane;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('m'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::MinifyContext(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('F'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::FlattenKubeconfig).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('U'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::NormalizeKubeconfig).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('A'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::ShowAuditLog).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('C'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::ShowEntries).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('S'),
+                    ..
+                }) if list_state.selected().is_some() => {
+                    self.send_event(KtxEvent::CreateServiceAccountToken(
+                        filtered_contexts[list_state.selected().unwrap()]
+                            .0
+                            .name
+                            .clone(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('a'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::AddContext(Default::default()))
+                        .await;
+                }
                 Event::Key(KeyEvent {
                     code: KeyCode::Char('i'),
                     ..
@@ -115,6 +418,46 @@ impl ContextListView {
                     self.send_event(KtxEvent::ShowImportView(CloudImportPath::from(vec![])))
                         .await;
                 }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('I'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::RefreshImportedCredentials).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('X'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::CancelTasks).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('R') | KeyCode::F(5),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::RefreshConfig).await;
+                    self.send_event(KtxEvent::PushInfoMessage(
+                        "Reloading the kubeconfig".to_string(),
+                    ))
+                    .await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('\''),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::EnterJumpMode).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('D'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::DeleteExpiredContexts).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('E'),
+                    ..
+                }) => {
+                    self.send_event(KtxEvent::DeleteEphemeralContexts).await;
+                }
                 _ => {
                     view_state.remembered_g = false;
                     return Ok(Some(KtxEvent::TerminalEvent(event)));
@@ -132,7 +475,8 @@ impl ContextListView {
     ) -> HandleEventResult {
         let filtered_contexts = state.get_filtered_contexts(view_state.filter.as_str());
         let list_state = &mut view_state.list_state;
-        handle_list_navigation_event(event, list_state, filtered_contexts.len()).await
+        handle_list_navigation_event(event, list_state, filtered_contexts.len(), &state.config)
+            .await
     }
 
     fn render_context(
@@ -140,34 +484,192 @@ impl ContextListView {
         c: &(NamedContext, KubeContextStatus),
         state: &AppState,
         area: &Rect,
-    ) -> ListItem {
-        let title = if state.is_current_context(&c.0) {
+        shortcuts: &[String],
+        is_ephemeral: bool,
+    ) -> ListItem<'_> {
+        let expiring_cert = state
+            .cert_expiry
+            .get(&c.0.name)
+            .filter(|expiry| cluster::cert_expires_soon(expiry));
+        let status = match (&c.1, expiring_cert) {
+            (KubeContextStatus::Healthy(v), Some(expiry)) => Span::styled(
+                format!(
+                    "Healthy ({}, cert {})",
+                    v,
+                    cluster::format_cert_expiry(expiry)
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+            (KubeContextStatus::Healthy(v), None) => Span::styled(
+                format!("Healthy ({})", v),
+                Style::default().fg(Color::Green),
+            ),
+            // Network, TLS and credential failures need different fixes
+            (KubeContextStatus::Unhealthy, _) => Span::styled(
+                state
+                    .health_failures
+                    .get(&c.0.name)
+                    .map(|error| FailureKind::classify(error).label())
+                    .unwrap_or("Unhealthy"),
+                Style::default().fg(Color::Red),
+            ),
+            (KubeContextStatus::Unknown, _) => {
+                Span::styled("Unknown", Style::default().fg(Color::DarkGray))
+            }
+            (KubeContextStatus::Waiting(secs), _) => Span::styled(
+                format!("Waiting ({}s)", secs),
+                Style::default().fg(Color::Yellow),
+            ),
+        };
+        // How fresh the status is, checks can be minutes apart when polling
+        let checked = match (
+            &c.1,
+            state.health_history.get(&c.0.name).and_then(|h| h.last()),
+        ) {
+            (KubeContextStatus::Healthy(_) | KubeContextStatus::Unhealthy, Some(sample)) => {
+                Span::styled(
+                    format!(" · checked {} ago", format_age(sample.checked_at)),
+                    Style::default().fg(Color::DarkGray),
+                )
+            }
+            _ => Span::raw(""),
+        };
+        let mut icons = self.render_markers(c, state, shortcuts);
+        icons.extend(self.render_icons(c, state));
+        // Which of the merged files the context lives in
+        let source = if state.kubeconfig_sources.is_multi_file() {
+            let path = state.kubeconfig_sources.context_file(&c.0.name);
+            let file = Path::new(path)
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.to_string());
+            if state.kubeconfig_sources.is_read_only(&c.0.name) {
+                Span::styled(
+                    format!("{} (read-only)  ", file),
+                    Style::default().fg(Color::Magenta),
+                )
+            } else {
+                Span::styled(format!("{}  ", file), Style::default().fg(Color::DarkGray))
+            }
+        } else {
+            Span::raw("")
+        };
+        // Same-named EKS clusters in different accounts are common
+        let account = match state.aws_identity(&c.0.name) {
+            Some(identity) => Span::styled(
+                format!("{}  ", identity.label()),
+                Style::default().fg(Color::DarkGray),
+            ),
+            None => Span::raw(""),
+        };
+        let (account, source, checked) = if area.width < ROW_DETAILS_MIN_WIDTH {
+            (Span::raw(""), Span::raw(""), Span::raw(""))
+        } else {
+            (account, source, checked)
+        };
+        let status_width = status.width() + checked.width() + source.width() + account.width();
+        let name = truncate_to_width(
+            &c.0.name,
+            (area.width as usize)
+                .saturating_sub(icons.iter().map(|i| i.width()).sum::<usize>())
+                .saturating_sub(status_width + STATUS_PADDING),
+        );
+        let title = if state.metadata.is_expired(&c.0.name) {
+            Span::styled(
+                name,
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            )
+        } else if state.is_current_context(&c.0) {
             Span::styled(
-                c.0.name.clone(),
+                name,
                 Style::default()
                     .fg(Color::LightBlue)
                     .add_modifier(Modifier::BOLD),
             )
+        } else if is_ephemeral {
+            Span::styled(name, Style::default().add_modifier(Modifier::ITALIC))
         } else {
-            Span::raw(c.0.name.clone())
+            Span::raw(name)
         };
-        let status = match &c.1 {
-            KubeContextStatus::Healthy(v) => Span::styled(
-                format!("Healthy ({})", v),
-                Style::default().fg(Color::Green),
-            ),
-            KubeContextStatus::Unhealthy => {
-                Span::styled("Unhealthy", Style::default().fg(Color::Red))
+        let spacer_length = (area.width as usize)
+            .saturating_sub(icons.iter().map(|i| i.width()).sum::<usize>())
+            .saturating_sub(title.width() + status_width + STATUS_PADDING);
+        let spacer = Span::styled(" ".repeat(spacer_length), Style::default());
+        let mut spans = icons;
+        spans.extend([title, spacer, account, source, status, checked]);
+        ListItem::new(Line::from(spans))
+    }
+
+    // Quick switch digit and pin, padded so names stay aligned while either is in use
+    fn render_markers(
+        &self,
+        c: &(NamedContext, KubeContextStatus),
+        state: &AppState,
+        shortcuts: &[String],
+    ) -> Vec<Span<'static>> {
+        let mut markers = vec![];
+        if state.config.quick_switch != QuickSwitch::Off {
+            markers.push(match shortcuts.iter().position(|n| *n == c.0.name) {
+                Some(i) => Span::styled(format!("{} ", i + 1), Style::default().fg(Color::Cyan)),
+                None => Span::raw("  "),
+            });
+        }
+        if state.metadata.contexts.values().any(|m| m.pinned) {
+            markers.push(if state.metadata.is_pinned(&c.0.name) {
+                Span::styled("* ", Style::default().fg(Color::Yellow))
+            } else {
+                Span::raw("  ")
+            });
+        }
+        markers
+    }
+
+    fn render_icons(
+        &self,
+        c: &(NamedContext, KubeContextStatus),
+        state: &AppState,
+    ) -> Vec<Span<'static>> {
+        let icon_mode = state.config.icons;
+        if icon_mode == IconMode::None {
+            return vec![];
+        }
+        let provider = state.context_provider(&c.0.name);
+        let provider_icon = match (icon_mode, provider.as_deref()) {
+            (IconMode::NerdFont, Some("aws")) => "\u{f270} ",
+            (IconMode::NerdFont, Some("gcp")) => "\u{f1a0} ",
+            (IconMode::NerdFont, Some("azure")) => "\u{f0805} ",
+            (IconMode::NerdFont, _) => "\u{f10fe} ",
+            (_, Some("aws")) => "aws ",
+            (_, Some("gcp")) => "gcp ",
+            (_, Some("azure")) => "az  ",
+            _ => "k8s ",
+        };
+        let health_icon = match (&c.1, icon_mode) {
+            (KubeContextStatus::Healthy(_), IconMode::NerdFont) => {
+                Span::styled("✓ ", Style::default().fg(Color::Green))
             }
-            KubeContextStatus::Unknown => {
-                Span::styled("Unknown", Style::default().fg(Color::DarkGray))
+            (KubeContextStatus::Unhealthy, IconMode::NerdFont) => {
+                Span::styled("✗ ", Style::default().fg(Color::Red))
+            }
+            (KubeContextStatus::Healthy(_), _) => {
+                Span::styled("+ ", Style::default().fg(Color::Green))
+            }
+            (KubeContextStatus::Unhealthy, _) => {
+                Span::styled("x ", Style::default().fg(Color::Red))
+            }
+            (KubeContextStatus::Unknown, _) => {
+                Span::styled("? ", Style::default().fg(Color::DarkGray))
+            }
+            (KubeContextStatus::Waiting(_), _) => {
+                Span::styled("~ ", Style::default().fg(Color::Yellow))
             }
         };
-        let spacer_length = area
-            .width
-            .saturating_sub(title.width() as u16 + status.width() as u16 + STATUS_PADDING as u16);
-        let spacer = Span::styled(" ".repeat(spacer_length as usize), Style::default());
-        ListItem::new(Line::from(vec![title, spacer, status]))
+        vec![
+            Span::styled(provider_icon, Style::default().fg(Color::Yellow)),
+            health_icon,
+        ]
     }
 }
 
@@ -182,7 +684,7 @@ where
 
     async fn update_filter(&self, filter: String) {
         let mut state = self.state.lock().await;
-        let mut state = ContextListViewState::from_view_state(&mut state);
+        let state = ContextListViewState::from_view_state(&mut state);
         state.filter = filter;
     }
 
@@ -192,41 +694,225 @@ where
         state.filter.clone()
     }
 
-    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
-        Paragraph::new(Line::from(vec![
+    async fn jump_to(&self, text: &str, state: &AppState) {
+        let mut view_state = self.state.lock().await;
+        let view_state = ContextListViewState::from_view_state(&mut view_state);
+        let names: Vec<String> = state
+            .get_filtered_contexts(&view_state.filter)
+            .into_iter()
+            .map(|(context, _)| context.name)
+            .collect();
+        if let Some(index) = jump_index(&names, text) {
+            view_state.list_state.select(Some(index));
+            view_state.selected_name = names.get(index).cloned();
+        }
+    }
+
+    fn memory_key(&self) -> Option<String> {
+        Some("contexts".to_string())
+    }
+
+    async fn get_memory(&self, state: &AppState) -> Option<ViewMemory> {
+        let mut view_state = self.state.lock().await;
+        let view_state = ContextListViewState::from_view_state(&mut view_state);
+        let selected = view_state.list_state.selected().and_then(|i| {
+            state
+                .get_filtered_contexts(&view_state.filter)
+                .get(i)
+                .map(|(context, _)| context.name.clone())
+        });
+        Some(ViewMemory {
+            filter: view_state.filter.clone(),
+            selected,
+            offset: view_state.list_state.offset(),
+            sort: None,
+        })
+    }
+
+    // Contexts may have been renamed or deleted since, the list then starts at the top
+    async fn restore_memory(&self, memory: ViewMemory, state: &AppState) {
+        let mut view_state = self.state.lock().await;
+        let view_state = ContextListViewState::from_view_state(&mut view_state);
+        view_state.filter = memory.filter;
+        let contexts = state.get_filtered_contexts(&view_state.filter);
+        let index = memory.selected.and_then(|name| {
+            contexts
+                .iter()
+                .position(|(context, _)| context.name == name)
+        });
+        view_state.list_state.select(Some(index.unwrap_or(0)));
+        view_state.track_selection(&contexts);
+        if index.is_some() {
+            *view_state.list_state.offset_mut() = memory.offset;
+        }
+    }
+
+    fn draw_top_bar(&self, state: &AppState) -> Paragraph<'_> {
+        let mut keys = vec![
             key_style("jk"),
             action_style(" - up/down, "),
             key_style("Enter"),
             action_style(" - select, "),
+            key_style("Tab"),
+            action_style(" - recent, "),
+            key_style("'"),
+            action_style(" - jump, "),
             key_style("Esc"),
             action_style(" - quit, "),
             key_style("t"),
             action_style(" - test, "),
+            key_style("T"),
+            action_style(" - latency, "),
+            key_style("H"),
+            action_style(" - health, "),
+            key_style("p"),
+            action_style(" - details pane, "),
+            key_style("v"),
+            action_style(" - details, "),
+            key_style("b"),
+            action_style(" - namespaces, "),
+            key_style("s"),
+            action_style(" - shell, "),
+            key_style("o"),
+            action_style(" - console, "),
+            key_style("r"),
+            action_style(" - rename, "),
+            key_style("c"),
+            action_style(" - clone, "),
+            key_style("e"),
+            action_style(" - edit server, "),
             key_style("d"),
             action_style(" - delete, "),
             key_style("i"),
-            action_style(" - import"),
-        ]))
+            action_style(" - import, "),
+            key_style("I"),
+            action_style(" - refresh imported, "),
+            key_style("a"),
+            action_style(" - add, "),
+            key_style("S"),
+            action_style(" - service account token, "),
+            key_style("m"),
+            action_style(" - minify, "),
+            key_style("F"),
+            action_style(" - flatten, "),
+            key_style("U"),
+            action_style(" - normalize, "),
+            key_style("C"),
+            action_style(" - clusters/users, "),
+            key_style("A"),
+            action_style(" - audit log, "),
+            key_style("u"),
+            action_style(" - history, "),
+            key_style("P"),
+            action_style(" - pin"),
+        ];
+        if state.kubeconfig_sources.is_multi_file() {
+            keys.extend([
+                action_style(", "),
+                key_style("M"),
+                action_style(" - move to file"),
+            ]);
+        }
+        if state
+            .kubeconfig
+            .contexts
+            .iter()
+            .any(|c| state.metadata.is_expired(&c.name))
+        {
+            keys.extend([
+                action_style(", "),
+                key_style("D"),
+                action_style(" - delete expired"),
+            ]);
+        }
+        if !state.ephemeral_contexts().is_empty() {
+            keys.extend([
+                action_style(", "),
+                key_style("E"),
+                action_style(" - delete ephemeral"),
+            ]);
+        }
+        if state.has_background_tasks() {
+            keys.extend([
+                action_style(", "),
+                key_style("X"),
+                action_style(" - cancel background tasks"),
+            ]);
+        }
+        Paragraph::new(Line::from(keys))
     }
 
     fn draw(&self, f: &mut Frame<B>, area: Rect, state: &AppState, view_state: &mut ViewState) {
         let view_state = ContextListViewState::from_view_state(view_state);
-        let items: Vec<ListItem> = state
-            .get_filtered_contexts(view_state.filter.as_str())
+        let filtered_contexts = state.get_filtered_contexts(view_state.filter.as_str());
+        view_state.follow_selection(&filtered_contexts);
+        let (list_area, details_area) =
+            if view_state.split_pane && area.width >= SPLIT_PANE_MIN_WIDTH {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(area);
+                (panes[0], Some(panes[1]))
+            } else {
+                (area, None)
+            };
+        let shortcuts = state.quick_switch_targets();
+        let ephemeral = state.ephemeral_contexts();
+        let items: Vec<ListItem> = filtered_contexts
             .iter()
-            .map(|c| self.render_context(c, state, &area))
+            .map(|c| {
+                let is_ephemeral = ephemeral.contains(&c.0.name);
+                self.render_context(c, state, &list_area, &shortcuts, is_ephemeral)
+            })
             .collect();
+        let total = items.len();
 
-        let list = styled_list("Kubernetes config contexts", items);
-        f.render_stateful_widget(list, area, &mut view_state.list_state);
+        let title = if ephemeral.is_empty() {
+            "Kubernetes config contexts".to_string()
+        } else {
+            format!(
+                "Kubernetes config contexts · {} ephemeral at the bottom",
+                ephemeral.len()
+            )
+        };
+        let list = styled_list(&title, items);
+        f.render_stateful_widget(list, list_area, &mut view_state.list_state);
+        draw_list_position(f, list_area, &view_state.list_state, total);
+
+        if let Some(details_area) = details_area {
+            let selected = view_state
+                .list_state
+                .selected()
+                .and_then(|i| filtered_contexts.get(i));
+            let lines = match selected {
+                Some(c) => render_details(state, &c.0.name),
+                None => vec![],
+            };
+            let details = Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Details")
+                        .padding(Padding::new(1, 1, 0, 0)),
+                )
+                .wrap(Wrap { trim: false });
+            f.render_widget(details, details_area);
+        }
     }
 
     async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
         let mut locked_state = self.state.lock().await;
         let view_state = ContextListViewState::from_view_state(&mut locked_state);
-        match event {
+        let contexts = state.get_filtered_contexts(&view_state.filter);
+        view_state.follow_selection(&contexts);
+        let selected = view_state.list_state.selected();
+        let result = match event {
             KtxEvent::TerminalEvent(evt) => self.handle_keyboard(evt, state, view_state).await,
             _ => self.handle_app_event(event, state, view_state).await,
+        };
+        if view_state.list_state.selected() != selected {
+            view_state.track_selection(&state.get_filtered_contexts(&view_state.filter));
         }
+        result
     }
 }
@@ -1,5 +1,20 @@
-pub mod list;
-pub mod import;
+pub mod audit;
+pub mod banner;
+pub mod bench;
 pub mod confirmation;
+pub mod conflict;
+pub mod dashboard;
+pub mod detail;
+pub mod entries;
+pub mod history;
+pub mod import;
+pub mod input;
+pub mod list;
+pub mod namespaces;
+pub mod picker;
+pub mod recent;
+pub mod summary;
+pub mod toast;
+pub mod unhealthy;
 
 mod utils;
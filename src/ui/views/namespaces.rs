@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Clear, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::kubeconfig;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, jump_index, key_style, pad_to_width, styled_list,
+    truncate_to_width,
+};
+
+#[derive(Clone)]
+pub struct NamespaceListViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+    pub namespaces: Option<Vec<(String, String)>>,
+    pub filter: String,
+}
+
+impl NamespaceListViewState {
+    fn get_filtered_namespaces(&self) -> Vec<(String, String)> {
+        let mut namespaces = self.namespaces.clone().unwrap_or_default();
+        namespaces.retain(|(name, _)| name.to_lowercase().contains(&self.filter.to_lowercase()));
+        namespaces
+    }
+}
+
+pub struct NamespaceListView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    context_name: String,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl NamespaceListView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, context_name: String) -> Self {
+        let state = NamespaceListViewState {
+            list_state: ListState::default(),
+            remembered_g: false,
+            pending_count: 0,
+            namespaces: None,
+            filter: "".to_string(),
+        };
+        Self {
+            event_bus_tx,
+            context_name,
+            state: Arc::new(Mutex::new(ViewState::NamespaceListView(state))),
+        }
+    }
+}
+
+#[async_trait]
+impl<B> AppView<B> for NamespaceListView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    async fn update_filter(&self, filter: String) {
+        let mut state = self.state.lock().await;
+        let state = NamespaceListViewState::from_view_state(&mut state);
+        state.filter = filter;
+    }
+
+    async fn get_filter(&self) -> String {
+        let mut state = self.state.lock().await;
+        let state = NamespaceListViewState::from_view_state(&mut state);
+        state.filter.clone()
+    }
+
+    async fn jump_to(&self, text: &str, _state: &AppState) {
+        let mut state = self.state.lock().await;
+        let state = NamespaceListViewState::from_view_state(&mut state);
+        let names: Vec<String> = state
+            .get_filtered_namespaces()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        if let Some(index) = jump_index(&names, text) {
+            state.list_state.select(Some(index));
+        }
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("Enter"),
+            action_style(" - switch to namespace, "),
+            key_style("/"),
+            action_style(" - filter, "),
+            key_style("'"),
+            action_style(" - jump, "),
+            key_style("Esc"),
+            action_style(" - close"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = NamespaceListViewState::from_view_state(view_state);
+        let popup_width = (area.width as f32 * 0.6) as u16;
+        let popup_height = (area.height as f32 * 0.8) as u16;
+        let popup = Rect::new(
+            area.x + (area.width - popup_width) / 2,
+            area.y + (area.height - popup_height) / 2,
+            popup_width,
+            popup_height,
+        );
+        let items: Vec<ListItem> = match &view_state.namespaces {
+            None => vec![ListItem::new(Span::styled(
+                "Loading...",
+                Style::default().fg(Color::DarkGray),
+            ))],
+            Some(_) => view_state
+                .get_filtered_namespaces()
+                .into_iter()
+                .map(|(name, phase)| {
+                    let phase_style = if phase == "Active" {
+                        Style::default().fg(Color::Green)
+                    } else {
+                        Style::default().fg(Color::Yellow)
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::raw(pad_to_width(&truncate_to_width(&name, 49), 50)),
+                        Span::styled(phase, phase_style),
+                    ]))
+                })
+                .collect(),
+        };
+        let total = view_state.namespaces.as_ref().map_or(0, |_| items.len());
+        let list = styled_list(
+            format!("Namespaces in {}", self.context_name).as_str(),
+            items,
+        );
+        f.render_widget(Clear, popup);
+        f.render_stateful_widget(list, popup, &mut view_state.list_state);
+        draw_list_position(f, popup, &view_state.list_state, total);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = NamespaceListViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('\''),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::EnterJumpMode).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Enter,
+                            ..
+                        }) => {
+                            let selected = view_state
+                                .list_state
+                                .selected()
+                                .and_then(|i| view_state.get_filtered_namespaces().get(i).cloned());
+                            if let Some((namespace, _)) = selected {
+                                let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                                let _ = self
+                                    .event_bus_tx
+                                    .send(KtxEvent::SetContext((
+                                        self.context_name.clone(),
+                                        Some(namespace),
+                                    )))
+                                    .await;
+                            }
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            KtxEvent::SetNamespaces((context, namespaces)) if context == self.context_name => {
+                // Start on the namespace the context uses now
+                let current = kubeconfig::find_context(&state.kubeconfig, &context)
+                    .and_then(|c| c.context.as_ref())
+                    .and_then(|c| c.namespace.clone())
+                    .unwrap_or_else(|| "default".to_string());
+                let position = namespaces.iter().position(|(name, _)| *name == current);
+                view_state.namespaces = Some(namespaces);
+                view_state.list_state.select(Some(position.unwrap_or(0)));
+                Ok(None)
+            }
+            _ => {
+                let namespaces_len = view_state.get_filtered_namespaces().len();
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    namespaces_len,
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
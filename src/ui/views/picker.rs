@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::kubeconfig::EntryKind;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{action_style, key_style};
+
+#[derive(Clone)]
+pub struct EntryPickerViewState {
+    pub list_state: ListState,
+}
+
+// Popup for pointing a context at another existing cluster or user, starting on the current one
+pub struct EntryPickerView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    context_name: String,
+    kind: EntryKind,
+    names: Vec<String>,
+    current: String,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl EntryPickerView {
+    pub fn new(
+        event_bus_tx: mpsc::Sender<KtxEvent>,
+        context_name: String,
+        kind: EntryKind,
+        names: Vec<String>,
+        current: String,
+    ) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(names.iter().position(|n| *n == current).unwrap_or(0)));
+        Self {
+            event_bus_tx,
+            context_name,
+            kind,
+            names,
+            current,
+            state: Arc::new(Mutex::new(ViewState::EntryPickerView(
+                EntryPickerViewState { list_state },
+            ))),
+        }
+    }
+
+    fn step(&self, list_state: &mut ListState, forward: bool) {
+        let len = self.names.len();
+        let current = list_state.selected().unwrap_or(0);
+        list_state.select(Some(if forward {
+            (current + 1).min(len - 1)
+        } else {
+            current.saturating_sub(1)
+        }));
+    }
+}
+
+#[async_trait]
+impl<B> AppView<B> for EntryPickerView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("Enter"),
+            action_style(" - use, "),
+            key_style("Esc"),
+            action_style(" - close"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = EntryPickerViewState::from_view_state(view_state);
+        let title = format!("Pick a {} for {}", self.kind.label(), self.context_name);
+        let longest = self
+            .names
+            .iter()
+            .map(|n| n.len())
+            .chain([title.len()])
+            .max()
+            .unwrap_or(0) as u16;
+        let width = (longest + 8).max(30).min(area.width);
+        let height = (self.names.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        let items: Vec<ListItem> = self
+            .names
+            .iter()
+            .map(|name| {
+                let style = if *name == self.current {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(name.clone()).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+        f.render_widget(Clear, popup);
+        f.render_stateful_widget(list, popup, &mut view_state.list_state);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, _state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = EntryPickerViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => match evt {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Down | KeyCode::Char('j'),
+                    ..
+                }) => {
+                    self.step(&mut view_state.list_state, true);
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Up | KeyCode::Char('k'),
+                    ..
+                }) => {
+                    self.step(&mut view_state.list_state, false);
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                }) => {
+                    if let Some(name) = view_state
+                        .list_state
+                        .selected()
+                        .and_then(|i| self.names.get(i))
+                    {
+                        let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        let _ = self
+                            .event_bus_tx
+                            .send(KtxEvent::AssignEntry((
+                                self.context_name.clone(),
+                                self.kind,
+                                name.clone(),
+                            )))
+                            .await;
+                    }
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc | KeyCode::Char('q'),
+                    ..
+                }) => {
+                    let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                }
+                _ => return Ok(Some(KtxEvent::TerminalEvent(evt))),
+            },
+            _ => return Ok(Some(event)),
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{action_style, key_style};
+
+#[derive(Clone)]
+pub struct RecentContextsViewState {
+    pub list_state: ListState,
+}
+
+// Alt-tab style popup: the previous context is preselected, so Tab then Enter toggles between
+// the last two
+pub struct RecentContextsView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    names: Vec<String>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl RecentContextsView {
+    pub fn new(event_bus_tx: mpsc::Sender<KtxEvent>, names: Vec<String>) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+        Self {
+            event_bus_tx,
+            names,
+            state: Arc::new(Mutex::new(ViewState::RecentContextsView(
+                RecentContextsViewState { list_state },
+            ))),
+        }
+    }
+
+    fn step(&self, list_state: &mut ListState, forward: bool) {
+        let len = self.names.len();
+        let current = list_state.selected().unwrap_or(0);
+        list_state.select(Some(if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        }));
+    }
+}
+
+#[async_trait]
+impl<B> AppView<B> for RecentContextsView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("Tab, jk"),
+            action_style(" - next/previous, "),
+            key_style("Enter"),
+            action_style(" - switch, "),
+            key_style("Esc"),
+            action_style(" - close"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = RecentContextsViewState::from_view_state(view_state);
+        let longest = self.names.iter().map(|n| n.len()).max().unwrap_or(0) as u16;
+        let width = (longest + 8).max(30).min(area.width);
+        let height = (self.names.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        let items: Vec<ListItem> = self
+            .names
+            .iter()
+            .map(|name| ListItem::new(name.clone()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Recent"))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+        f.render_widget(Clear, popup);
+        f.render_stateful_widget(list, popup, &mut view_state.list_state);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, _state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = RecentContextsViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => match evt {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Tab | KeyCode::Down | KeyCode::Char('j'),
+                    ..
+                }) => {
+                    self.step(&mut view_state.list_state, true);
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::BackTab | KeyCode::Up | KeyCode::Char('k'),
+                    ..
+                }) => {
+                    self.step(&mut view_state.list_state, false);
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                }) => {
+                    if let Some(name) = view_state
+                        .list_state
+                        .selected()
+                        .and_then(|i| self.names.get(i))
+                    {
+                        let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        let _ = self
+                            .event_bus_tx
+                            .send(KtxEvent::SwitchContext(name.clone()))
+                            .await;
+                    }
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc | KeyCode::Char('q'),
+                    ..
+                }) => {
+                    let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                }
+                _ => return Ok(Some(KtxEvent::TerminalEvent(evt))),
+            },
+            _ => return Ok(Some(event)),
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::cloud::ImportOption;
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::{CloudImportPath, ImportOutcome, KtxEvent, ViewState},
+    AppView,
+};
+
+use super::utils::{
+    action_style, draw_list_position, handle_list_navigation_event,
+    handle_list_navigation_keyboard_event, key_style, pad_to_width, styled_list,
+};
+
+#[derive(Clone)]
+pub struct ImportSummaryViewState {
+    pub list_state: ListState,
+    pub remembered_g: bool,
+    pub pending_count: usize,
+    pub results: Vec<(ImportOption, ImportOutcome)>,
+    // Failed entries marked for retrying
+    pub selected: HashSet<ImportOption>,
+}
+
+pub struct ImportSummaryView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    import_path: CloudImportPath,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl ImportSummaryView {
+    pub fn new(
+        event_bus_tx: mpsc::Sender<KtxEvent>,
+        import_path: CloudImportPath,
+        mut results: Vec<(ImportOption, ImportOutcome)>,
+    ) -> Self {
+        // Failures first, they are what needs attention
+        results.sort_by_key(|(option, outcome)| {
+            let rank = match outcome {
+                ImportOutcome::Failed(_) => 0,
+                ImportOutcome::Imported => 1,
+                ImportOutcome::Skipped => 2,
+            };
+            (rank, option.1.clone())
+        });
+        let mut list_state = ListState::default();
+        if !results.is_empty() {
+            list_state.select(Some(0));
+        }
+        let state = ImportSummaryViewState {
+            list_state,
+            remembered_g: false,
+            pending_count: 0,
+            results,
+            selected: HashSet::new(),
+        };
+        Self {
+            event_bus_tx,
+            import_path,
+            state: Arc::new(Mutex::new(ViewState::ImportSummaryView(state))),
+        }
+    }
+
+    fn title(&self, results: &[(ImportOption, ImportOutcome)]) -> String {
+        let count = |wanted: fn(&ImportOutcome) -> bool| {
+            results
+                .iter()
+                .filter(|(_, outcome)| wanted(outcome))
+                .count()
+        };
+        // Refreshes cover contexts from anywhere and come without a path
+        let source = if self.import_path.is_empty() {
+            String::new()
+        } else {
+            format!(" for {}", self.import_path.display_names().join(" / "))
+        };
+        format!(
+            "Import results{}: {} imported, {} skipped, {} failed",
+            source,
+            count(|o| *o == ImportOutcome::Imported),
+            count(|o| *o == ImportOutcome::Skipped),
+            count(|o| matches!(o, ImportOutcome::Failed(_))),
+        )
+    }
+
+    // Marked failures, or all of them when none is marked
+    fn retry_options(view_state: &ImportSummaryViewState) -> Vec<ImportOption> {
+        let failed = view_state
+            .results
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, ImportOutcome::Failed(_)))
+            .map(|(option, _)| option.clone());
+        if view_state.selected.is_empty() {
+            failed.collect()
+        } else {
+            failed
+                .filter(|option| view_state.selected.contains(option))
+                .collect()
+        }
+    }
+}
+
+fn render_result(result: &(ImportOption, ImportOutcome), selected: bool) -> ListItem<'static> {
+    let (option, outcome) = result;
+    let (mark, label, color) = match outcome {
+        ImportOutcome::Imported => ("✓ ", "imported", Color::Green),
+        ImportOutcome::Skipped => ("- ", "skipped", Color::DarkGray),
+        ImportOutcome::Failed(_) if selected => ("● ", "failed", Color::LightBlue),
+        ImportOutcome::Failed(_) => ("✗ ", "failed", Color::Red),
+    };
+    ListItem::new(Line::from(vec![
+        Span::styled(mark, Style::default().fg(color)),
+        Span::raw(pad_to_width(&option.1, 50)),
+        Span::styled(label, Style::default().fg(color)),
+    ]))
+}
+
+#[async_trait]
+impl<B> AppView<B> for ImportSummaryView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        Paragraph::new(Line::from(vec![
+            key_style("jk"),
+            action_style(" - up/down, "),
+            key_style("Space"),
+            action_style(" - mark failed, "),
+            key_style("r"),
+            action_style(" - retry failed, "),
+            key_style("Esc"),
+            action_style(" - back"),
+        ]))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let view_state = ImportSummaryViewState::from_view_state(view_state);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(6)].as_ref())
+            .split(area);
+        let items: Vec<ListItem> = view_state
+            .results
+            .iter()
+            .map(|result| render_result(result, view_state.selected.contains(&result.0)))
+            .collect();
+        let total = items.len();
+        let mut title = self.title(&view_state.results);
+        if !view_state.selected.is_empty() {
+            title.push_str(&format!(" · {} marked", view_state.selected.len()));
+        }
+        let list = styled_list(&title, items);
+        f.render_stateful_widget(list, layout[0], &mut view_state.list_state);
+        draw_list_position(f, layout[0], &view_state.list_state, total);
+
+        let details = match view_state
+            .list_state
+            .selected()
+            .and_then(|i| view_state.results.get(i))
+        {
+            Some((_, ImportOutcome::Failed(error))) => {
+                Span::styled(error.clone(), Style::default().fg(Color::Red))
+            }
+            Some((_, ImportOutcome::Skipped)) => Span::raw("Already in the kubeconfig"),
+            Some((_, ImportOutcome::Imported)) => Span::raw("Merged into the kubeconfig"),
+            None => Span::raw(""),
+        };
+        let paragraph = Paragraph::new(Line::from(details))
+            .block(Block::default().borders(Borders::ALL).title("Details"))
+            .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, layout[1]);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = ImportSummaryViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => {
+                if let Some(evt) = handle_list_navigation_keyboard_event(
+                    evt,
+                    self.event_bus_tx.clone(),
+                    &mut view_state.remembered_g,
+                    &mut view_state.pending_count,
+                )
+                .await?
+                {
+                    match evt {
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Esc | KeyCode::Char('q'),
+                            ..
+                        }) => {
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char(' '),
+                            ..
+                        }) => {
+                            if let Some(i) = view_state.list_state.selected() {
+                                if let Some((option, ImportOutcome::Failed(_))) =
+                                    view_state.results.get(i)
+                                {
+                                    if !view_state.selected.remove(option) {
+                                        view_state.selected.insert(option.clone());
+                                    }
+                                }
+                                let len = view_state.results.len();
+                                view_state.list_state.select(Some((i + 1).min(len - 1)));
+                            }
+                        }
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('r'),
+                            ..
+                        }) => {
+                            let options = Self::retry_options(view_state);
+                            if options.is_empty() {
+                                return Ok(Some(KtxEvent::PushInfoMessage(
+                                    "No failed imports to retry".to_string(),
+                                )));
+                            }
+                            // Retried by the import view underneath, with the options it has
+                            let _ = self.event_bus_tx.send(KtxEvent::PopView).await;
+                            let _ = self
+                                .event_bus_tx
+                                .send(KtxEvent::RetryImports((self.import_path.clone(), options)))
+                                .await;
+                        }
+                        _ => {
+                            view_state.remembered_g = false;
+                            return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            _ => {
+                let results_len = view_state.results.len();
+                handle_list_navigation_event(
+                    event,
+                    &mut view_state.list_state,
+                    results_len,
+                    &state.config,
+                )
+                .await
+            }
+        }
+    }
+}
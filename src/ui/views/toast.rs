@@ -0,0 +1,61 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::ui::{app::AppState, types::MessageLevel};
+
+use super::utils::truncate_to_width;
+
+// Stacks the messages still on screen in the bottom right corner of the view, newest at the bottom
+pub fn render_toasts<B: Backend>(f: &mut Frame<B>, area: Rect, state: &AppState) {
+    let now = chrono::Utc::now();
+    let messages: Vec<_> = state
+        .messages
+        .iter()
+        .filter(|m| m.expires_at > now)
+        .collect();
+    let max_width = (area.width as usize * 2 / 3)
+        .max(20)
+        .min(area.width as usize);
+    let visible = messages.len().min(area.height as usize);
+    for (row, message) in messages[messages.len() - visible..].iter().enumerate() {
+        let color = match message.level {
+            MessageLevel::Error => Color::Red,
+            MessageLevel::Info => Color::Gray,
+            MessageLevel::Success => Color::Green,
+        };
+        let hint = message
+            .hint()
+            .map(|hint| format!("  {}", hint))
+            .unwrap_or_default();
+        // Only the first line, long messages are cut off
+        let text = message.text.lines().next().unwrap_or_default();
+        let text = match &message.context {
+            Some(context) if !text.contains(context.as_str()) => format!("{}: {}", context, text),
+            _ => text.to_string(),
+        };
+        let text = truncate_to_width(&text, max_width.saturating_sub(3 + hint.width()));
+        let width = (text.width() + hint.width() + 3) as u16;
+        let toast = Rect::new(
+            area.x + area.width - width,
+            area.y + area.height - (visible - row) as u16,
+            width,
+            1,
+        );
+        f.render_widget(Clear, toast);
+        f.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled("▌ ", Style::default().fg(color)),
+                Span::styled(text, Style::default().fg(color)),
+                Span::styled(hint, Style::default().fg(Color::DarkGray)),
+            ])),
+            toast,
+        );
+    }
+}
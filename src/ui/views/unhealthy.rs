@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tokio::sync::{mpsc, Mutex};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::cluster::{self, FailureKind};
+use crate::ui::{
+    app::{AppState, HandleEventResult},
+    types::ViewState,
+    AppView, KtxEvent,
+};
+
+use super::utils::{action_style, centered_rect, key_style, styled_button};
+
+#[derive(Clone)]
+pub struct UnhealthySwitchDialogViewState {
+    pub selection: Option<usize>,
+}
+
+// Asked before switching to a context whose last health check failed
+pub struct UnhealthySwitchDialogView {
+    event_bus_tx: mpsc::Sender<KtxEvent>,
+    name: String,
+    kind: FailureKind,
+    content: String,
+    // Switching anyway, re-testing and re-running the import, the last only for imported contexts
+    actions: Vec<(&'static str, KtxEvent)>,
+    state: Arc<Mutex<ViewState>>,
+}
+
+impl UnhealthySwitchDialogView {
+    pub fn new(
+        event_bus_tx: mpsc::Sender<KtxEvent>,
+        name: String,
+        error: String,
+        switch_event: KtxEvent,
+        can_reauth: bool,
+    ) -> Self {
+        let kind = FailureKind::classify(&error);
+        let mut actions = vec![
+            ("Switch anyway", switch_event),
+            ("Re-test", KtxEvent::RetestSwitch(name.clone())),
+        ];
+        if can_reauth {
+            actions.push(("Re-auth", KtxEvent::ReauthContext(name.clone())));
+        }
+        // Suggest the fix that matches the failure
+        let selection = match kind {
+            FailureKind::AuthExpired | FailureKind::InteractiveAuth if can_reauth => 2,
+            _ => 1,
+        };
+        let details = match cluster::split_dns_details(&error) {
+            (error, Some(dns)) => format!("{}\n\nDNS: {}", error, dns),
+            (error, None) => error.to_string(),
+        };
+        let content = format!(
+            "The last check of {} failed:\n\n{}\n\nSwitch anyway, test it again or re-run its import first?",
+            name, details
+        );
+        Self {
+            event_bus_tx,
+            name,
+            kind,
+            content,
+            actions,
+            state: Arc::new(Mutex::new(ViewState::UnhealthySwitchDialogView(
+                UnhealthySwitchDialogViewState {
+                    selection: Some(selection),
+                },
+            ))),
+        }
+    }
+
+    async fn choose(&self, index: usize) {
+        if let Some((_, event)) = self.actions.get(index) {
+            let _ = self.event_bus_tx.send(KtxEvent::DialogConfirm).await;
+            let _ = self.event_bus_tx.send(event.clone()).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<B> AppView<B> for UnhealthySwitchDialogView
+where
+    B: Backend + Sync + Send,
+{
+    fn get_state_mutex(&self) -> Arc<Mutex<ViewState>> {
+        self.state.clone()
+    }
+
+    fn draw_top_bar(&self, _state: &AppState) -> Paragraph<'_> {
+        let mut spans = vec![
+            key_style("s"),
+            action_style(" - switch anyway, "),
+            key_style("r"),
+            action_style(" - re-test, "),
+        ];
+        if self.actions.len() > 2 {
+            spans.push(key_style("a"));
+            spans.push(action_style(" - re-auth, "));
+        }
+        spans.push(key_style("Esc"));
+        spans.push(action_style(" - cancel"));
+        Paragraph::new(Line::from(spans))
+    }
+
+    fn draw(&self, f: &mut Frame<B>, area: Rect, _state: &AppState, view_state: &mut ViewState) {
+        let state = UnhealthySwitchDialogViewState::from_view_state(view_state);
+        let dialog = centered_rect(
+            area,
+            ((area.width as f32 * 0.5) as u16).max(56),
+            ((area.height as f32 * 0.5) as u16).max(10),
+        );
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(dialog);
+
+        let mut spans = vec![];
+        for (index, (label, _)) in self.actions.iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::styled("          ", Style::default()));
+            }
+            spans.push(styled_button(label, state.selection == Some(index)));
+        }
+        let buttons = Paragraph::new(Line::from(spans))
+            .block(Block::default().borders(Borders::ALL))
+            .alignment(tui::layout::Alignment::Center);
+
+        let content = Paragraph::new(self.content.as_str())
+            .block(
+                Block::default()
+                    .title(format!("{}: {}", self.kind.label(), self.name))
+                    .borders(Borders::ALL)
+                    .padding(Padding::new(1, 1, 1, 1)),
+            )
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(Clear, dialog);
+        f.render_widget(content, layout[0]);
+        f.render_widget(buttons, layout[1]);
+    }
+
+    async fn handle_event(&self, event: KtxEvent, _state: &AppState) -> HandleEventResult {
+        let mut locked_state = self.state.lock().await;
+        let view_state = UnhealthySwitchDialogViewState::from_view_state(&mut locked_state);
+        match event {
+            KtxEvent::TerminalEvent(evt) => match evt {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    ..
+                }) => {
+                    self.choose(0).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) => {
+                    self.choose(1).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('a'),
+                    ..
+                }) => {
+                    self.choose(2).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc | KeyCode::Char('q'),
+                    ..
+                }) => {
+                    let _ = self.event_bus_tx.send(KtxEvent::DialogReject).await;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Left | KeyCode::Char('h'),
+                    ..
+                }) => {
+                    view_state.selection = match view_state.selection {
+                        Some(i) if i > 0 => Some(i - 1),
+                        _ => Some(self.actions.len() - 1),
+                    };
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right | KeyCode::Char('l'),
+                    ..
+                }) => {
+                    view_state.selection = match view_state.selection {
+                        Some(i) if i + 1 < self.actions.len() => Some(i + 1),
+                        _ => Some(0),
+                    };
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                }) if view_state.selection.is_some() => {
+                    self.choose(view_state.selection.unwrap()).await;
+                }
+                _ => {
+                    return Ok(Some(KtxEvent::TerminalEvent(evt)));
+                }
+            },
+            _ => {
+                return Ok(Some(event));
+            }
+        };
+        Ok(None)
+    }
+}
@@ -3,11 +3,18 @@ use std::error::Error;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use tokio::sync::mpsc;
 use tui::{
+    backend::Backend,
+    layout::Rect,
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, ListState},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
 };
 
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::KtxConfig;
 use crate::ui::{app::HandleEventResult, KtxEvent};
 
 pub fn key_style(s: &str) -> Span<'static> {
@@ -34,6 +41,59 @@ pub fn styled_button(label: &str, selected: bool) -> Span<'static> {
     Span::styled(label.to_string(), style)
 }
 
+// Shortens a string to fit the given number of terminal columns without splitting graphemes, so
+// wide CJK characters and emoji are accounted for.
+pub fn truncate_to_width(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut width = 0;
+    for grapheme in s.graphemes(true) {
+        // Leave one column for the ellipsis
+        if width + grapheme.width() >= max_width {
+            break;
+        }
+        width += grapheme.width();
+        truncated.push_str(grapheme);
+    }
+    truncated.push('…');
+    truncated
+}
+
+pub fn pad_to_width(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+// Centers a popup in the area, shrinking it when the terminal is too small to fit it
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// Coarse age of a timestamp, e.g. "12s", "5m" or "3h"
+pub fn format_age(since: chrono::DateTime<chrono::Utc>) -> String {
+    format_duration(chrono::Utc::now() - since)
+}
+
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
 pub fn styled_list<'a>(label: &str, items: Vec<ListItem<'a>>) -> List<'a> {
     List::new(items)
         .block(
@@ -49,20 +109,83 @@ pub fn styled_list<'a>(label: &str, items: Vec<ListItem<'a>>) -> List<'a> {
         .highlight_symbol("> ")
 }
 
+// Draws a scrollbar over the right border of a list rendered by styled_list, and an "N of M"
+// indicator on its bottom border. Must be called after the list is rendered so the offset is
+// up to date.
+pub fn draw_list_position<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    list_state: &ListState,
+    total: usize,
+) {
+    let visible = area.height.saturating_sub(2) as usize;
+    if total > visible && visible > 0 && area.width > 0 {
+        let thumb_height = (visible * visible / total).max(1);
+        let max_offset = total - visible;
+        let thumb_top = (visible - thumb_height) * list_state.offset().min(max_offset) / max_offset;
+        let thumb = Paragraph::new(vec![Line::from("█"); thumb_height])
+            .style(Style::default().fg(Color::DarkGray));
+        f.render_widget(
+            thumb,
+            Rect::new(
+                area.x + area.width - 1,
+                area.y + 1 + thumb_top as u16,
+                1,
+                thumb_height as u16,
+            ),
+        );
+    }
+    if let Some(selected) = list_state.selected().filter(|s| *s < total) {
+        let position = format!(" {} of {} ", selected + 1, total);
+        let width = position.len() as u16;
+        if width + 4 <= area.width && area.height > 0 {
+            f.render_widget(
+                Paragraph::new(position),
+                Rect::new(
+                    area.x + area.width - width - 2,
+                    area.y + area.height - 1,
+                    width,
+                    1,
+                ),
+            );
+        }
+    }
+}
+
+// Type-to-jump prefers names starting with the typed text, then names containing it
+pub fn jump_index(names: &[String], text: &str) -> Option<usize> {
+    let text = text.to_lowercase();
+    let names: Vec<String> = names.iter().map(|n| n.to_lowercase()).collect();
+    names
+        .iter()
+        .position(|n| n.starts_with(&text))
+        .or_else(|| names.iter().position(|n| n.contains(&text)))
+}
+
+// Digits typed before a motion are accumulated in `count` and applied to it vim-style, e.g. `5j`
+// moves five items down and `10G` jumps to the tenth item.
 pub async fn handle_list_navigation_keyboard_event(
     event: Event,
     event_bus: mpsc::Sender<KtxEvent>,
     g_mem: &mut bool,
+    count: &mut usize,
 ) -> Result<Option<Event>, Box<dyn Error + Send + Sync>> {
+    let repeat = (*count).max(1);
     match event {
         Event::Key(KeyEvent {
             code, modifiers, ..
         }) => match (code, modifiers) {
+            (KeyCode::Char(c @ '0'..='9'), KeyModifiers::NONE) if c != '0' || *count > 0 => {
+                *count = count
+                    .saturating_mul(10)
+                    .saturating_add(c.to_digit(10).unwrap() as usize);
+                return Ok(None);
+            }
             (KeyCode::Up, _) | (KeyCode::Char('k'), _) => {
-                let _ = event_bus.send(KtxEvent::ListOneUp).await;
+                let _ = event_bus.send(KtxEvent::ListUp(repeat)).await;
             }
             (KeyCode::Down, _) | (KeyCode::Char('j'), _) => {
-                let _ = event_bus.send(KtxEvent::ListOneDown).await;
+                let _ = event_bus.send(KtxEvent::ListDown(repeat)).await;
             }
             (KeyCode::PageUp, _) | (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
                 let _ = event_bus.send(KtxEvent::ListPageUp).await;
@@ -73,18 +196,33 @@ pub async fn handle_list_navigation_keyboard_event(
             (KeyCode::Home, _) | (KeyCode::Char('g'), _) => {
                 if (code == KeyCode::Char('g') && *g_mem) || code == KeyCode::Home {
                     *g_mem = false;
-                    let _ = event_bus.send(KtxEvent::ListTop).await;
+                    let _ = event_bus
+                        .send(if *count > 0 {
+                            KtxEvent::ListSelect(*count - 1)
+                        } else {
+                            KtxEvent::ListTop
+                        })
+                        .await;
                 } else {
                     *g_mem = true;
+                    // Keep the count around for the second g
+                    return Ok(None);
                 }
             }
             (KeyCode::End, _) | (KeyCode::Char('G'), _) => {
-                let _ = event_bus.send(KtxEvent::ListBottom).await;
+                let _ = event_bus
+                    .send(if *count > 0 {
+                        KtxEvent::ListSelect(*count - 1)
+                    } else {
+                        KtxEvent::ListBottom
+                    })
+                    .await;
             }
             (KeyCode::Char('/'), _) => {
                 let _ = event_bus.send(KtxEvent::EnterFilterMode).await;
             }
             _ => {
+                *count = 0;
                 return Ok(Some(event));
             }
         },
@@ -92,6 +230,7 @@ pub async fn handle_list_navigation_keyboard_event(
             return Ok(Some(event));
         }
     };
+    *count = 0;
     Ok(None)
 }
 
@@ -99,6 +238,7 @@ pub async fn handle_list_navigation_event(
     event: KtxEvent,
     list_state: &mut ListState,
     max_len: usize,
+    config: &KtxConfig,
 ) -> HandleEventResult {
     // Hack: fixup list state if it's out of bounds
     if let Some(current_selection) = list_state.selected() {
@@ -108,26 +248,31 @@ pub async fn handle_list_navigation_event(
     }
     match event {
         KtxEvent::ListSelect(pos) => {
-            list_state.select(Some(pos));
+            list_state.select(Some(usize::min(pos, max_len.saturating_sub(1))));
         }
-        KtxEvent::ListOneUp => {
+        KtxEvent::ListUp(n) => {
             if let Some(current_selection) = list_state.selected() {
-                if current_selection > 0 {
-                    list_state.select(Some(current_selection - 1));
+                if config.wrap_around && current_selection == 0 {
+                    list_state.select(Some(max_len.saturating_sub(1)));
+                } else {
+                    list_state.select(Some(current_selection.saturating_sub(n)));
                 }
             }
         }
-        KtxEvent::ListOneDown => {
+        KtxEvent::ListDown(n) => {
             if let Some(current_selection) = list_state.selected() {
-                if current_selection < max_len - 1 {
-                    list_state.select(Some(current_selection + 1));
+                if config.wrap_around && current_selection + 1 >= max_len {
+                    list_state.select(Some(0));
+                } else {
+                    let new_selection = current_selection.saturating_add(n);
+                    list_state.select(Some(usize::min(new_selection, max_len.saturating_sub(1))));
                 }
             }
         }
         KtxEvent::ListPageUp => {
             if let Some(current_selection) = list_state.selected() {
                 if current_selection > 0 {
-                    let new_selection = current_selection.saturating_sub(10);
+                    let new_selection = current_selection.saturating_sub(config.page_size);
                     list_state.select(Some(new_selection));
                 }
             }
@@ -135,7 +280,8 @@ pub async fn handle_list_navigation_event(
         KtxEvent::ListPageDown => {
             if let Some(current_selection) = list_state.selected() {
                 if current_selection < max_len - 1 {
-                    let new_selection = usize::min(current_selection + 10, max_len - 1);
+                    let new_selection =
+                        usize::min(current_selection + config.page_size, max_len - 1);
                     list_state.select(Some(new_selection));
                 }
             }